@@ -56,7 +56,7 @@ fn draw(canvas: &mut Canvas, position: &Point) {
 
 fn save(canvas: &Canvas, path: &Path) {
     let ppm = canvas.ppm();
-    if let Err(error) = write(path, &ppm.as_bytes()) {
+    if let Err(error) = write(path, ppm.as_bytes()) {
         panic!("failed to write to {}: {}", path.display(), error);
     }
 }
@@ -19,7 +19,7 @@ fn main() {
     let ppm = canvas.ppm();
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     path.push("examples/chapter_04/output.ppm");
-    if let Err(error) = write(&path, &ppm.as_bytes()) {
+    if let Err(error) = write(&path, ppm.as_bytes()) {
         panic!("failed to write to {}: {}", path.display(), error);
     }
 }
@@ -0,0 +1,176 @@
+use crate::{
+    util::{cosine_hemisphere, Rng},
+    Camera, Canvas, Color, Computations, Intersection, World,
+};
+
+/// Integrator rendering how exposed surfaces are to ambient light.
+///
+/// Rays are shot from the surface seen through the center of each pixel in
+/// directions distributed proportionally to their cosine with the normal.
+/// The fraction of the rays not hitting any shape within the maximum
+/// distance is written as a gray level: white for fully exposed surfaces (and
+/// pixels seeing no shape), black for fully occluded ones.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AmbientOcclusion {
+    samples: usize,
+    max_distance: f64,
+    seed: u64,
+}
+
+impl AmbientOcclusion {
+    const SAMPLES: usize = 16;
+
+    /// Creates an `AmbientOcclusion` integrator with default settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::AmbientOcclusion;
+    /// let occlusion = AmbientOcclusion::new();
+    /// assert_eq!(occlusion.sample_count(), 16);
+    /// assert_eq!(occlusion.max_distance(), f64::INFINITY);
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            samples: Self::SAMPLES,
+            max_distance: f64::INFINITY,
+            seed: 0,
+        }
+    }
+
+    /// Get the number of rays shot from each surface.
+    #[must_use]
+    pub fn sample_count(&self) -> usize {
+        self.samples
+    }
+
+    /// Sets the number of rays shot from each surface.
+    pub fn set_sample_count(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    /// Get the distance beyond which shapes do not occlude surfaces.
+    #[must_use]
+    pub fn max_distance(&self) -> f64 {
+        self.max_distance
+    }
+
+    /// Sets the distance beyond which shapes do not occlude surfaces.
+    pub fn set_max_distance(&mut self, max_distance: f64) {
+        self.max_distance = max_distance;
+    }
+
+    /// Get the seed of the random sequences.
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets the seed of the random sequences, renders with the same seed are
+    /// identical.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Renders the exposure of the surfaces seen by the camera as a
+    /// grayscale canvas.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{AmbientOcclusion, Camera, Color, Matrix, Quadric, World};
+    /// let mut world = World::new();
+    /// world.add_object(Quadric::sphere());
+    /// let mut camera = Camera::new(4, 4, 1.);
+    /// camera.set_transform(Matrix::translation(0., 0., -5.));
+    /// let image = AmbientOcclusion::new().render(&world, &camera);
+    /// // nothing occludes the sphere
+    /// assert!(image.iter().all(|&color| color == Color::white()));
+    /// ```
+    pub fn render(&self, world: &World, camera: &Camera) -> Canvas {
+        let mut image = Canvas::new(camera.hsize(), camera.vsize());
+        for y in 0..camera.vsize() {
+            for x in 0..camera.hsize() {
+                let mut rng = Rng::from_values(&[x as f64, y as f64, self.seed as f64]);
+                let ray = camera.ray_for_pixel(x, y);
+                let intersections = world.intersect(&ray);
+                let exposure = match Intersection::hit(&intersections) {
+                    Some(hit) => {
+                        let comps = Computations::new(&hit, &ray);
+                        let exposed = (0..self.samples)
+                            .filter(|_| {
+                                let direction = cosine_hemisphere(comps.normalv, rng.next_f64(), rng.next_f64());
+                                !world.is_shadowed(comps.over_point, direction, self.max_distance)
+                            })
+                            .count();
+                        exposed as f64 / self.samples as f64
+                    }
+                    None => 1.,
+                };
+                *image.get_mut(x, y) = Color::new(exposure, exposure, exposure);
+            }
+        }
+        image
+    }
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Matrix, Point, Quadric, Shape, Vector};
+
+    use super::*;
+
+    fn camera() -> Camera {
+        let mut camera = Camera::new(1, 1, 0.01);
+        let from = Point::new(0., 0.5, 0.);
+        let to = Point::new(0., 0., 0.);
+        let up = Vector::new(0., 0., 1.);
+        camera.set_transform(Matrix::view_transform(from, to, up));
+        camera
+    }
+
+    #[test]
+    fn settings() {
+        let mut occlusion = AmbientOcclusion::default();
+        occlusion.set_sample_count(0);
+        assert_eq!(occlusion.sample_count(), 1);
+        occlusion.set_max_distance(2.);
+        assert_eq!(occlusion.max_distance(), 2.);
+        occlusion.set_seed(3);
+        assert_eq!(occlusion.seed(), 3);
+    }
+
+    #[test]
+    fn miss() {
+        let image = AmbientOcclusion::new().render(&World::new(), &camera());
+        assert_eq!(image.get(0, 0), &Color::white());
+    }
+
+    #[test]
+    fn ceiling() {
+        // floor under a ceiling at height 1: rays are occluded within the
+        // distance d when cos θ > 1 / d, exposure is 1 / d²
+        let mut world = World::new();
+        world.add_object(Quadric::plane());
+        let mut ceiling = Quadric::plane();
+        ceiling.set_transform(Matrix::translation(0., 1., 0.));
+        world.add_object(ceiling);
+        let mut occlusion = AmbientOcclusion::new();
+        assert_eq!(occlusion.render(&world, &camera()).get(0, 0), &Color::black());
+        occlusion.set_max_distance(0.5);
+        assert_eq!(occlusion.render(&world, &camera()).get(0, 0), &Color::white());
+        occlusion.set_max_distance(2.);
+        occlusion.set_sample_count(4096);
+        let image = occlusion.render(&world, &camera());
+        let color = image.get(0, 0);
+        assert!((color.r - 0.25).abs() < 0.02);
+        assert_eq!(color.r, color.g);
+        assert_eq!(color.r, color.b);
+    }
+}
@@ -0,0 +1,376 @@
+use std::f64::consts::PI;
+
+use crate::{util::Rng, Attenuation, Color, Emitter, Light, LightSample, Point, Vector};
+
+/// Rectangular light source producing soft shadows.
+///
+/// The rectangle is defined by a corner and two edges and is subdivided in
+/// `usteps * vsteps` cells. Each cell is sampled once at a position jittered
+/// within the cell, shadows are then proportional to the number of occluded
+/// samples.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AreaLight {
+    corner: Point,
+    uvec: Vector,
+    usteps: usize,
+    vvec: Vector,
+    vsteps: usize,
+    intensity: Color,
+    attenuation: Attenuation,
+    jitter: bool,
+}
+
+impl AreaLight {
+    /// Creates an `AreaLight` from a corner and two edges subdivided in
+    /// specified number of cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `usteps` or `vsteps` is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{AreaLight, Color, Point, Vector};
+    /// let corner = Point::new(0., 0., 0.);
+    /// let u = Vector::new(2., 0., 0.);
+    /// let v = Vector::new(0., 0., 1.);
+    /// let light = AreaLight::new(corner, u, 4, v, 2, Color::white());
+    /// assert_eq!(light.sample_count(), 8);
+    /// assert_eq!(light.position(), Point::new(1., 0., 0.5));
+    /// ```
+    pub fn new(corner: Point, uvec: Vector, usteps: usize, vvec: Vector, vsteps: usize, intensity: Color) -> Self {
+        assert!(usteps > 0);
+        assert!(vsteps > 0);
+        Self {
+            corner,
+            uvec: uvec / usteps as f64,
+            usteps,
+            vvec: vvec / vsteps as f64,
+            vsteps,
+            intensity,
+            attenuation: Attenuation::none(),
+            jitter: true,
+        }
+    }
+
+    /// Creates a physically based `AreaLight` from its radiant power (watts).
+    ///
+    /// As the other lights, the area light emits its radiant intensity
+    /// `power / 4π` (watts per steradian) in every direction (the cosine of
+    /// the emission angle is ignored), the attenuation is inverse-square.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `usteps` or `vsteps` is `0`.
+    pub fn from_power(
+        corner: Point,
+        uvec: Vector,
+        usteps: usize,
+        vvec: Vector,
+        vsteps: usize,
+        color: Color,
+        power: f64,
+    ) -> Self {
+        let mut light = Self::new(corner, uvec, usteps, vvec, vsteps, color * (power / (4. * PI)));
+        light.attenuation = Attenuation::inverse_square();
+        light
+    }
+
+    /// Returns the number of samples (cells) of the light.
+    pub fn sample_count(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    /// Returns the center of the light.
+    pub fn position(&self) -> Point {
+        self.corner + self.uvec * (self.usteps as f64 / 2.) + self.vvec * (self.vsteps as f64 / 2.)
+    }
+
+    /// Get the intensity of the light.
+    #[must_use]
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    /// Get the attenuation of the intensity with distance.
+    #[must_use]
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    /// Sets the attenuation of the intensity with distance.
+    ///
+    /// Distances are measured from each sample position.
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+
+    /// Enables or disables the jittering of the samples within their cell.
+    ///
+    /// When disabled each cell is sampled at its center.
+    pub fn set_jitter(&mut self, jitter: bool) {
+        self.jitter = jitter;
+    }
+
+    /// Returns the sample of a position on the light as seen from `point`.
+    ///
+    /// Positions are uniformly distributed over the area of the light, the
+    /// density per steradian is `distance² / (area * cos θ)`.
+    fn sample_at(&self, position: Point, point: Point) -> LightSample {
+        let normal = self.uvec.cross(self.vvec);
+        let area = normal.len() * self.sample_count() as f64;
+        let mut sample = LightSample::at(position, point, self.intensity);
+        sample.intensity *= self.attenuation.factor(sample.distance);
+        let cosine = sample.direction.dot(normal.normalized()).abs();
+        sample.pdf = sample.distance * sample.distance / (area * cosine);
+        sample
+    }
+
+    /// Returns the position of the cell `(u, v)` offset by `(du, dv)` in
+    /// `[0,1)` within the cell.
+    fn point_on_light(&self, u: usize, v: usize, du: f64, dv: f64) -> Point {
+        self.corner + self.uvec * (u as f64 + du) + self.vvec * (v as f64 + dv)
+    }
+}
+
+impl Light for AreaLight {
+    /// Jitter is seeded by the illuminated point so that repeated queries
+    /// for the same point return the same samples.
+    fn samples(&self, point: Point) -> Vec<LightSample> {
+        let mut rng = Rng::from_values(&[point.x, point.y, point.z]);
+        let mut samples = Vec::with_capacity(self.sample_count());
+        for v in 0..self.vsteps {
+            for u in 0..self.usteps {
+                let (du, dv) = if self.jitter {
+                    (rng.next_f64(), rng.next_f64())
+                } else {
+                    (0.5, 0.5)
+                };
+                let position = self.point_on_light(u, v, du, dv);
+                samples.push(self.sample_at(position, point));
+            }
+        }
+        samples
+    }
+
+    fn sample_along(&self, point: Point, direction: Vector) -> Option<LightSample> {
+        let uvec = self.uvec * self.usteps as f64;
+        let vvec = self.vvec * self.vsteps as f64;
+        let normal = uvec.cross(vvec);
+        let denominator = direction.dot(normal);
+        if denominator.abs() < f64::EPSILON {
+            return None;
+        }
+        let t = (self.corner - point).dot(normal) / denominator;
+        if t <= 0. {
+            return None;
+        }
+        // coordinates of the intersection relative to the edges
+        let offset = point + direction * t - self.corner;
+        let area = normal.dot(normal);
+        let u = offset.cross(vvec).dot(normal) / area;
+        let v = uvec.cross(offset).dot(normal) / area;
+        if !(0. ..=1.).contains(&u) || !(0. ..=1.).contains(&v) {
+            return None;
+        }
+        Some(self.sample_at(point + direction * t, point))
+    }
+
+    fn emitter(&self) -> Option<Emitter> {
+        let uvec = self.uvec * self.usteps as f64;
+        let vvec = self.vvec * self.vsteps as f64;
+        (self.attenuation == Attenuation::inverse_square())
+            .then(|| Emitter::area(self.corner, uvec, vvec, self.intensity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::float_eq;
+
+    use super::*;
+
+    #[test]
+    fn new() {
+        let corner = Point::new(0., 0., 0.);
+        let light = AreaLight::new(
+            corner,
+            Vector::new(2., 0., 0.),
+            4,
+            Vector::new(0., 0., 1.),
+            2,
+            Color::white(),
+        );
+        assert_eq!(light.corner, corner);
+        assert_eq!(light.uvec, Vector::new(0.5, 0., 0.));
+        assert_eq!(light.usteps, 4);
+        assert_eq!(light.vvec, Vector::new(0., 0., 0.5));
+        assert_eq!(light.vsteps, 2);
+        assert_eq!(light.sample_count(), 8);
+        assert_eq!(light.position(), Point::new(1., 0., 0.5));
+    }
+
+    #[test]
+    fn point_on_light() {
+        let light = AreaLight::new(
+            Point::new(0., 0., 0.),
+            Vector::new(2., 0., 0.),
+            4,
+            Vector::new(0., 0., 1.),
+            2,
+            Color::white(),
+        );
+        assert_eq!(light.point_on_light(0, 0, 0.5, 0.5), Point::new(0.25, 0., 0.25));
+        assert_eq!(light.point_on_light(1, 0, 0.5, 0.5), Point::new(0.75, 0., 0.25));
+        assert_eq!(light.point_on_light(0, 1, 0.5, 0.5), Point::new(0.25, 0., 0.75));
+        assert_eq!(light.point_on_light(2, 0, 0.5, 0.5), Point::new(1.25, 0., 0.25));
+        assert_eq!(light.point_on_light(3, 1, 0.5, 0.5), Point::new(1.75, 0., 0.75));
+    }
+
+    #[test]
+    fn samples_centered() {
+        let mut light = AreaLight::new(
+            Point::new(-1., 2., 0.),
+            Vector::new(2., 0., 0.),
+            2,
+            Vector::new(0., 0., 2.),
+            1,
+            Color::white(),
+        );
+        light.set_jitter(false);
+        let samples = light.samples(Point::new(0., 0., 1.));
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].direction, Vector::new(-0.5, 2., 0.).normalized());
+        assert_eq!(samples[1].direction, Vector::new(0.5, 2., 0.).normalized());
+    }
+
+    #[test]
+    fn samples_jittered() {
+        let light = AreaLight::new(
+            Point::new(0., 1., 0.),
+            Vector::new(1., 0., 0.),
+            4,
+            Vector::new(0., 0., 1.),
+            4,
+            Color::white(),
+        );
+        let point = Point::new(0., 0., 0.);
+        let samples = light.samples(point);
+        assert_eq!(samples.len(), 16);
+        for (i, sample) in samples.iter().enumerate() {
+            let position = point + sample.direction * sample.distance;
+            let (u, v) = ((i % 4) as f64 * 0.25, (i / 4) as f64 * 0.25);
+            assert!(position.x >= u && position.x <= u + 0.25);
+            assert!(position.z >= v && position.z <= v + 0.25);
+        }
+        assert_eq!(samples, light.samples(point));
+        assert_ne!(samples, light.samples(Point::new(0., 0., 0.5)));
+    }
+
+    #[test]
+    fn samples_attenuated() {
+        let mut light = AreaLight::new(
+            Point::new(-1., 2., -1.),
+            Vector::new(2., 0., 0.),
+            1,
+            Vector::new(0., 0., 2.),
+            1,
+            Color::white(),
+        );
+        light.set_jitter(false);
+        light.set_attenuation(Attenuation::inverse_square());
+        let samples = light.samples(Point::new(0., 0., 0.));
+        assert_eq!(samples[0].intensity, Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn samples_pdf() {
+        // 2x2 light seen from 2 units below: density is d² / (4 cos θ)
+        let light = AreaLight::new(
+            Point::new(-1., 2., -1.),
+            Vector::new(2., 0., 0.),
+            2,
+            Vector::new(0., 0., 2.),
+            2,
+            Color::white(),
+        );
+        for sample in light.samples(Point::new(0., 0., 0.)) {
+            let cosine = sample.direction.y;
+            assert!((sample.pdf - sample.distance * sample.distance / (4. * cosine)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn sample_along() {
+        let mut light = AreaLight::new(
+            Point::new(-1., 2., -1.),
+            Vector::new(2., 0., 0.),
+            2,
+            Vector::new(0., 0., 2.),
+            2,
+            Color::white(),
+        );
+        light.set_attenuation(Attenuation::inverse_square());
+        let point = Point::new(0., 0., 0.);
+        let sample = light.sample_along(point, Vector::new(0., 1., 0.)).unwrap();
+        assert_eq!(sample.direction, Vector::new(0., 1., 0.));
+        assert!(float_eq(sample.distance, 2.));
+        assert!(float_eq(sample.pdf, 1.));
+        assert_eq!(sample.intensity, Color::new(0.25, 0.25, 0.25));
+        let corner = Vector::new(0.99, 2., 0.99).normalized();
+        assert!(light.sample_along(point, corner).is_some());
+        let outside = Vector::new(1.01, 2., 0.).normalized();
+        assert!(light.sample_along(point, outside).is_none());
+        assert!(light.sample_along(point, Vector::new(0., -1., 0.)).is_none());
+        assert!(light.sample_along(point, Vector::new(1., 0., 0.)).is_none());
+    }
+
+    #[test]
+    fn from_power() {
+        let light = AreaLight::from_power(
+            Point::new(0., 0., 0.),
+            Vector::new(1., 0., 0.),
+            2,
+            Vector::new(0., 0., 1.),
+            2,
+            Color::white(),
+            4. * PI,
+        );
+        assert_eq!(light.intensity(), Color::white());
+        assert_eq!(light.attenuation(), Attenuation::inverse_square());
+        // radiant intensity integrated over the sphere around the light
+        let mut light = AreaLight::from_power(
+            Point::new(-1., 0., -1.),
+            Vector::new(2., 0., 0.),
+            2,
+            Vector::new(0., 0., 2.),
+            2,
+            Color::white(),
+            100.,
+        );
+        light.set_jitter(false);
+        let steps = 200;
+        let mut power = 0.;
+        for i in 0..steps {
+            let theta = PI * (i as f64 + 0.5) / steps as f64;
+            for j in 0..steps {
+                let phi = 2. * PI * (j as f64 + 0.5) / steps as f64;
+                let direction = Vector::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                let samples = light.samples(Point::new(0., 0., 0.) + direction * 1000.);
+                let intensity = samples
+                    .iter()
+                    .map(|sample| sample.intensity.r * sample.distance * sample.distance)
+                    .sum::<f64>()
+                    / samples.len() as f64;
+                power += intensity * theta.sin() * 2. * PI * PI / (steps * steps) as f64;
+            }
+        }
+        assert!((power - 100.).abs() < 0.1);
+        // the emitter spreads the same power over the area
+        let emitter = light.emitter().unwrap();
+        let (position, pdf) = emitter.sample_position(0.5, 0.5);
+        let normal = emitter.normal().unwrap();
+        assert!(float_eq(emitter.intensity(position, normal).r / pdf * 4. * PI, 100.));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::util::float_eq;
+
+/// Decrease of the intensity of a light with the distance to the light.
+///
+/// The intensity at distance `d` is divided by
+/// `constant + linear * d + quadratic * d²`.
+#[derive(Copy, Clone, Debug)]
+pub struct Attenuation {
+    /// constant term
+    pub constant: f64,
+    /// term proportional to the distance
+    pub linear: f64,
+    /// term proportional to the squared distance
+    pub quadratic: f64,
+}
+
+impl Attenuation {
+    /// Creates an `Attenuation` from specified coefficients.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Attenuation;
+    /// let attenuation = Attenuation::new(1., 0.5, 0.25);
+    /// assert_eq!(attenuation.factor(2.), 1. / 3.);
+    /// ```
+    pub const fn new(constant: f64, linear: f64, quadratic: f64) -> Self {
+        Self {
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    /// Creates the attenuation leaving intensity unchanged.
+    pub const fn none() -> Self {
+        Self::new(1., 0., 0.)
+    }
+
+    /// Creates the physically based inverse-square attenuation.
+    pub const fn inverse_square() -> Self {
+        Self::new(0., 0., 1.)
+    }
+
+    /// Returns the factor applied to the intensity at specified distance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Attenuation;
+    /// assert_eq!(Attenuation::none().factor(10.), 1.);
+    /// assert_eq!(Attenuation::inverse_square().factor(10.), 0.01);
+    /// ```
+    pub fn factor(&self, distance: f64) -> f64 {
+        1. / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+    }
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl PartialEq for Attenuation {
+    fn eq(&self, other: &Self) -> bool {
+        float_eq(self.constant, other.constant)
+            && float_eq(self.linear, other.linear)
+            && float_eq(self.quadratic, other.quadratic)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default() {
+        assert_eq!(Attenuation::default(), Attenuation::new(1., 0., 0.));
+    }
+
+    #[test]
+    fn factor() {
+        let attenuation = Attenuation::none();
+        assert!(float_eq(attenuation.factor(0.), 1.));
+        assert!(float_eq(attenuation.factor(100.), 1.));
+        let attenuation = Attenuation::inverse_square();
+        assert!(float_eq(attenuation.factor(2.), 0.25));
+        assert!(float_eq(attenuation.factor(4.), 0.0625));
+        let attenuation = Attenuation::new(1., 0.1, 0.01);
+        assert!(float_eq(attenuation.factor(10.), 1. / 3.));
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{Material, Matrix, Motion};
+
+/// Transform, motion and material common to every [`Shape`](crate::Shape).
+///
+/// Shapes embed their attributes and expose them through
+/// [`Shape::attributes`](crate::Shape::attributes), the provided methods of
+/// the trait reading and updating them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attributes {
+    pub(crate) transform: Matrix,
+    pub(crate) inverse: Matrix,
+    pub(crate) motion: Option<Motion>,
+    pub(crate) material: Material,
+}
+
+impl Default for Attributes {
+    /// Identity transform, no motion and default material.
+    fn default() -> Self {
+        Self {
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+            motion: None,
+            material: Material::default(),
+        }
+    }
+}
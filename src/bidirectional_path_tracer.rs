@@ -0,0 +1,755 @@
+use std::slice;
+
+use crate::{
+    path_tracer::{self, direct, reached, Scattering},
+    util::Rng,
+    Bsdf, Camera, Canvas, Color, Computations, Emitter, Heuristic, Intersection, Light, Point, Projection, Ray, Vector,
+    World,
+};
+
+/// Bidirectional path tracing integrator computing global illumination.
+///
+/// For each sample a subpath is traced from the camera and another one from
+/// a light of the world chosen uniformly, every vertex of the camera subpath
+/// is then connected to every vertex of the light subpath. The paths obtained
+/// by the different connections are weighted with multiple importance
+/// sampling. Connections to the camera itself (light tracing) land anywhere
+/// on the canvas and are splatted onto the pixel they are seen through.
+///
+/// Only the lights with an [`Emitter`] start light subpaths. Lights without
+/// one (directional lights, the environment and lights not following the
+/// inverse-square law) and emissive shapes are gathered along the camera
+/// subpath as done by the [`PathTracer`](crate::PathTracer).
+///
+/// Participating media are not supported: the shapes bounding a
+/// [`Medium`](crate::Medium) are rendered as surfaces. Light subpaths are
+/// only connected to perspective cameras, other projections are rendered
+/// from the camera subpaths alone. The aperture and shutter interval of the
+/// camera are ignored: moving shapes are rendered at time `0`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BidirectionalPathTracer {
+    samples: usize,
+    max_depth: usize,
+    roulette_depth: usize,
+    heuristic: Heuristic,
+    seed: u64,
+}
+
+/// Vertex of a subpath.
+#[derive(Copy, Clone, Debug)]
+struct Vertex {
+    kind: Kind,
+    /// position of the vertex (above the surface for surface vertices)
+    point: Point,
+    /// normal of surfaces (on the side of `wo`) and of area emitters
+    normal: Option<Vector>,
+    /// unit vector towards the previous vertex of the subpath
+    wo: Vector,
+    /// contribution of the subpath up to the vertex divided by its density
+    beta: Color,
+    /// density (per unit area) of sampling the vertex from the previous one
+    pdf_fwd: f64,
+    /// density (per unit area) of sampling the vertex from the next one
+    pdf_rev: f64,
+}
+
+/// Kind of a [`Vertex`].
+#[derive(Copy, Clone, Debug)]
+enum Kind {
+    Camera,
+    Light(Emitter),
+    Surface(Bsdf),
+}
+
+impl Vertex {
+    /// Returns the fraction of the light arriving from `wi` scattered
+    /// towards the previous vertex.
+    fn eval(&self, wi: Vector) -> Color {
+        match (self.kind, self.normal) {
+            (Kind::Surface(bsdf), Some(normal)) => bsdf.eval(normal, self.wo, wi),
+            _ => Color::black(),
+        }
+    }
+
+    /// Returns the absolute cosine between the normal and a direction (`1`
+    /// for vertices without a normal).
+    fn cosine(&self, direction: Vector) -> f64 {
+        self.normal.map_or(1., |normal| normal.dot(direction).abs())
+    }
+
+    /// Converts the density (per steradian) of sampling `next` from the
+    /// vertex to a density per unit area.
+    fn convert(&self, pdf: f64, next: &Vertex) -> f64 {
+        let vector = next.point - self.point;
+        let distance2 = vector.dot(vector);
+        if distance2 == 0. {
+            return 0.;
+        }
+        pdf * next.cosine(vector / distance2.sqrt()) / distance2
+    }
+
+    /// Returns the density (per unit area) of sampling `next` from the
+    /// vertex when it is reached from `previous`.
+    fn pdf(&self, camera: &Camera, previous: Option<&Vertex>, next: &Vertex) -> f64 {
+        let wi = (next.point - self.point).normalized();
+        let pdf = match self.kind {
+            Kind::Camera => camera.pdf(wi),
+            Kind::Light(emitter) => emitter.direction_pdf(wi),
+            Kind::Surface(bsdf) => match (previous, self.normal) {
+                (Some(previous), Some(normal)) => {
+                    let wo = (previous.point - self.point).normalized();
+                    let normal = if normal.dot(wo) < 0. { -normal } else { normal };
+                    bsdf.pdf(normal, wo, wi)
+                }
+                _ => 0.,
+            },
+        };
+        self.convert(pdf, next)
+    }
+}
+
+impl BidirectionalPathTracer {
+    const SAMPLES: usize = 16;
+    const MAX_DEPTH: usize = 16;
+    const ROULETTE_DEPTH: usize = 3;
+
+    /// Creates a `BidirectionalPathTracer` with default settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{BidirectionalPathTracer, Heuristic};
+    /// let tracer = BidirectionalPathTracer::new();
+    /// assert_eq!(tracer.samples_per_pixel(), 16);
+    /// assert_eq!(tracer.max_depth(), 16);
+    /// assert_eq!(tracer.roulette_depth(), 3);
+    /// assert_eq!(tracer.heuristic(), Heuristic::Power);
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            samples: Self::SAMPLES,
+            max_depth: Self::MAX_DEPTH,
+            roulette_depth: Self::ROULETTE_DEPTH,
+            heuristic: Heuristic::default(),
+            seed: 0,
+        }
+    }
+
+    /// Get the number of pairs of subpaths traced per pixel.
+    #[must_use]
+    pub fn samples_per_pixel(&self) -> usize {
+        self.samples
+    }
+
+    /// Sets the number of pairs of subpaths traced per pixel.
+    pub fn set_samples_per_pixel(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    /// Get the maximum number of bounces of a path.
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Sets the maximum number of bounces of a path.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Get the number of bounces after which subpaths are subject to Russian
+    /// roulette.
+    #[must_use]
+    pub fn roulette_depth(&self) -> usize {
+        self.roulette_depth
+    }
+
+    /// Sets the number of bounces after which subpaths are subject to
+    /// Russian roulette.
+    pub fn set_roulette_depth(&mut self, roulette_depth: usize) {
+        self.roulette_depth = roulette_depth;
+    }
+
+    /// Get the heuristic weighting the connections.
+    #[must_use]
+    pub fn heuristic(&self) -> Heuristic {
+        self.heuristic
+    }
+
+    /// Sets the heuristic weighting the connections.
+    pub fn set_heuristic(&mut self, heuristic: Heuristic) {
+        self.heuristic = heuristic;
+    }
+
+    /// Get the seed of the random sequences.
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets the seed of the random sequences, renders with the same seed are
+    /// identical.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Renders the world by averaging the paths built from subpaths traced
+    /// through random positions within each pixel and from the lights.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::f64::consts::PI;
+    /// # use ray_tracer_challenge::{BidirectionalPathTracer, Camera, Color, Point, PointLight, Quadric, World};
+    /// let mut world = World::new();
+    /// world.add_object(Quadric::plane());
+    /// world.add_light(PointLight::from_power(Point::new(0., 1., 0.), Color::white(), 4. * PI));
+    /// let camera = Camera::new(4, 4, 1.);
+    /// let image = BidirectionalPathTracer::new().render(&world, &camera);
+    /// assert_eq!(image.width(), 4);
+    /// ```
+    pub fn render(&self, world: &World, camera: &Camera) -> Canvas {
+        let mut image = Canvas::new(camera.hsize(), camera.vsize());
+        let mut splats = Canvas::new(camera.hsize(), camera.vsize());
+        let emitters: Vec<Emitter> = world.light_sources().filter_map(|light| light.emitter()).collect();
+        let lights: Vec<&dyn Light> = world
+            .light_sources()
+            .filter(|light| light.emitter().is_none())
+            .collect();
+        for y in 0..camera.vsize() {
+            for x in 0..camera.hsize() {
+                let mut rng = Rng::from_values(&[x as f64, y as f64, self.seed as f64]);
+                let mut color = Color::black();
+                for _ in 0..self.samples {
+                    let ray = camera.ray_for_point(x as f64 + rng.next_f64(), y as f64 + rng.next_f64());
+                    let mut camera_path = vec![Vertex {
+                        kind: Kind::Camera,
+                        point: ray.origin,
+                        normal: None,
+                        wo: -ray.direction,
+                        beta: Color::white(),
+                        pdf_fwd: 1.,
+                        pdf_rev: 0.,
+                    }];
+                    let pdf = camera.pdf(ray.direction);
+                    color += self.walk(
+                        world,
+                        Some(&lights),
+                        &ray,
+                        Color::white(),
+                        pdf,
+                        &mut rng,
+                        &mut camera_path,
+                    );
+                    let light_path = self.light_path(world, &emitters, &mut rng);
+                    for t in 1..=camera_path.len() {
+                        for s in 1..=light_path.len() {
+                            if s + t < 3 || s + t - 2 > self.max_depth {
+                                continue;
+                            }
+                            let (light_path, camera_path) = (&light_path[..s], &camera_path[..t]);
+                            if t == 1 {
+                                if !light_tracing(camera) {
+                                    continue;
+                                }
+                                if let Some(((px, py), splat)) = self.splat(world, camera, light_path, camera_path) {
+                                    *splats.get_mut(px, py) += splat;
+                                }
+                            } else if s == 1 {
+                                color += self.sample_light(world, camera, &emitters, camera_path, &mut rng);
+                            } else {
+                                color += self.connect(world, camera, light_path, camera_path);
+                            }
+                        }
+                    }
+                }
+                *image.get_mut(x, y) = color;
+            }
+        }
+        // light subpaths are traced as many times as camera subpaths
+        for y in 0..camera.vsize() {
+            for x in 0..camera.hsize() {
+                let color = *image.get(x, y) + *splats.get(x, y);
+                *image.get_mut(x, y) = color * (1. / self.samples as f64);
+            }
+        }
+        image
+    }
+
+    /// Returns a subpath starting from a position of a randomly chosen
+    /// emitter, empty if there are no emitters.
+    fn light_path(&self, world: &World, emitters: &[Emitter], rng: &mut Rng) -> Vec<Vertex> {
+        let (emitter, choice) = match Emitter::choose(emitters, rng.next_f64()) {
+            Some(chosen) => chosen,
+            None => return vec![],
+        };
+        let (position, pdf_position) = emitter.sample_position(rng.next_f64(), rng.next_f64());
+        let direction = emitter.sample_direction(rng.next_f64(), rng.next_f64());
+        let mut path = vec![Vertex {
+            kind: Kind::Light(emitter),
+            point: position,
+            normal: emitter.normal(),
+            wo: direction,
+            beta: Color::white() * (1. / (choice * pdf_position)),
+            pdf_fwd: choice * pdf_position,
+            pdf_rev: 0.,
+        }];
+        let pdf = emitter.direction_pdf(direction);
+        let intensity = emitter.intensity(position, direction);
+        if pdf <= 0. || intensity == Color::black() {
+            return path;
+        }
+        let beta = intensity * (1. / (choice * pdf_position * pdf));
+        self.walk(world, None, &Ray::new(position, direction), beta, pdf, rng, &mut path);
+        path
+    }
+
+    /// Extends a subpath from its last vertex by a random walk along a ray
+    /// sampled with density `pdf` (per steradian).
+    ///
+    /// Camera subpaths are given the lights handled by path tracing and the
+    /// radiance gathered from these lights and the emissive shapes is
+    /// returned.
+    #[allow(clippy::too_many_arguments)]
+    fn walk(
+        &self,
+        world: &World,
+        lights: Option<&[&dyn Light]>,
+        ray: &Ray,
+        mut beta: Color,
+        mut pdf: f64,
+        rng: &mut Rng,
+        path: &mut Vec<Vertex>,
+    ) -> Color {
+        let mut color = Color::black();
+        let start = beta.r.max(beta.g).max(beta.b);
+        let mut ray = *ray;
+        let mut scattering: Option<path_tracer::Vertex> = None;
+        for depth in 0..self.max_depth {
+            let intersections = world.intersect(&ray);
+            let hit = Intersection::hit(&intersections);
+            if let Some(lights) = lights {
+                match &scattering {
+                    Some(vertex) => color += beta * reached(self.heuristic, world, lights, vertex, &ray, None, rng),
+                    None if hit.is_none() => color += world.background(ray.direction),
+                    None => {}
+                }
+            }
+            let hit = match hit {
+                Some(hit) => hit,
+                None => break,
+            };
+            let comps = Computations::new(&hit, &ray);
+            let material = comps.object.material();
+            let bsdf = Bsdf::new(material);
+            let (normal, wo) = (comps.normalv, comps.eyev);
+            let mut vertex = Vertex {
+                kind: Kind::Surface(bsdf),
+                point: comps.over_point,
+                normal: Some(normal),
+                wo,
+                beta,
+                pdf_fwd: 0.,
+                pdf_rev: 0.,
+            };
+            let previous = path.len() - 1;
+            vertex.pdf_fwd = path[previous].convert(pdf, &vertex);
+            path.push(vertex);
+            if bsdf.is_black() {
+                if lights.is_some() {
+                    color += beta * material.emission;
+                }
+                break;
+            }
+            let counts = match lights {
+                Some(lights) => {
+                    let scattering = Scattering::Surface { bsdf, normal, wo };
+                    let (direct, counts) = direct(
+                        self.heuristic,
+                        world,
+                        lights,
+                        comps.over_point,
+                        0.,
+                        &scattering,
+                        None,
+                        rng,
+                    );
+                    color += beta * (material.emission + direct);
+                    counts
+                }
+                None => vec![],
+            };
+            if depth + 1 >= self.roulette_depth {
+                let survival = (beta.r.max(beta.g).max(beta.b) / start).min(0.95);
+                if rng.next_f64() >= survival {
+                    break;
+                }
+                beta *= 1. / survival;
+            }
+            let wi = match bsdf.sample(normal, wo, rng.next_f64(), rng.next_f64(), rng.next_f64()) {
+                Some(wi) => wi,
+                None => break,
+            };
+            pdf = bsdf.pdf(normal, wo, wi);
+            if pdf <= 0. {
+                break;
+            }
+            let reverse = bsdf.pdf(normal, wi, wo);
+            path[previous].pdf_rev = vertex.convert(reverse, &path[previous]);
+            beta *= bsdf.eval(normal, wo, wi) * (wi.dot(normal) / pdf);
+            if lights.is_some() {
+                scattering = Some(path_tracer::Vertex {
+                    point: comps.over_point,
+                    pdf,
+                    counts,
+                });
+            }
+            ray = Ray::new(comps.over_point, wi);
+        }
+        color
+    }
+
+    /// Returns the contribution of the path connecting the last vertices of
+    /// both subpaths.
+    fn connect(&self, world: &World, camera: &Camera, light_path: &[Vertex], camera_path: &[Vertex]) -> Color {
+        let qs = &light_path[light_path.len() - 1];
+        let pt = &camera_path[camera_path.len() - 1];
+        let vector = qs.point - pt.point;
+        let distance = vector.len();
+        let direction = vector / distance;
+        let f = pt.eval(direction) * qs.eval(-direction);
+        if f == Color::black() || world.is_shadowed(pt.point, direction, distance) {
+            return Color::black();
+        }
+        let geometry = pt.cosine(direction) * qs.cosine(direction) / (distance * distance);
+        let weight = self.weight(camera, light_path, camera_path);
+        qs.beta * pt.beta * f * (geometry * weight)
+    }
+
+    /// Returns the contribution of the path connecting the last vertex of the
+    /// camera subpath to a position sampled on a randomly chosen emitter.
+    fn sample_light(
+        &self,
+        world: &World,
+        camera: &Camera,
+        emitters: &[Emitter],
+        camera_path: &[Vertex],
+        rng: &mut Rng,
+    ) -> Color {
+        let (emitter, choice) = match Emitter::choose(emitters, rng.next_f64()) {
+            Some(chosen) => chosen,
+            None => return Color::black(),
+        };
+        let (position, pdf_position) = emitter.sample_position(rng.next_f64(), rng.next_f64());
+        let pt = &camera_path[camera_path.len() - 1];
+        let vector = position - pt.point;
+        let distance = vector.len();
+        let direction = vector / distance;
+        let light = Vertex {
+            kind: Kind::Light(emitter),
+            point: position,
+            normal: emitter.normal(),
+            wo: -direction,
+            beta: Color::white() * (1. / (choice * pdf_position)),
+            pdf_fwd: choice * pdf_position,
+            pdf_rev: 0.,
+        };
+        // intensities already account for the orientation of area emitters
+        let f = pt.eval(direction) * emitter.intensity(position, -direction);
+        if f == Color::black() || world.is_shadowed(pt.point, direction, distance) {
+            return Color::black();
+        }
+        let weight = self.weight(camera, slice::from_ref(&light), camera_path);
+        light.beta * pt.beta * f * (pt.cosine(direction) / (distance * distance) * weight)
+    }
+
+    /// Returns the pixel through which the camera sees the last vertex of the
+    /// light subpath and the contribution of the path connecting it to the
+    /// camera, `None` if the vertex is not visible.
+    fn splat(
+        &self,
+        world: &World,
+        camera: &Camera,
+        light_path: &[Vertex],
+        camera_path: &[Vertex],
+    ) -> Option<((usize, usize), Color)> {
+        let qs = &light_path[light_path.len() - 1];
+        let (x, y) = camera.project(qs.point)?;
+        let vector = camera_path[0].point - qs.point;
+        let distance = vector.len();
+        let direction = vector / distance;
+        let lens = camera.cosine(-direction)?;
+        let f = qs.eval(direction);
+        if f == Color::black() || world.is_shadowed(qs.point, direction, distance) {
+            return None;
+        }
+        let importance = camera.importance(-direction) * lens / (distance * distance);
+        let weight = self.weight(camera, light_path, camera_path);
+        Some((
+            (x as usize, y as usize),
+            qs.beta * f * (qs.cosine(direction) * importance * weight),
+        ))
+    }
+
+    /// Returns the weight of the path connecting the last vertices of both
+    /// subpaths among the paths of the same length built with the other
+    /// connections.
+    ///
+    /// The densities of the connected vertices are updated for the new path
+    /// and the ratios of the densities of the other connections to the
+    /// density of this one are accumulated walking away from the connection
+    /// along both subpaths. Connections reaching a light vertex from the
+    /// camera subpath are not built and are skipped, as are connections to
+    /// the camera when it is not perspective.
+    fn weight(&self, camera: &Camera, light_path: &[Vertex], camera_path: &[Vertex]) -> f64 {
+        let (s, t) = (light_path.len(), camera_path.len());
+        let mut light_pdfs: Vec<(f64, f64)> = light_path.iter().map(|v| (v.pdf_fwd, v.pdf_rev)).collect();
+        let mut camera_pdfs: Vec<(f64, f64)> = camera_path.iter().map(|v| (v.pdf_fwd, v.pdf_rev)).collect();
+        let (qs, pt) = (&light_path[s - 1], &camera_path[t - 1]);
+        let qs_minus = s.checked_sub(2).map(|i| &light_path[i]);
+        let pt_minus = t.checked_sub(2).map(|i| &camera_path[i]);
+        camera_pdfs[t - 1].1 = qs.pdf(camera, qs_minus, pt);
+        if let Some(pt_minus) = pt_minus {
+            camera_pdfs[t - 2].1 = pt.pdf(camera, Some(qs), pt_minus);
+        }
+        light_pdfs[s - 1].1 = pt.pdf(camera, pt_minus, qs);
+        if let Some(qs_minus) = qs_minus {
+            light_pdfs[s - 2].1 = qs.pdf(camera, Some(pt), qs_minus);
+        }
+        let remap = |pdf: f64| if pdf == 0. { 1. } else { pdf };
+        let mut sum = 0.;
+        let mut ratio = 1.;
+        for (i, &(fwd, rev)) in camera_pdfs.iter().enumerate().skip(1).rev() {
+            ratio *= remap(rev) / remap(fwd);
+            if i > 1 || light_tracing(camera) {
+                sum += self.heuristic.power(ratio);
+            }
+        }
+        let mut ratio = 1.;
+        for &(fwd, rev) in light_pdfs[1..].iter().rev() {
+            ratio *= remap(rev) / remap(fwd);
+            sum += self.heuristic.power(ratio);
+        }
+        1. / (1. + sum)
+    }
+}
+
+/// Returns `true` if light subpaths can be connected to the camera, which
+/// requires the importance of a perspective projection.
+fn light_tracing(camera: &Camera) -> bool {
+    camera.projection() == Projection::Perspective
+}
+
+impl Default for BidirectionalPathTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{AreaLight, Environment, Material, Matrix, PointLight, Quadric, Shape};
+
+    use super::*;
+
+    fn camera(field_of_view: f64) -> Camera {
+        let mut camera = Camera::new(1, 1, field_of_view);
+        let from = Point::new(0., 5., 0.);
+        let to = Point::new(0., 0., 0.);
+        let up = Vector::new(0., 0., 1.);
+        camera.set_transform(Matrix::view_transform(from, to, up));
+        camera
+    }
+
+    fn diffuse(color: Color, emission: Color) -> Material {
+        Material {
+            color,
+            diffuse: 1.,
+            specular: 0.,
+            emission,
+            ..Material::default()
+        }
+    }
+
+    fn floor() -> Quadric {
+        let mut plane = Quadric::plane();
+        plane.set_material(diffuse(Color::new(0.5, 0.5, 0.5), Color::black()));
+        plane
+    }
+
+    #[test]
+    fn projection() {
+        // furnace seen through cameras without light tracing: the radiance
+        // is the same in every direction
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::scaling(10., 10., 10.));
+        sphere.set_material(diffuse(Color::new(0.5, 0.5, 0.5), Color::black()));
+        world.add_object(sphere);
+        world.add_light(PointLight::from_power(Point::new(0., 0., 0.), Color::white(), 4. * PI));
+        let expected = 1. / (100. * PI);
+        let mut tracer = BidirectionalPathTracer::new();
+        tracer.set_samples_per_pixel(1024);
+        let transform = *camera(1.).transform();
+        for mut camera in [
+            Camera::orthographic(2, 2, 1.),
+            Camera::fisheye(2, 2, PI),
+            Camera::equirectangular(2, 1),
+        ] {
+            camera.set_transform(transform);
+            let image = tracer.render(&world, &camera);
+            for color in image.iter() {
+                assert!(
+                    (color.r - expected).abs() < 0.03 * expected,
+                    "{:?}",
+                    camera.projection()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn settings() {
+        let mut tracer = BidirectionalPathTracer::default();
+        tracer.set_samples_per_pixel(0);
+        assert_eq!(tracer.samples_per_pixel(), 1);
+        tracer.set_max_depth(4);
+        assert_eq!(tracer.max_depth(), 4);
+        tracer.set_roulette_depth(2);
+        assert_eq!(tracer.roulette_depth(), 2);
+        tracer.set_heuristic(Heuristic::Balance);
+        assert_eq!(tracer.heuristic(), Heuristic::Balance);
+        tracer.set_seed(7);
+        assert_eq!(tracer.seed(), 7);
+    }
+
+    #[test]
+    fn path_traced_lights() {
+        // lights without emitter are sampled as by the path tracer
+        let mut world = World::new();
+        world.add_object(floor());
+        world.add_light(PointLight::new(Point::new(0., 10., 0.), Color::white()));
+        let image = BidirectionalPathTracer::new().render(&world, &camera(0.01));
+        assert_eq!(image.get(0, 0), &(Color::white() * (0.5 / PI)));
+        let mut world = World::new();
+        world.set_environment(Environment::new(Canvas::with_color(8, 4, Color::new(0.2, 0.4, 0.6))));
+        let image = BidirectionalPathTracer::new().render(&world, &camera(0.01));
+        assert_eq!(image.get(0, 0), &Color::new(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn emission() {
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(diffuse(Color::black(), Color::new(1., 2., 3.)));
+        world.add_object(sphere);
+        let image = BidirectionalPathTracer::new().render(&world, &camera(0.01));
+        assert_eq!(image.get(0, 0), &Color::new(1., 2., 3.));
+    }
+
+    #[test]
+    fn point_emitter() {
+        // Lambertian plane lit from above: L = albedo / π * I / d²
+        let mut world = World::new();
+        world.add_object(floor());
+        world.add_light(PointLight::from_power(
+            Point::new(0., 10., 0.),
+            Color::white(),
+            400. * PI,
+        ));
+        let image = BidirectionalPathTracer::new().render(&world, &camera(0.01));
+        assert!((image.get(0, 0).r - 0.5 / PI).abs() < 1e-3);
+    }
+
+    #[test]
+    fn area_emitter() {
+        // Lambertian plane under a 2x2 area light at height 1
+        let mut world = World::new();
+        let mut plane = Quadric::plane();
+        plane.set_material(diffuse(Color::white(), Color::black()));
+        world.add_object(plane);
+        let corner = Point::new(-1., 1., -1.);
+        let (u, v) = (Vector::new(2., 0., 0.), Vector::new(0., 0., 2.));
+        world.add_light(AreaLight::from_power(corner, u, 1, v, 1, Color::white(), 4. * PI));
+        // L = 1 / π * 1 / A ∫ cos θ / d² dA
+        let steps = 400;
+        let mut expected = 0.;
+        for i in 0..steps {
+            for j in 0..steps {
+                let x = -1. + 2. * (i as f64 + 0.5) / steps as f64;
+                let z = -1. + 2. * (j as f64 + 0.5) / steps as f64;
+                let d2 = x * x + 1. + z * z;
+                expected += 1. / (d2 * d2.sqrt());
+            }
+        }
+        expected /= (steps * steps) as f64 * PI;
+        let mut tracer = BidirectionalPathTracer::new();
+        tracer.set_samples_per_pixel(512);
+        let image = tracer.render(&world, &camera(0.01));
+        assert!((image.get(0, 0).r - expected).abs() < 0.02 * expected);
+    }
+
+    #[test]
+    fn light_tracing() {
+        // a light close to the floor seen through a wide pixel is mostly
+        // estimated by the paths splatted from the light
+        let mut world = World::new();
+        world.add_object(floor());
+        world.add_light(PointLight::from_power(Point::new(0., 0.5, 0.), Color::white(), 4. * PI));
+        // the pixel sees the 10x10 square around the light
+        let steps = 400;
+        let mut expected = 0.;
+        for i in 0..steps {
+            for j in 0..steps {
+                let x = -5. + 10. * (i as f64 + 0.5) / steps as f64;
+                let z = -5. + 10. * (j as f64 + 0.5) / steps as f64;
+                let d2 = x * x + 0.25 + z * z;
+                expected += 0.5 / (d2 * d2.sqrt());
+            }
+        }
+        expected *= 0.5 / (PI * (steps * steps) as f64);
+        let mut tracer = BidirectionalPathTracer::new();
+        tracer.set_samples_per_pixel(16384);
+        for heuristic in [Heuristic::Balance, Heuristic::Power] {
+            tracer.set_heuristic(heuristic);
+            let image = tracer.render(&world, &camera(PI / 2.));
+            assert!((image.get(0, 0).r - expected).abs() < 0.03 * expected);
+        }
+    }
+
+    #[test]
+    fn furnace() {
+        // light at the center of a sphere of albedo a and radius R:
+        // L = a / π * I / R² / (1 - a)
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::scaling(10., 10., 10.));
+        sphere.set_material(diffuse(Color::new(0.5, 0.5, 0.5), Color::black()));
+        world.add_object(sphere);
+        world.add_light(PointLight::from_power(Point::new(0., 0., 0.), Color::white(), 4. * PI));
+        let expected = 1. / (100. * PI);
+        let mut tracer = BidirectionalPathTracer::new();
+        tracer.set_samples_per_pixel(1024);
+        for heuristic in [Heuristic::Balance, Heuristic::Power] {
+            tracer.set_heuristic(heuristic);
+            let image = tracer.render(&world, &camera(1.));
+            assert!((image.get(0, 0).r - expected).abs() < 0.03 * expected);
+        }
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut world = World::new();
+        world.add_object(floor());
+        world.add_light(PointLight::from_power(Point::new(0., 1., 0.), Color::white(), 100.));
+        let mut tracer = BidirectionalPathTracer::new();
+        let a = tracer.render(&world, &camera(PI / 2.));
+        let b = tracer.render(&world, &camera(PI / 2.));
+        assert_eq!(a.get(0, 0), b.get(0, 0));
+        tracer.set_seed(1);
+        let c = tracer.render(&world, &camera(PI / 2.));
+        assert_ne!(a.get(0, 0), c.get(0, 0));
+    }
+}
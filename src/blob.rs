@@ -0,0 +1,322 @@
+use std::fmt::Debug;
+
+use crate::{util::EPSILON, Attributes, Bounds, Intersection, Point, Ray, Shape, Vector};
+
+/// Implicit surface (metaballs) defined by a set of weighted centers.
+///
+/// Each element contributes `weight * falloff(distance / radius)` to the field
+/// within its radius of influence, the surface is the set of points where the
+/// field equals the threshold, points above the threshold being inside.
+///
+/// Rays are first clipped against the spheres of influence of the elements.
+/// The remaining intervals are sampled to bracket the threshold crossings
+/// which are then refined by bisection. Normals are estimated by central
+/// differences of the field.
+pub struct Blob {
+    elements: Vec<Element>,
+    threshold: f64,
+    falloff: Box<dyn Fn(f64) -> f64>,
+    steps: usize,
+    attributes: Attributes,
+}
+
+/// Weighted center of a [`Blob`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Element {
+    center: Point,
+    radius: f64,
+    weight: f64,
+}
+
+impl Blob {
+    const STEPS: usize = 64;
+    const BISECTIONS: usize = 64;
+
+    /// Creates an empty `Blob` whose surface lies at specified field value.
+    ///
+    /// Elements use the soft object falloff `(1 - d²)³` by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Blob, Point, Ray, Shape, Vector};
+    /// let mut blob = Blob::new(0.5);
+    /// blob.add(Point::new(-0.5, 0., 0.), 1., 1.);
+    /// blob.add(Point::new(0.5, 0., 0.), 1., 1.);
+    /// let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+    /// assert_eq!(blob.intersect(&ray).len(), 2);
+    /// ```
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            elements: vec![],
+            threshold,
+            falloff: Box::new(|d| {
+                let s = 1. - d * d;
+                s * s * s
+            }),
+            steps: Self::STEPS,
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Adds an element of specified center, radius of influence and weight.
+    ///
+    /// Negative weights carve the surface of other elements.
+    pub fn add(&mut self, center: Point, radius: f64, weight: f64) {
+        self.elements.push(Element { center, radius, weight });
+    }
+
+    /// Get the field value of the surface.
+    #[must_use]
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// Sets the falloff function.
+    ///
+    /// The function receives the distance to the element center divided by
+    /// the element radius (in `[0,1]`) and should reach `0` at `1`.
+    pub fn set_falloff(&mut self, falloff: impl Fn(f64) -> f64 + 'static) {
+        self.falloff = Box::new(falloff);
+    }
+
+    /// Get the number of samples used to bracket the surface crossings within
+    /// each interval of influence.
+    #[must_use]
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Sets the number of samples used to bracket the surface crossings
+    /// within each interval of influence.
+    pub fn set_steps(&mut self, steps: usize) {
+        self.steps = steps.max(1);
+    }
+
+    /// Returns the field value at specified object space point.
+    pub fn field(&self, point: Point) -> f64 {
+        self.elements
+            .iter()
+            .map(|element| {
+                let distance = (point - element.center).len() / element.radius;
+                if distance < 1. {
+                    element.weight * (self.falloff)(distance)
+                } else {
+                    0.
+                }
+            })
+            .sum()
+    }
+
+    /// Returns the merged `t` intervals where the ray crosses a sphere of
+    /// influence.
+    fn intervals(&self, ray: &Ray) -> Vec<(f64, f64)> {
+        let mut intervals = vec![];
+        for element in self.elements.iter() {
+            let to_origin = ray.origin - element.center;
+            let a = ray.direction.dot(ray.direction);
+            let b = 2. * ray.direction.dot(to_origin);
+            let c = to_origin.dot(to_origin) - element.radius * element.radius;
+            let discriminant = b * b - 4. * a * c;
+            if discriminant > 0. {
+                let t1 = (-b - discriminant.sqrt()) / (2. * a);
+                let t2 = (-b + discriminant.sqrt()) / (2. * a);
+                intervals.push((t1, t2));
+            }
+        }
+        intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut merged: Vec<(f64, f64)> = vec![];
+        for (start, end) in intervals {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+
+    /// Refines a threshold crossing bracketed by `[t0, t1]`.
+    fn bisect(&self, ray: &Ray, mut t0: f64, mut t1: f64) -> f64 {
+        let inside = |t: f64| self.field(ray.position(t)) > self.threshold;
+        let start = inside(t0);
+        for _ in 0..Self::BISECTIONS {
+            let t = (t0 + t1) / 2.;
+            if inside(t) == start {
+                t0 = t;
+            } else {
+                t1 = t;
+            }
+        }
+        (t0 + t1) / 2.
+    }
+}
+
+impl Debug for Blob {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("Blob")
+            .field("elements", &self.elements)
+            .field("threshold", &self.threshold)
+            .field("steps", &self.steps)
+            .field("attributes", &self.attributes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Shape for Blob {
+    fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    fn attributes_mut(&mut self) -> &mut Attributes {
+        &mut self.attributes
+    }
+
+    /// Surfaces lie within the spheres of influence of the elements.
+    fn bounds(&self) -> Bounds {
+        self.elements.iter().fold(Bounds::empty(), |bounds, element| {
+            let radius = Vector::new(element.radius, element.radius, element.radius);
+            bounds.union(&Bounds::new(element.center - radius, element.center + radius))
+        })
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut intersections = vec![];
+        for (start, end) in self.intervals(ray) {
+            let step = (end - start) / self.steps as f64;
+            let mut t0 = start;
+            let mut inside = self.field(ray.position(t0)) > self.threshold;
+            for i in 1..=self.steps {
+                let t1 = start + step * i as f64;
+                let next = self.field(ray.position(t1)) > self.threshold;
+                if next != inside {
+                    let t = self.bisect(ray, t0, t1);
+                    intersections.push(Intersection::new(
+                        t,
+                        self,
+                        self.local_normal_at(ray.position(t), ray.time),
+                    ));
+                }
+                t0 = t1;
+                inside = next;
+            }
+        }
+        intersections
+    }
+
+    fn local_normal_at(&self, point: Point, _time: f64) -> Vector {
+        let dx = Vector::new(EPSILON, 0., 0.);
+        let dy = Vector::new(0., EPSILON, 0.);
+        let dz = Vector::new(0., 0., EPSILON);
+        -Vector::new(
+            self.field(point + dx) - self.field(point - dx),
+            self.field(point + dy) - self.field(point - dy),
+            self.field(point + dz) - self.field(point - dz),
+        )
+        .normalized()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{util::float_eq, Matrix};
+
+    use super::*;
+
+    #[test]
+    fn field() {
+        let mut blob = Blob::new(0.5);
+        blob.add(Point::new(0., 0., 0.), 2., 1.);
+        assert!(float_eq(blob.field(Point::new(0., 0., 0.)), 1.));
+        assert!(float_eq(blob.field(Point::new(1., 0., 0.)), 0.421875));
+        assert!(float_eq(blob.field(Point::new(0., 3., 0.)), 0.));
+        blob.add(Point::new(2., 0., 0.), 2., 2.);
+        assert!(float_eq(blob.field(Point::new(1., 0., 0.)), 3. * 0.421875));
+        assert_eq!(
+            blob.bounds(),
+            Bounds::new(Point::new(-2., -2., -2.), Point::new(4., 2., 2.))
+        );
+        assert!(Blob::new(0.5).bounds().is_empty());
+    }
+
+    #[test]
+    fn falloff() {
+        let mut blob = Blob::new(0.5);
+        blob.add(Point::new(0., 0., 0.), 2., 1.);
+        blob.set_falloff(|d| 1. - d);
+        assert!(float_eq(blob.field(Point::new(1., 0., 0.)), 0.5));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = blob.intersect(&ray);
+        assert!(float_eq(xs[0].t, 4.));
+        assert!(float_eq(xs[1].t, 6.));
+    }
+
+    #[test]
+    fn intersect_single() {
+        // (1 - d²)³ = 1/8 at d² = 1/2
+        let mut blob = Blob::new(0.125);
+        blob.add(Point::new(0., 0., 0.), 2., 1.);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = blob.intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        assert!(float_eq(xs[0].t, 5. - 2f64.sqrt()));
+        assert!(float_eq(xs[1].t, 5. + 2f64.sqrt()));
+        assert_eq!(xs[0].normal, Vector::new(0., 0., -1.));
+        assert_eq!(xs[1].normal, Vector::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn intersect_merged() {
+        let mut blob = Blob::new(0.5);
+        blob.add(Point::new(-0.8, 0., 0.), 1., 1.);
+        blob.add(Point::new(0.8, 0., 0.), 1., 1.);
+        // each element alone is below the threshold at the origin
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        assert!(blob.intersect(&ray).is_empty());
+        blob.add(Point::new(0., 0., 0.), 1., 0.5);
+        let xs = blob.intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        // along the x axis the elements form a single surface
+        let ray = Ray::new(Point::new(-5., 0., 0.), Vector::new(1., 0., 0.));
+        assert_eq!(blob.intersect(&ray).len(), 2);
+    }
+
+    #[test]
+    fn intersect_separate() {
+        let mut blob = Blob::new(0.5);
+        blob.add(Point::new(-2., 0., 0.), 1., 1.);
+        blob.add(Point::new(2., 0., 0.), 1., 1.);
+        let ray = Ray::new(Point::new(-5., 0., 0.), Vector::new(1., 0., 0.));
+        let xs = blob.intersect(&ray);
+        assert_eq!(xs.len(), 4);
+        assert!(xs.windows(2).all(|w| w[0].t < w[1].t));
+    }
+
+    #[test]
+    fn intersect_miss() {
+        let mut blob = Blob::new(0.5);
+        blob.add(Point::new(0., 0., 0.), 1., 1.);
+        let ray = Ray::new(Point::new(0., 2., -5.), Vector::new(0., 0., 1.));
+        assert!(blob.intersect(&ray).is_empty());
+        let ray = Ray::new(Point::new(0., 0.9, -5.), Vector::new(0., 0., 1.));
+        assert!(blob.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_transformed() {
+        let mut blob = Blob::new(0.125);
+        blob.add(Point::new(0., 0., 0.), 2., 1.);
+        blob.set_transform(Matrix::translation(0., 0., 1.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = blob.intersect(&ray);
+        assert!(float_eq(xs[0].t, 6. - 2f64.sqrt()));
+    }
+
+    #[test]
+    fn normal() {
+        let mut blob = Blob::new(0.125);
+        blob.add(Point::new(0., 0., 0.), 2., 1.);
+        let value = 3f64.sqrt() / 3.;
+        let normal = blob.normal_at(Point::new(value, value, value), 0.);
+        assert_eq!(normal, Vector::new(value, value, value));
+    }
+}
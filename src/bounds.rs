@@ -0,0 +1,235 @@
+use crate::{Matrix, Point, Ray};
+
+/// Axis aligned bounding box of a shape.
+///
+/// Unbounded shapes have infinite bounds, shapes without any surface have
+/// empty bounds (minimum above maximum).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bounds {
+    min: Point,
+    max: Point,
+}
+
+impl Bounds {
+    /// Creates the `Bounds` between specified minimum and maximum corners.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Bounds, Point, Ray, Vector};
+    /// let bounds = Bounds::new(Point::new(-1., -1., -1.), Point::new(1., 1., 1.));
+    /// assert!(bounds.intersects(&Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.))));
+    /// assert!(!bounds.intersects(&Ray::new(Point::new(0., 2., -5.), Vector::new(0., 0., 1.))));
+    /// ```
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    /// Creates the bounds of unbounded shapes, containing every point.
+    pub fn infinite() -> Self {
+        Self::new(
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        )
+    }
+
+    /// Creates the bounds of shapes without any surface, containing no
+    /// point.
+    pub fn empty() -> Self {
+        Self::new(
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        )
+    }
+
+    /// Get the minimum corner.
+    #[must_use]
+    pub fn min(&self) -> Point {
+        self.min
+    }
+
+    /// Get the maximum corner.
+    #[must_use]
+    pub fn max(&self) -> Point {
+        self.max
+    }
+
+    /// Returns `true` if the bounds contain no point.
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// Returns `true` if the bounds are neither empty nor infinite.
+    pub fn is_finite(&self) -> bool {
+        !self.is_empty()
+            && [self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z]
+                .iter()
+                .all(|c| c.is_finite())
+    }
+
+    /// Returns the smallest bounds containing both bounds.
+    #[must_use]
+    pub fn union(&self, other: &Bounds) -> Self {
+        Self::new(
+            Point::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            Point::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        )
+    }
+
+    /// Returns `true` if the bounds contain the other bounds.
+    pub fn contains(&self, other: &Bounds) -> bool {
+        other.is_empty()
+            || (self.min.x <= other.min.x
+                && self.min.y <= other.min.y
+                && self.min.z <= other.min.z
+                && self.max.x >= other.max.x
+                && self.max.y >= other.max.y
+                && self.max.z >= other.max.z)
+    }
+
+    /// Returns the bounds of the box transformed by specified matrix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Bounds, Matrix, Point};
+    /// let bounds = Bounds::new(Point::new(-1., -1., -1.), Point::new(1., 1., 1.));
+    /// let bounds = bounds.transform(&(Matrix::translation(1., 0., 0.) * Matrix::scaling(2., 1., 1.)));
+    /// assert_eq!(bounds.min(), Point::new(-1., -1., -1.));
+    /// assert_eq!(bounds.max(), Point::new(3., 1., 1.));
+    /// ```
+    #[must_use]
+    pub fn transform(&self, matrix: &Matrix) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        if !self.is_finite() {
+            return Self::infinite();
+        }
+        let (min, max) = (self.min, self.max);
+        (0..8)
+            .map(|corner| {
+                Point::new(
+                    if corner & 1 == 0 { min.x } else { max.x },
+                    if corner & 2 == 0 { min.y } else { max.y },
+                    if corner & 4 == 0 { min.z } else { max.z },
+                )
+            })
+            .fold(Self::empty(), |bounds, corner| {
+                let corner = *matrix * corner;
+                bounds.union(&Self::new(corner, corner))
+            })
+    }
+
+    /// Returns `true` if the line of the ray crosses the box.
+    ///
+    /// The whole line is tested, behind the origin of the ray too, so that
+    /// skipping the shapes inside the box never drops any intersection.
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+        let min = [self.min.x, self.min.y, self.min.z];
+        let max = [self.max.x, self.max.y, self.max.z];
+        let (mut tmin, mut tmax) = (f64::NEG_INFINITY, f64::INFINITY);
+        for axis in 0..3 {
+            if direction[axis] == 0. {
+                if !(min[axis]..=max[axis]).contains(&origin[axis]) {
+                    return false;
+                }
+                continue;
+            }
+            let t0 = (min[axis] - origin[axis]) / direction[axis];
+            let t1 = (max[axis] - origin[axis]) / direction[axis];
+            tmin = tmin.max(t0.min(t1));
+            tmax = tmax.min(t0.max(t1));
+        }
+        tmin <= tmax
+    }
+
+    /// Returns the halves of the box split across its longest side.
+    pub(crate) fn split(&self) -> (Self, Self) {
+        let size = self.max - self.min;
+        let (mut left, mut right) = (*self, *self);
+        if size.x >= size.y && size.x >= size.z {
+            left.max.x = self.min.x + size.x / 2.;
+            right.min.x = left.max.x;
+        } else if size.y >= size.z {
+            left.max.y = self.min.y + size.y / 2.;
+            right.min.y = left.max.y;
+        } else {
+            left.max.z = self.min.z + size.z / 2.;
+            right.min.z = left.max.z;
+        }
+        (left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::Vector;
+
+    use super::*;
+
+    #[test]
+    fn union() {
+        let a = Bounds::new(Point::new(-1., 0., 0.), Point::new(1., 1., 1.));
+        let b = Bounds::new(Point::new(0., -2., 0.), Point::new(3., 0., 1.));
+        let union = a.union(&b);
+        assert_eq!(union, Bounds::new(Point::new(-1., -2., 0.), Point::new(3., 1., 1.)));
+        assert!(union.contains(&a) && union.contains(&b));
+        assert!(!a.contains(&b));
+        assert_eq!(Bounds::empty().union(&a), a);
+        assert!(Bounds::empty().is_empty());
+        assert!(!Bounds::infinite().is_finite());
+    }
+
+    #[test]
+    fn transform() {
+        let bounds = Bounds::new(Point::new(-1., -1., -1.), Point::new(1., 1., 1.));
+        let rotated = bounds.transform(&Matrix::rotation_y(PI / 4.));
+        let value = 2f64.sqrt();
+        assert_eq!(
+            rotated,
+            Bounds::new(Point::new(-value, -1., -value), Point::new(value, 1., value))
+        );
+        assert_eq!(
+            Bounds::infinite().transform(&Matrix::translation(1., 0., 0.)),
+            Bounds::infinite()
+        );
+        assert!(Bounds::empty().transform(&Matrix::scaling(2., 2., 2.)).is_empty());
+    }
+
+    #[test]
+    fn intersects() {
+        let bounds = Bounds::new(Point::new(-1., -1., -1.), Point::new(1., 1., 1.));
+        assert!(bounds.intersects(&Ray::new(Point::new(5., 0.5, 0.), Vector::new(-1., 0., 0.))));
+        assert!(bounds.intersects(&Ray::new(Point::new(0., 0., 0.), Vector::new(1., 1., 1.))));
+        // behind the origin
+        assert!(bounds.intersects(&Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.))));
+        assert!(!bounds.intersects(&Ray::new(Point::new(-2., 0., 0.), Vector::new(2., 4., 6.))));
+        assert!(!bounds.intersects(&Ray::new(Point::new(2., 2., 0.), Vector::new(0., 0., 1.))));
+        assert!(!Bounds::empty().intersects(&Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.))));
+        assert!(Bounds::infinite().intersects(&Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.))));
+    }
+
+    #[test]
+    fn split() {
+        let bounds = Bounds::new(Point::new(-1., -2., -3.), Point::new(9., 5.5, 1.5));
+        let (left, right) = bounds.split();
+        assert_eq!(left, Bounds::new(Point::new(-1., -2., -3.), Point::new(4., 5.5, 1.5)));
+        assert_eq!(right, Bounds::new(Point::new(4., -2., -3.), Point::new(9., 5.5, 1.5)));
+    }
+}
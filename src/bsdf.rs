@@ -0,0 +1,277 @@
+use std::f64::consts::PI;
+
+use crate::{
+    util::{cosine_hemisphere, orthonormal_basis},
+    Color, Material, Microfacet, Vector,
+};
+
+/// Scattering of light at a surface used by the Monte Carlo integrators.
+///
+/// Materials with a [`Microfacet`] reflectance use the Cook-Torrance model.
+/// Other materials combine a Lambertian lobe of albedo `color * diffuse` with
+/// a normalized Phong lobe of albedo `specular` and exponent `shininess`
+/// around the mirror direction, they conserve energy as long as the sum of
+/// both albedos does not exceed `1`.
+///
+/// Directions point away from the surface and the normal lies on the side of
+/// the outgoing direction `wo`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bsdf {
+    model: Model,
+}
+
+/// Reflectance model of a [`Bsdf`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Model {
+    Phong {
+        diffuse: Color,
+        specular: f64,
+        shininess: f64,
+    },
+    Microfacet {
+        base_color: Color,
+        microfacet: Microfacet,
+    },
+}
+
+impl Bsdf {
+    /// Creates the `Bsdf` of a material.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Bsdf, Color, Material, Vector};
+    /// let material = Material {
+    ///     diffuse: 1.,
+    ///     specular: 0.,
+    ///     ..Material::default()
+    /// };
+    /// let bsdf = Bsdf::new(&material);
+    /// let normal = Vector::new(0., 1., 0.);
+    /// let wo = Vector::new(0., 1., 0.);
+    /// let wi = Vector::new(1., 1., 0.).normalized();
+    /// assert_eq!(bsdf.eval(normal, wo, wi), Color::white() * std::f64::consts::FRAC_1_PI);
+    /// ```
+    pub fn new(material: &Material) -> Self {
+        let model = match material.microfacet {
+            Some(microfacet) => Model::Microfacet {
+                base_color: material.color,
+                microfacet,
+            },
+            None => Model::Phong {
+                diffuse: material.color * material.diffuse,
+                specular: material.specular.max(0.),
+                shininess: material.shininess.max(0.),
+            },
+        };
+        Self { model }
+    }
+
+    /// Returns `true` if the surface does not scatter any light.
+    pub fn is_black(&self) -> bool {
+        match self.model {
+            Model::Phong { diffuse, specular, .. } => diffuse == Color::black() && specular <= 0.,
+            // conductors reflect white light at grazing angles
+            Model::Microfacet { .. } => false,
+        }
+    }
+
+    /// Returns `true` if the surface only reflects light around the mirror
+    /// direction (no diffuse lobe), the light it reflects forms caustics on
+    /// other surfaces.
+    pub fn is_specular(&self) -> bool {
+        match self.model {
+            Model::Phong { diffuse, specular, .. } => diffuse == Color::black() && specular > 0.,
+            Model::Microfacet { microfacet, .. } => microfacet.metalness >= 1.,
+        }
+    }
+
+    /// Returns the fraction of the radiance arriving from `wi` scattered
+    /// towards `wo` (per steradian).
+    pub fn eval(&self, normal: Vector, wo: Vector, wi: Vector) -> Color {
+        let (diffuse, specular, shininess) = match self.model {
+            Model::Phong {
+                diffuse,
+                specular,
+                shininess,
+            } => (diffuse, specular, shininess),
+            Model::Microfacet { base_color, microfacet } => return microfacet.eval(base_color, normal, wo, wi),
+        };
+        if wi.dot(normal) <= 0. || wo.dot(normal) <= 0. {
+            return Color::black();
+        }
+        let cosine = mirror(normal, wo).dot(wi).max(0.);
+        let specular = specular * (shininess + 2.) / (2. * PI) * cosine.powf(shininess);
+        diffuse * (1. / PI) + Color::white() * specular
+    }
+
+    /// Returns the density (per steradian) of sampling `wi` from `wo`.
+    pub fn pdf(&self, normal: Vector, wo: Vector, wi: Vector) -> f64 {
+        let shininess = match self.model {
+            Model::Phong { shininess, .. } => shininess,
+            Model::Microfacet { base_color, microfacet } => return microfacet.pdf(base_color, normal, wo, wi),
+        };
+        if wi.dot(normal) <= 0. || wo.dot(normal) <= 0. {
+            return 0.;
+        }
+        let diffuse = self.diffuse_probability();
+        let cosine = mirror(normal, wo).dot(wi).max(0.);
+        let specular = (shininess + 1.) / (2. * PI) * cosine.powf(shininess);
+        diffuse * wi.dot(normal) / PI + (1. - diffuse) * specular
+    }
+
+    /// Returns a direction `wi` sampled from `wo` proportionally to the lobes
+    /// from three uniform values in `[0,1)`, `None` if the sampled direction
+    /// lies below the surface.
+    pub fn sample(&self, normal: Vector, wo: Vector, u1: f64, u2: f64, u3: f64) -> Option<Vector> {
+        let shininess = match self.model {
+            Model::Phong { shininess, .. } => shininess,
+            Model::Microfacet { base_color, microfacet } => {
+                return microfacet.sample(base_color, normal, wo, u1, u2, u3)
+            }
+        };
+        let wi = if u1 < self.diffuse_probability() {
+            cosine_hemisphere(normal, u2, u3)
+        } else {
+            // cos α = u^(1 / (n + 1)) around the mirror direction
+            let mirror = mirror(normal, wo);
+            let (tangent, bitangent) = orthonormal_basis(mirror);
+            let cosine = u2.powf(1. / (shininess + 1.));
+            let sine = (1. - cosine * cosine).max(0.).sqrt();
+            let phi = 2. * PI * u3;
+            (tangent * (sine * phi.cos()) + bitangent * (sine * phi.sin()) + mirror * cosine).normalized()
+        };
+        if wi.dot(normal) <= 0. {
+            return None;
+        }
+        Some(wi)
+    }
+
+    /// Returns the probability of sampling the diffuse lobe of the Phong
+    /// model.
+    fn diffuse_probability(&self) -> f64 {
+        let (diffuse, specular) = match self.model {
+            Model::Phong { diffuse, specular, .. } => (diffuse.luminance().max(0.), specular),
+            Model::Microfacet { .. } => return 0.,
+        };
+        if diffuse + specular <= 0. {
+            return 1.;
+        }
+        diffuse / (diffuse + specular)
+    }
+}
+
+/// Returns the mirror direction of `wo`.
+fn mirror(normal: Vector, wo: Vector) -> Vector {
+    (-wo).reflect(normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::{float_eq, Rng};
+
+    use super::*;
+
+    fn bsdf(diffuse: f64, specular: f64, shininess: f64) -> Bsdf {
+        Bsdf::new(&Material {
+            diffuse,
+            specular,
+            shininess,
+            ..Material::default()
+        })
+    }
+
+    #[test]
+    fn eval_diffuse() {
+        let bsdf = bsdf(0.5, 0., 10.);
+        let normal = Vector::new(0., 1., 0.);
+        let wo = Vector::new(0., 1., 0.);
+        let wi = Vector::new(1., 1., 1.).normalized();
+        assert_eq!(bsdf.eval(normal, wo, wi), Color::white() * (0.5 / PI));
+        assert_eq!(bsdf.eval(normal, wo, -wi), Color::black());
+        assert!(float_eq(bsdf.pdf(normal, wo, wi), wi.y / PI));
+        assert!(float_eq(bsdf.pdf(normal, wo, -wi), 0.));
+    }
+
+    #[test]
+    fn eval_specular() {
+        let bsdf = bsdf(0., 1., 10.);
+        let normal = Vector::new(0., 1., 0.);
+        let wo = Vector::new(-1., 1., 0.).normalized();
+        let mirror = Vector::new(1., 1., 0.).normalized();
+        assert_eq!(bsdf.eval(normal, wo, mirror), Color::white() * (12. / (2. * PI)));
+        assert!(float_eq(bsdf.pdf(normal, wo, mirror), 11. / (2. * PI)));
+        assert_eq!(bsdf.eval(normal, wo, wo), Color::black());
+    }
+
+    #[test]
+    fn black() {
+        assert!(bsdf(0., 0., 10.).is_black());
+        assert!(!bsdf(0.5, 0., 10.).is_black());
+        assert!(!bsdf(0., 0.5, 10.).is_black());
+    }
+
+    #[test]
+    fn specular() {
+        assert!(bsdf(0., 1., 100.).is_specular());
+        assert!(!bsdf(0.5, 1., 100.).is_specular());
+        assert!(!bsdf(0., 0., 100.).is_specular());
+        assert!(Bsdf::new(&Material::pbr(Color::white(), 0.5, 1.)).is_specular());
+        assert!(!Bsdf::new(&Material::pbr(Color::white(), 0.1, 0.5)).is_specular());
+    }
+
+    #[test]
+    fn sample_above_surface() {
+        let bsdf = bsdf(0.5, 0.5, 20.);
+        let normal = Vector::new(0., 1., 0.);
+        let wo = Vector::new(-1., 0.2, 0.).normalized();
+        let mut rng = Rng::from_values(&[1.]);
+        for _ in 0..1000 {
+            if let Some(wi) = bsdf.sample(normal, wo, rng.next_f64(), rng.next_f64(), rng.next_f64()) {
+                assert!(wi.dot(normal) > 0.);
+                assert!(float_eq(wi.len(), 1.));
+                assert!(bsdf.pdf(normal, wo, wi) > 0.);
+            }
+        }
+    }
+
+    #[test]
+    fn microfacet() {
+        let material = Material::pbr(Color::new(0.9, 0.6, 0.3), 0.4, 1.);
+        let microfacet = material.microfacet.unwrap();
+        let bsdf = Bsdf::new(&material);
+        let normal = Vector::new(0., 1., 0.);
+        let wo = Vector::new(-1., 1., 0.).normalized();
+        let wi = Vector::new(1., 1.2, 0.).normalized();
+        assert!(!bsdf.is_black());
+        assert_eq!(
+            bsdf.eval(normal, wo, wi),
+            microfacet.eval(material.color, normal, wo, wi)
+        );
+        assert!(float_eq(
+            bsdf.pdf(normal, wo, wi),
+            microfacet.pdf(material.color, normal, wo, wi)
+        ));
+        assert_eq!(
+            bsdf.sample(normal, wo, 0.1, 0.2, 0.3),
+            microfacet.sample(material.color, normal, wo, 0.1, 0.2, 0.3)
+        );
+    }
+
+    #[test]
+    fn sample_pdf() {
+        // E[f cos / pdf] is the albedo (0.8) when no sample is lost
+        let bsdf = bsdf(0.4, 0.4, 30.);
+        let normal = Vector::new(0., 1., 0.);
+        let wo = normal;
+        let mut rng = Rng::from_values(&[2.]);
+        let count = 20_000;
+        let mut sum = 0.;
+        for _ in 0..count {
+            if let Some(wi) = bsdf.sample(normal, wo, rng.next_f64(), rng.next_f64(), rng.next_f64()) {
+                sum += bsdf.eval(normal, wo, wi).r * wi.dot(normal) / bsdf.pdf(normal, wo, wi);
+            }
+        }
+        assert!((sum / count as f64 - 0.8).abs() < 0.02);
+    }
+}
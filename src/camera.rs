@@ -0,0 +1,841 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::{
+    util::{uniform_disk, Rng},
+    Canvas, Color, Filter, Matrix, Point, Projection, Ray, Vector, World,
+};
+
+/// Camera mapping the pixels of a canvas to rays.
+///
+/// The camera looks towards `-z` in its own space and is oriented by its view
+/// transform (see [`Matrix::view_transform`]). Its [`Projection`] maps the
+/// canvas to the rays, a perspective canvas lying one unit in front of the
+/// camera.
+///
+/// The camera is a pinhole by default. With a non-zero aperture it becomes a
+/// thin lens: rays leave from a disk and converge on the plane at the focal
+/// distance, points off that plane are blurred (depth of field).
+///
+/// The shutter is open for an instant at time `0` by default. When it stays
+/// open over an interval, the rays are traced at random times of the
+/// interval and moving shapes are blurred (motion blur).
+///
+/// [`Camera::render`] traces a single ray through the center of each pixel by
+/// default. With supersampling, it traces `n * n` rays jittered inside a grid
+/// over each pixel and reconstructs the pixels from the samples around them
+/// weighted by its [`Filter`] (anti-aliasing).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    hsize: usize,
+    vsize: usize,
+    field_of_view: f64,
+    projection: Projection,
+    transform: Matrix,
+    inverse: Matrix,
+    half_width: f64,
+    half_height: f64,
+    pixel_size: f64,
+    aperture: f64,
+    focal_distance: f64,
+    shutter: (f64, f64),
+    supersampling: usize,
+    filter: Filter,
+}
+
+impl Camera {
+    /// Creates a perspective `Camera` rendering a canvas of `hsize * vsize`
+    /// pixels with specified field of view (radians).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Matrix};
+    /// use std::f64::consts::PI;
+    /// let camera = Camera::new(160, 120, PI / 2.);
+    /// assert_eq!(camera.hsize(), 160);
+    /// assert_eq!(camera.vsize(), 120);
+    /// assert_eq!(camera.transform(), &Matrix::identity());
+    /// ```
+    pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
+        let half_view = (field_of_view / 2.).tan();
+        Self::with_projection(hsize, vsize, field_of_view, Projection::Perspective, half_view)
+    }
+
+    /// Creates an orthographic `Camera` rendering a canvas of `hsize * vsize`
+    /// pixels covering a rectangle of the larger dimension `size` (in world
+    /// units), its field of view is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Point, Projection, Vector};
+    /// let camera = Camera::orthographic(200, 100, 4.);
+    /// assert_eq!(camera.projection(), Projection::Orthographic);
+    /// let ray = camera.ray_for_pixel(0, 0);
+    /// assert_eq!(ray.origin, Point::new(1.99, 0.99, 0.));
+    /// assert_eq!(ray.direction, Vector::new(0., 0., -1.));
+    /// ```
+    pub fn orthographic(hsize: usize, vsize: usize, size: f64) -> Self {
+        Self::with_projection(hsize, vsize, 0., Projection::Orthographic, size / 2.)
+    }
+
+    /// Creates an equidistant fisheye `Camera` rendering a canvas of `hsize *
+    /// vsize` pixels with specified field of view (radians, up to `2π`)
+    /// across its larger dimension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Vector};
+    /// use std::f64::consts::PI;
+    /// let camera = Camera::fisheye(200, 200, PI);
+    /// // the edges of the canvas see perpendicularly to the view axis
+    /// let ray = camera.ray_for_point(0., 100.);
+    /// assert_eq!(ray.direction, Vector::new(1., 0., 0.));
+    /// ```
+    pub fn fisheye(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
+        Self::with_projection(hsize, vsize, field_of_view, Projection::Fisheye, field_of_view / 2.)
+    }
+
+    /// Creates an equirectangular `Camera` rendering a canvas of `hsize *
+    /// vsize` pixels covering all the directions around the camera, `hsize`
+    /// should be twice `vsize` for square pixels.
+    ///
+    /// The center of the canvas sees along the view axis, its top and bottom
+    /// edges straight up and down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Vector};
+    /// let camera = Camera::equirectangular(200, 100);
+    /// assert_eq!(camera.ray_for_point(100., 50.).direction, Vector::new(0., 0., -1.));
+    /// assert_eq!(camera.ray_for_point(50., 50.).direction, Vector::new(1., 0., 0.));
+    /// assert_eq!(camera.ray_for_point(0., 50.).direction, Vector::new(0., 0., 1.));
+    /// assert_eq!(camera.ray_for_point(30., 0.).direction, Vector::new(0., 1., 0.));
+    /// ```
+    pub fn equirectangular(hsize: usize, vsize: usize) -> Self {
+        let mut camera = Self::with_projection(hsize, vsize, 2. * PI, Projection::Equirectangular, PI);
+        // the canvas spans the longitudes and latitudes whatever its shape
+        camera.half_width = PI;
+        camera.half_height = FRAC_PI_2;
+        camera
+    }
+
+    /// Creates a `Camera` whose canvas extends to `half_size` along its
+    /// larger dimension from its center, in the units of the projection.
+    fn with_projection(hsize: usize, vsize: usize, field_of_view: f64, projection: Projection, half_size: f64) -> Self {
+        let aspect = hsize as f64 / vsize as f64;
+        let (half_width, half_height) = if aspect >= 1. {
+            (half_size, half_size / aspect)
+        } else {
+            (half_size * aspect, half_size)
+        };
+        Self {
+            hsize,
+            vsize,
+            field_of_view,
+            projection,
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+            half_width,
+            half_height,
+            pixel_size: half_width * 2. / hsize as f64,
+            aperture: 0.,
+            focal_distance: 1.,
+            shutter: (0., 0.),
+            supersampling: 1,
+            filter: Filter::default(),
+        }
+    }
+
+    /// Get the horizontal size (in pixels) of the canvas.
+    #[must_use]
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    /// Get the vertical size (in pixels) of the canvas.
+    #[must_use]
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    /// Get the field of view (radians), zero for orthographic cameras.
+    #[must_use]
+    pub fn field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    /// Get the projection of the canvas.
+    #[must_use]
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// Get the size of a pixel on the canvas.
+    #[must_use]
+    pub fn pixel_size(&self) -> f64 {
+        self.pixel_size
+    }
+
+    /// Get the radius of the lens (`0` for a pinhole).
+    #[must_use]
+    pub fn aperture(&self) -> f64 {
+        self.aperture
+    }
+
+    /// Sets the radius of the lens (`0` for a pinhole).
+    ///
+    /// The lens is sampled by the Monte Carlo integrators
+    /// ([`PathTracer`](crate::PathTracer),
+    /// [`SpectralPathTracer`](crate::SpectralPathTracer)), the other
+    /// renderers trace the rays through the center of the lens.
+    pub fn set_aperture(&mut self, aperture: f64) {
+        self.aperture = aperture.max(0.);
+    }
+
+    /// Get the distance from the camera to the plane in focus.
+    #[must_use]
+    pub fn focal_distance(&self) -> f64 {
+        self.focal_distance
+    }
+
+    /// Sets the distance from the camera to the plane in focus.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the distance is not positive.
+    pub fn set_focal_distance(&mut self, focal_distance: f64) {
+        assert!(focal_distance > 0., "focal distance must be positive");
+        self.focal_distance = focal_distance;
+    }
+
+    /// Get the times of the opening and closing of the shutter.
+    #[must_use]
+    pub fn shutter(&self) -> (f64, f64) {
+        self.shutter
+    }
+
+    /// Sets the times of the opening and closing of the shutter.
+    ///
+    /// As for the lens, the shutter interval is sampled by the Monte Carlo
+    /// integrators, the other renderers trace the rays at time `0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shutter closes before it opens.
+    pub fn set_shutter(&mut self, open: f64, close: f64) {
+        assert!(open <= close, "shutter must close after it opens");
+        self.shutter = (open, close);
+    }
+
+    /// Get the number of samples per pixel along each axis of the canvas.
+    #[must_use]
+    pub fn supersampling(&self) -> usize {
+        self.supersampling
+    }
+
+    /// Sets the number of samples per pixel along each axis of the canvas
+    /// (at least `1`), [`Camera::render`] tracing `n * n` rays per pixel.
+    pub fn set_supersampling(&mut self, supersampling: usize) {
+        self.supersampling = supersampling.max(1);
+    }
+
+    /// Get the filter reconstructing the pixels from the samples.
+    #[must_use]
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    /// Sets the filter reconstructing the pixels from the samples.
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+    }
+
+    /// Get the view transform.
+    #[must_use]
+    pub fn transform(&self) -> &Matrix {
+        &self.transform
+    }
+
+    /// Sets the view transform.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the transform is not invertible.
+    pub fn set_transform(&mut self, transform: Matrix) {
+        self.inverse = transform.inverse().expect("camera transform must be invertible");
+        self.transform = transform;
+    }
+
+    /// Returns the ray from the camera passing through the center of
+    /// specified pixel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Point, Vector};
+    /// use std::f64::consts::PI;
+    /// let camera = Camera::new(201, 101, PI / 2.);
+    /// let ray = camera.ray_for_pixel(100, 50);
+    /// assert_eq!(ray.origin, Point::new(0., 0., 0.));
+    /// assert_eq!(ray.direction, Vector::new(0., 0., -1.));
+    /// ```
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        self.ray_for_point(px as f64 + 0.5, py as f64 + 0.5)
+    }
+
+    /// Returns the ray from the camera passing through specified position on
+    /// the canvas (in pixels, `(0, 0)` being the top left corner of the
+    /// canvas).
+    pub fn ray_for_point(&self, x: f64, y: f64) -> Ray {
+        let (origin, direction) = self.local_ray(x, y);
+        Ray::new(self.inverse * origin, (self.inverse * direction).normalized())
+    }
+
+    /// Returns the ray from a point of the lens passing through specified
+    /// position on the canvas, the point of the lens being sampled from two
+    /// uniform values in `[0,1)`.
+    ///
+    /// All the rays through a position converge at the focal distance: on a
+    /// plane for perspective and orthographic cameras, on a sphere for the
+    /// others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Point};
+    /// use std::f64::consts::PI;
+    /// let mut camera = Camera::new(201, 101, PI / 2.);
+    /// camera.set_aperture(0.5);
+    /// camera.set_focal_distance(4.);
+    /// let ray = camera.ray_through_lens(100.5, 50.5, 0.3, 0.8);
+    /// assert_ne!(ray.origin, Point::new(0., 0., 0.));
+    /// let focus = ray.position(-4. / ray.direction.z);
+    /// assert!(focus.x.abs() < 1e-9 && focus.y.abs() < 1e-9);
+    /// ```
+    pub fn ray_through_lens(&self, x: f64, y: f64, u1: f64, u2: f64) -> Ray {
+        let (center, direction) = self.local_ray(x, y);
+        let distance = match self.projection {
+            Projection::Perspective | Projection::Orthographic => self.focal_distance / -direction.z,
+            Projection::Fisheye | Projection::Equirectangular => self.focal_distance / direction.len(),
+        };
+        let focus = center + direction * distance;
+        let (lens_x, lens_y) = uniform_disk(u1, u2);
+        let origin = center + Vector::new(lens_x * self.aperture, lens_y * self.aperture, 0.);
+        Ray::new(self.inverse * origin, (self.inverse * (focus - origin)).normalized())
+    }
+
+    /// Returns the origin and direction in the space of the camera of the
+    /// ray through the center of the lens and specified position on the
+    /// canvas.
+    fn local_ray(&self, x: f64, y: f64) -> (Point, Vector) {
+        let u = self.half_width - x * self.pixel_size;
+        let v = self.half_height - y * self.pixel_size;
+        let center = Point::new(0., 0., 0.);
+        match self.projection {
+            Projection::Perspective => (center, Vector::new(u, v, -1.)),
+            Projection::Orthographic => (Point::new(u, v, 0.), Vector::new(0., 0., -1.)),
+            Projection::Fisheye => {
+                // the distance to the center of the canvas is the angle
+                let theta = u.hypot(v);
+                if theta == 0. {
+                    return (center, Vector::new(0., 0., -1.));
+                }
+                let sine = theta.sin() / theta;
+                (center, Vector::new(u * sine, v * sine, -theta.cos()))
+            }
+            Projection::Equirectangular => {
+                let longitude = self.half_width - x * 2. * PI / self.hsize as f64;
+                let latitude = self.half_height - y * PI / self.vsize as f64;
+                let direction = Vector::new(
+                    longitude.sin() * latitude.cos(),
+                    latitude.sin(),
+                    -longitude.cos() * latitude.cos(),
+                );
+                (center, direction)
+            }
+        }
+    }
+
+    /// Returns a ray through a random position of a pixel and, for a thin
+    /// lens, a random point of the lens, at a random time of the shutter
+    /// interval.
+    pub(crate) fn sample_ray(&self, px: usize, py: usize, rng: &mut Rng) -> Ray {
+        let x = px as f64 + rng.next_f64();
+        let y = py as f64 + rng.next_f64();
+        let ray = if self.aperture == 0. {
+            self.ray_for_point(x, y)
+        } else {
+            self.ray_through_lens(x, y, rng.next_f64(), rng.next_f64())
+        };
+        let (open, close) = self.shutter;
+        if open == close {
+            return ray.with_time(open);
+        }
+        ray.with_time(open + (close - open) * rng.next_f64())
+    }
+
+    /// Returns the position of the camera.
+    pub fn origin(&self) -> Point {
+        self.inverse * Point::new(0., 0., 0.)
+    }
+
+    /// Returns the position on the canvas (in pixels) where a point is seen,
+    /// `None` if the point lies outside of the field of view. This is the
+    /// inverse of [`Camera::ray_for_point`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Point};
+    /// use std::f64::consts::PI;
+    /// let camera = Camera::new(201, 101, PI / 2.);
+    /// let (x, y) = camera.project(Point::new(0., 0., -5.)).unwrap();
+    /// assert!((x - 100.5).abs() < 1e-9 && (y - 50.5).abs() < 1e-9);
+    /// assert_eq!(camera.project(Point::new(0., 0., 5.)), None);
+    /// ```
+    pub fn project(&self, point: Point) -> Option<(f64, f64)> {
+        let point = self.transform * point;
+        let (x, y) = match self.projection {
+            Projection::Perspective => {
+                if point.z >= 0. {
+                    return None;
+                }
+                let x = (self.half_width + point.x / point.z) / self.pixel_size;
+                let y = (self.half_height + point.y / point.z) / self.pixel_size;
+                (x, y)
+            }
+            Projection::Orthographic => {
+                if point.z >= 0. {
+                    return None;
+                }
+                let x = (self.half_width - point.x) / self.pixel_size;
+                let y = (self.half_height - point.y) / self.pixel_size;
+                (x, y)
+            }
+            Projection::Fisheye => {
+                let direction = (point - Point::new(0., 0., 0.)).normalized();
+                let radius = direction.x.hypot(direction.y);
+                let theta = radius.atan2(-direction.z);
+                let scale = if radius == 0. { 0. } else { theta / radius };
+                let x = (self.half_width - direction.x * scale) / self.pixel_size;
+                let y = (self.half_height - direction.y * scale) / self.pixel_size;
+                (x, y)
+            }
+            Projection::Equirectangular => {
+                let direction = (point - Point::new(0., 0., 0.)).normalized();
+                let longitude = direction.x.atan2(-direction.z);
+                let latitude = direction.y.clamp(-1., 1.).asin();
+                let x = (self.half_width - longitude) * self.hsize as f64 / (2. * PI);
+                let y = (self.half_height - latitude) * self.vsize as f64 / PI;
+                (x, y)
+            }
+        };
+        if !(0. ..self.hsize as f64).contains(&x) || !(0. ..self.vsize as f64).contains(&y) {
+            return None;
+        }
+        Some((x, y))
+    }
+
+    /// Returns the cosine between the view axis and a direction leaving the
+    /// camera, `None` if the direction does not pass through the canvas.
+    ///
+    /// The importance and densities of the rays are those of a perspective
+    /// camera.
+    pub(crate) fn cosine(&self, direction: Vector) -> Option<f64> {
+        let direction = (self.transform * direction).normalized();
+        let cosine = -direction.z;
+        if cosine <= 0. {
+            return None;
+        }
+        let (x, y) = (direction.x / cosine, direction.y / cosine);
+        if x.abs() > self.half_width || y.abs() > self.half_height {
+            return None;
+        }
+        Some(cosine)
+    }
+
+    /// Returns the importance emitted by the camera along a direction, the
+    /// canvas measures the radiance arriving through its whole area.
+    pub(crate) fn importance(&self, direction: Vector) -> f64 {
+        let area = 4. * self.half_width * self.half_height;
+        self.cosine(direction).map_or(0., |cosine| 1. / (area * cosine.powi(4)))
+    }
+
+    /// Returns the density (per steradian) of the directions of the rays
+    /// through uniformly distributed positions of the canvas.
+    pub(crate) fn pdf(&self, direction: Vector) -> f64 {
+        let area = 4. * self.half_width * self.half_height;
+        self.cosine(direction).map_or(0., |cosine| 1. / (area * cosine.powi(3)))
+    }
+
+    /// Renders the world by shading the rays through the samples of each
+    /// pixel, a single sample at the center of the pixel without
+    /// supersampling.
+    ///
+    /// The samples are stratified: each pixel is divided in a grid of `n * n`
+    /// cells with a sample at a random position in each. Every pixel is the
+    /// average of the samples within the radius of the filter around its
+    /// center, weighted by the filter. Pixels whose weights nearly cancel
+    /// out (such as with the negative lobes of a Mitchell-Netravali filter)
+    /// are the plain average of their own samples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Color, Filter, World};
+    /// let mut camera = Camera::new(4, 3, 1.);
+    /// camera.set_supersampling(3);
+    /// camera.set_filter(Filter::gaussian(1.5, 0.5));
+    /// let image = camera.render(&World::new());
+    /// assert_eq!(image.get(3, 2), &Color::black());
+    /// ```
+    pub fn render(&self, world: &World) -> Canvas {
+        let (width, height) = (self.hsize, self.vsize);
+        let mut colors = vec![Color::black(); width * height];
+        let mut weights = vec![0.; width * height];
+        let mut averages = vec![Color::black(); width * height];
+        let radius = self.filter.radius();
+        let n = self.supersampling;
+        // smallest total weight of a pixel (per sample) worth normalizing
+        let min_weight = 1e-3 * (n * n) as f64;
+        // pixels whose center lies within the radius of a coordinate
+        let pixels = |coordinate: f64, size: usize| {
+            let first = (coordinate - 0.5 - radius).ceil().max(0.) as usize;
+            let last = (coordinate - 0.5 + radius).floor().min(size as f64 - 1.);
+            first..(last + 1.).max(0.) as usize
+        };
+        for y in 0..height {
+            for x in 0..width {
+                let mut rng = Rng::from_values(&[x as f64, y as f64]);
+                for j in 0..n {
+                    for i in 0..n {
+                        let (sx, sy) = if n == 1 {
+                            (x as f64 + 0.5, y as f64 + 0.5)
+                        } else {
+                            (
+                                x as f64 + (i as f64 + rng.next_f64()) / n as f64,
+                                y as f64 + (j as f64 + rng.next_f64()) / n as f64,
+                            )
+                        };
+                        let color = world.color_at(&self.ray_for_point(sx, sy));
+                        averages[y * width + x] += color * (1. / (n * n) as f64);
+                        for py in pixels(sy, height) {
+                            for px in pixels(sx, width) {
+                                let weight = self.filter.weight(sx - (px as f64 + 0.5), sy - (py as f64 + 0.5));
+                                colors[py * width + px] += color * weight;
+                                weights[py * width + px] += weight;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let mut image = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let weight = weights[y * width + x];
+                *image.get_mut(x, y) = if weight > min_weight {
+                    colors[y * width + x] * (1. / weight)
+                } else {
+                    averages[y * width + x]
+                };
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{util::float_eq, Material, PointLight, Quadric, Shape};
+
+    use super::*;
+
+    #[test]
+    fn new() {
+        let camera = Camera::new(160, 120, PI / 2.);
+        assert_eq!(camera.hsize(), 160);
+        assert_eq!(camera.vsize(), 120);
+        assert!(float_eq(camera.field_of_view(), PI / 2.));
+        assert_eq!(camera.transform(), &Matrix::identity());
+    }
+
+    #[test]
+    fn pixel_size() {
+        assert!(float_eq(Camera::new(200, 125, PI / 2.).pixel_size(), 0.01));
+        assert!(float_eq(Camera::new(125, 200, PI / 2.).pixel_size(), 0.01));
+    }
+
+    #[test]
+    fn ray_for_pixel_center() {
+        let camera = Camera::new(201, 101, PI / 2.);
+        let ray = camera.ray_for_pixel(100, 50);
+        assert_eq!(ray.origin, Point::new(0., 0., 0.));
+        assert_eq!(ray.direction, Vector::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn ray_for_pixel_corner() {
+        let camera = Camera::new(201, 101, PI / 2.);
+        let ray = camera.ray_for_pixel(0, 0);
+        assert_eq!(ray.origin, Point::new(0., 0., 0.));
+        assert_eq!(ray.direction, Vector::new(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn ray_for_pixel_transformed() {
+        let mut camera = Camera::new(201, 101, PI / 2.);
+        camera.set_transform(Matrix::rotation_y(PI / 4.) * Matrix::translation(0., -2., 5.));
+        let ray = camera.ray_for_pixel(100, 50);
+        let value = 2f64.sqrt() / 2.;
+        assert_eq!(ray.origin, Point::new(0., 2., -5.));
+        assert_eq!(ray.direction, Vector::new(value, 0., -value));
+    }
+
+    #[test]
+    fn ray_for_point() {
+        let camera = Camera::new(201, 101, PI / 2.);
+        assert_eq!(camera.ray_for_point(100.5, 50.5), camera.ray_for_pixel(100, 50));
+        assert_eq!(
+            camera.ray_for_point(0., 50.5).direction,
+            Vector::new(1., 0., -1.).normalized()
+        );
+    }
+
+    #[test]
+    fn thin_lens() {
+        let mut camera = Camera::new(201, 101, PI / 2.);
+        assert!(float_eq(camera.aperture(), 0.));
+        assert!(float_eq(camera.focal_distance(), 1.));
+        // a pinhole traces every ray through the center of the lens
+        assert_eq!(
+            camera.ray_through_lens(20., 30., 0.7, 0.2),
+            camera.ray_for_point(20., 30.)
+        );
+        camera.set_transform(Matrix::rotation_y(PI / 4.) * Matrix::translation(0., -2., 5.));
+        camera.set_aperture(0.2);
+        camera.set_focal_distance(3.);
+        let center = camera.ray_for_point(20.25, 70.75);
+        let focus = center.position(3. / -(*camera.transform() * center.direction).z);
+        for (u1, u2) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.7)] {
+            let ray = camera.ray_through_lens(20.25, 70.75, u1, u2);
+            assert!((ray.origin - camera.origin()).len() <= 0.2 + 1e-9);
+            // the rays through a position meet on the focal plane
+            let t = (focus - ray.origin).len();
+            assert_eq!(ray.position(t), focus);
+        }
+        camera.set_aperture(-1.);
+        assert!(float_eq(camera.aperture(), 0.));
+    }
+
+    #[test]
+    fn project() {
+        let mut camera = Camera::new(201, 101, PI / 2.);
+        camera.set_transform(Matrix::rotation_y(PI / 4.) * Matrix::translation(0., -2., 5.));
+        assert_eq!(camera.origin(), Point::new(0., 2., -5.));
+        let ray = camera.ray_for_point(20.25, 70.75);
+        let (x, y) = camera.project(ray.position(3.)).unwrap();
+        assert!(float_eq(x, 20.25));
+        assert!(float_eq(y, 70.75));
+        assert_eq!(camera.project(ray.position(-3.)), None);
+        assert_eq!(camera.project(Point::new(10., 2., -5.)), None);
+    }
+
+    #[test]
+    fn shutter() {
+        let mut camera = Camera::new(10, 10, PI / 2.);
+        assert_eq!(camera.shutter(), (0., 0.));
+        let mut rng = Rng::from_values(&[1.]);
+        assert_eq!(camera.sample_ray(3, 4, &mut rng).time, 0.);
+        camera.set_shutter(0.25, 0.75);
+        assert_eq!(camera.shutter(), (0.25, 0.75));
+        let times: Vec<f64> = (0..100).map(|_| camera.sample_ray(3, 4, &mut rng).time).collect();
+        assert!(times.iter().all(|time| (0.25..0.75).contains(time)));
+        assert!(times.iter().any(|&time| time < 0.3) && times.iter().any(|&time| time > 0.7));
+    }
+
+    #[test]
+    fn orthographic() {
+        let mut camera = Camera::orthographic(100, 200, 4.);
+        assert!(float_eq(camera.field_of_view(), 0.));
+        assert!(float_eq(camera.pixel_size(), 0.02));
+        camera.set_transform(Matrix::rotation_y(PI / 4.) * Matrix::translation(0., -2., 5.));
+        let a = camera.ray_for_point(10., 20.);
+        let b = camera.ray_for_point(60., 150.);
+        assert_eq!(a.direction, b.direction);
+        assert_eq!(a.direction, camera.ray_for_pixel(50, 100).direction);
+        assert!(float_eq((a.origin - b.origin).len(), 50f64.hypot(130.) * 0.02));
+        let (x, y) = camera.project(b.position(3.)).unwrap();
+        assert!(float_eq(x, 60.) && float_eq(y, 150.));
+        assert_eq!(camera.project(b.position(-3.)), None);
+    }
+
+    #[test]
+    fn fisheye() {
+        let mut camera = Camera::fisheye(200, 100, PI);
+        assert_eq!(camera.projection(), Projection::Fisheye);
+        assert_eq!(camera.ray_for_point(100., 50.).direction, Vector::new(0., 0., -1.));
+        // equidistant: the angle to the axis grows linearly
+        for x in [100., 125., 150., 175.] {
+            let angle = (-camera.ray_for_point(x, 50.).direction.z).acos();
+            assert!(float_eq(angle, (x - 100.) / 100. * PI / 2.));
+        }
+        let diagonal = camera.ray_for_point(125., 25.).direction;
+        assert!(float_eq(diagonal.x, -diagonal.y));
+        camera.set_transform(Matrix::rotation_x(0.3) * Matrix::translation(1., 2., 3.));
+        let ray = camera.ray_for_point(20.25, 70.75);
+        let (x, y) = camera.project(ray.position(2.)).unwrap();
+        assert!(float_eq(x, 20.25) && float_eq(y, 70.75));
+        // the sides behind the camera are out of the field of view
+        assert_eq!(
+            camera.project(camera.origin() + camera.ray_for_point(100., 50.).direction * -1.),
+            None
+        );
+    }
+
+    #[test]
+    fn equirectangular() {
+        let mut camera = Camera::equirectangular(40, 20);
+        assert_eq!(camera.projection(), Projection::Equirectangular);
+        assert!(float_eq(camera.field_of_view(), 2. * PI));
+        assert_eq!(camera.ray_for_point(20., 10.).direction, Vector::new(0., 0., -1.));
+        assert_eq!(camera.ray_for_point(30., 10.).direction, Vector::new(-1., 0., 0.));
+        assert_eq!(camera.ray_for_point(20., 20.).direction, Vector::new(0., -1., 0.));
+        camera.set_transform(Matrix::rotation_z(0.5) * Matrix::translation(-1., 0., 2.));
+        for (x, y) in [(3.5, 4.25), (20.5, 10.5), (37.75, 18.5)] {
+            let ray = camera.ray_for_point(x, y);
+            let (px, py) = camera.project(ray.position(5.)).unwrap();
+            assert!(float_eq(px, x) && float_eq(py, y));
+        }
+    }
+
+    #[test]
+    fn projected_lens() {
+        // rays through a thin lens converge at the focal distance
+        let mut camera = Camera::fisheye(100, 100, 1.5 * PI);
+        camera.set_aperture(0.1);
+        camera.set_focal_distance(2.);
+        let center = camera.ray_for_point(10., 30.);
+        let ray = camera.ray_through_lens(10., 30., 0.4, 0.6);
+        let focus = center.position(2.);
+        assert_eq!(ray.position((focus - ray.origin).len()), focus);
+        let mut camera = Camera::orthographic(100, 100, 2.);
+        camera.set_aperture(0.1);
+        camera.set_focal_distance(2.);
+        let center = camera.ray_for_point(10., 30.);
+        let ray = camera.ray_through_lens(10., 30., 0.4, 0.6);
+        assert_ne!(ray.origin, center.origin);
+        let focus = center.position(2.);
+        assert_eq!(ray.position((focus - ray.origin).len()), focus);
+    }
+
+    #[test]
+    fn importance() {
+        // the directions through the canvas have a total density of 1
+        let camera = Camera::new(20, 10, PI / 2.);
+        let steps = 100;
+        let mut sum = 0.;
+        for i in 0..steps {
+            for j in 0..steps {
+                let theta = PI * (i as f64 + 0.5) / steps as f64;
+                let phi = 2. * PI * (j as f64 + 0.5) / steps as f64;
+                let direction = Vector::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos());
+                sum += camera.pdf(direction) * theta.sin();
+            }
+        }
+        sum *= 2. * PI * PI / (steps * steps) as f64;
+        assert!((sum - 1.).abs() < 0.02);
+        let direction = Vector::new(0., 0., -1.);
+        assert!(float_eq(camera.importance(direction), camera.pdf(direction)));
+        assert!(float_eq(camera.pdf(direction), 0.5));
+        assert!(float_eq(camera.pdf(Vector::new(0., 0., 1.)), 0.));
+    }
+
+    #[test]
+    fn render() {
+        let mut world = World::new();
+        world.add_light(PointLight::new(Point::new(-10., 10., -10.), Color::white()));
+        let mut outer = Quadric::sphere();
+        outer.set_material(Material {
+            color: Color::new(0.8, 1.0, 0.6),
+            diffuse: 0.7,
+            specular: 0.2,
+            ..Material::default()
+        });
+        world.add_object(outer);
+        let mut inner = Quadric::sphere();
+        inner.set_transform(Matrix::scaling(0.5, 0.5, 0.5));
+        world.add_object(inner);
+        let mut camera = Camera::new(11, 11, PI / 2.);
+        let from = Point::new(0., 0., -5.);
+        let to = Point::new(0., 0., 0.);
+        let up = Vector::new(0., 1., 0.);
+        camera.set_transform(Matrix::view_transform(from, to, up));
+        let image = camera.render(&world);
+        assert_eq!(image.get(5, 5), &Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn supersampling() {
+        // an emissive ground seen up to the horizon through the middle row
+        let mut world = World::new();
+        let mut ground = Quadric::plane();
+        ground.set_material(Material {
+            emission: Color::white(),
+            ..Material::default()
+        });
+        world.add_object(ground);
+        let mut camera = Camera::new(3, 3, PI / 2.);
+        camera.set_transform(Matrix::translation(0., -1., 0.));
+        assert_eq!(camera.supersampling(), 1);
+        assert_eq!(camera.filter(), Filter::default());
+        let image = camera.render(&world);
+        assert_eq!(image.get(1, 0), &Color::black());
+        assert_eq!(image.get(1, 2), &Color::white());
+        camera.set_supersampling(4);
+        let image = camera.render(&world);
+        assert_eq!(image.get(1, 0), &Color::black());
+        assert_eq!(image.get(1, 1), &Color::new(0.5, 0.5, 0.5));
+        assert_eq!(image.get(1, 2), &Color::white());
+        // wider filters blend the neighboring rows
+        camera.set_filter(Filter::tent(1.5));
+        let image = camera.render(&world);
+        assert!(image.get(1, 0).r > 0. && image.get(1, 0).r < 0.2);
+        assert!((image.get(1, 1).r - 0.5).abs() < 0.1);
+        assert!(image.get(1, 2).r > 0.8 && image.get(1, 2).r < 1.);
+        camera.set_supersampling(0);
+        assert_eq!(camera.supersampling(), 1);
+    }
+
+    #[test]
+    fn degenerate_filters() {
+        // every pixel sees the emissive inside of a sphere
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(Material {
+            emission: Color::white(),
+            ..Material::default()
+        });
+        world.add_object(sphere);
+        let mut camera = Camera::new(8, 8, PI / 2.);
+        camera.set_supersampling(2);
+        let filters = [
+            Filter::Gaussian {
+                radius: 0.2,
+                sigma: 0.1,
+            },
+            Filter::Tent { radius: 0. },
+            Filter::mitchell_netravali(1., 0., 1.),
+        ];
+        for filter in filters {
+            camera.set_filter(filter);
+            let image = camera.render(&world);
+            assert!(image.iter().all(|color| color == &Color::white()), "{filter:?}");
+        }
+    }
+}
@@ -81,12 +81,12 @@ impl Canvas {
     }
 
     /// Iterator over pixels
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         Iter(self.pixels.iter())
     }
 
     /// Mutable iterator over pixels
-    pub fn iter_mut(&mut self) -> IterMut {
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
         IterMut(self.pixels.iter_mut())
     }
 
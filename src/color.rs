@@ -81,6 +81,19 @@ impl Color {
         let b = (self.b.clamp(0., 1.) * 255.).round() as u8;
         (r, g, b)
     }
+
+    /// Returns the relative luminance of the color (Rec. 709 weights).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Color;
+    /// assert_eq!(Color::white().luminance(), 1.);
+    /// assert_eq!(Color::black().luminance(), 0.);
+    /// ```
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
 }
 
 impl Display for Color {
@@ -207,6 +220,14 @@ mod tests {
         assert!(float_eq(color.b, 3.));
     }
 
+    #[test]
+    fn luminance() {
+        assert!(float_eq(Color::red().luminance(), 0.2126));
+        assert!(float_eq(Color::green().luminance(), 0.7152));
+        assert!(float_eq(Color::blue().luminance(), 0.0722));
+        assert!(float_eq(Color::new(0.5, 0.5, 0.5).luminance(), 0.5));
+    }
+
     #[test]
     fn eq() {
         let color1 = Color::new(1., 2., 3.);
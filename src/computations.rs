@@ -0,0 +1,115 @@
+use crate::{util::EPSILON, Intersection, Point, Ray, Shape, Vector};
+
+/// Precomputed state of an [`Intersection`] used for shading.
+#[derive(Copy, Clone, Debug)]
+pub struct Computations<'a> {
+    /// distance along the ray
+    pub t: f64,
+    /// intersected shape
+    pub object: &'a dyn Shape,
+    /// intersection point
+    pub point: Point,
+    /// intersection point slightly moved along the normal to avoid self
+    /// shadowing (acne)
+    pub over_point: Point,
+    /// intersection point slightly moved below the surface, origin of the
+    /// rays crossing it
+    pub under_point: Point,
+    /// unit vector from the intersection point towards the eye
+    pub eyev: Vector,
+    /// unit surface normal facing the eye
+    pub normalv: Vector,
+    /// `true` if the intersection occurs inside the shape
+    pub inside: bool,
+}
+
+impl<'a> Computations<'a> {
+    /// Prepares the computations of an intersection with specified ray.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Computations, Point, Quadric, Ray, Shape, Vector};
+    /// let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+    /// let shape = Quadric::sphere();
+    /// let xs = shape.intersect(&ray);
+    /// let comps = Computations::new(&xs[0], &ray);
+    /// assert_eq!(comps.point, Point::new(0., 0., -1.));
+    /// assert_eq!(comps.eyev, Vector::new(0., 0., -1.));
+    /// assert_eq!(comps.normalv, Vector::new(0., 0., -1.));
+    /// assert!(!comps.inside);
+    /// ```
+    pub fn new(intersection: &Intersection<'a>, ray: &Ray) -> Self {
+        let point = ray.position(intersection.t);
+        let eyev = -ray.direction.normalized();
+        let mut normalv = intersection.normal;
+        let inside = normalv.dot(eyev) < 0.;
+        if inside {
+            normalv = -normalv;
+        }
+        Self {
+            t: intersection.t,
+            object: intersection.object,
+            point,
+            over_point: point + normalv * EPSILON,
+            under_point: point - normalv * EPSILON,
+            eyev,
+            normalv,
+            inside,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{util::float_eq, Matrix, Quadric};
+
+    use super::*;
+
+    #[test]
+    fn outside() {
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let shape = Quadric::sphere();
+        let intersection = Intersection::new(4., &shape, Vector::new(0., 0., -1.));
+        let comps = Computations::new(&intersection, &ray);
+        assert!(float_eq(comps.t, 4.));
+        assert_eq!(comps.point, Point::new(0., 0., -1.));
+        assert_eq!(comps.eyev, Vector::new(0., 0., -1.));
+        assert_eq!(comps.normalv, Vector::new(0., 0., -1.));
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn inside() {
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let shape = Quadric::sphere();
+        let intersection = Intersection::new(1., &shape, Vector::new(0., 0., 1.));
+        let comps = Computations::new(&intersection, &ray);
+        assert_eq!(comps.point, Point::new(0., 0., 1.));
+        assert_eq!(comps.eyev, Vector::new(0., 0., -1.));
+        assert_eq!(comps.normalv, Vector::new(0., 0., -1.));
+        assert!(comps.inside);
+    }
+
+    #[test]
+    fn over_point() {
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let mut shape = Quadric::sphere();
+        shape.set_transform(Matrix::translation(0., 0., 1.));
+        let xs = shape.intersect(&ray);
+        let comps = Computations::new(&xs[0], &ray);
+        assert!(comps.over_point.z < -EPSILON / 2.);
+        assert!(comps.point.z > comps.over_point.z);
+    }
+
+    #[test]
+    fn under_point() {
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let mut shape = Quadric::sphere();
+        shape.set_transform(Matrix::translation(0., 0., 1.));
+        let xs = shape.intersect(&ray);
+        let comps = Computations::new(&xs[0], &ray);
+        assert!(comps.under_point.z > EPSILON / 2.);
+        assert!(comps.point.z < comps.under_point.z);
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{util::Rng, Matrix, Point, Ray};
+
+/// Spatially varying density of a [`Medium`](crate::Medium) scaling its
+/// absorption and scattering coefficients (clouds, smoke, explosions).
+///
+/// Densities are defined in their own space and placed in the world by their
+/// transform, like shapes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Density {
+    source: Source,
+    transform: Matrix,
+    inverse: Matrix,
+}
+
+/// Definition of a [`Density`].
+#[derive(Clone, Debug, PartialEq)]
+enum Source {
+    /// samples at the corners of the voxels of a grid covering the unit cube
+    Grid {
+        width: usize,
+        height: usize,
+        depth: usize,
+        values: Vec<f64>,
+        max: f64,
+    },
+    /// sum of octaves of gradient noise
+    Noise { octaves: usize, permutation: Vec<u8> },
+}
+
+impl Density {
+    /// Creates a `Density` from a grid of `width` by `height` by `depth`
+    /// samples given by `density(u, v, w)` with `u`, `v` and `w` in `[0,1]`.
+    ///
+    /// The grid covers the unit cube `[0,1]` along the `x`, `y` and `z` axes,
+    /// the density is interpolated trilinearly between the samples and is
+    /// zero outside the cube. Negative samples are clamped to zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width`, `height` or `depth` is lower than 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Density, Point};
+    /// let density = Density::grid(3, 3, 3, |u, _, _| u);
+    /// assert_eq!(density.at(Point::new(0.25, 0.5, 0.5)), 0.25);
+    /// assert_eq!(density.at(Point::new(2., 0.5, 0.5)), 0.);
+    /// assert_eq!(density.max(), 1.);
+    /// ```
+    pub fn grid(width: usize, height: usize, depth: usize, density: impl Fn(f64, f64, f64) -> f64) -> Self {
+        assert!(width >= 2);
+        assert!(height >= 2);
+        assert!(depth >= 2);
+        let mut values = Vec::with_capacity(width * height * depth);
+        for z in 0..depth {
+            for y in 0..height {
+                for x in 0..width {
+                    let u = x as f64 / (width - 1) as f64;
+                    let v = y as f64 / (height - 1) as f64;
+                    let w = z as f64 / (depth - 1) as f64;
+                    values.push(density(u, v, w).max(0.));
+                }
+            }
+        }
+        let max = values.iter().copied().fold(0., f64::max);
+        Self::from_source(Source::Grid {
+            width,
+            height,
+            depth,
+            values,
+            max,
+        })
+    }
+
+    /// Creates a `Density` from fractal gradient noise: `octaves` layers of
+    /// Perlin noise of doubling frequency and halving amplitude, remapped to
+    /// `[0,1]`.
+    ///
+    /// The noise varies over a unit distance and fills the cube `[-1,1]`
+    /// along the `x`, `y` and `z` axes, the density is zero outside the cube.
+    /// The seed selects the pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Density, Point};
+    /// let density = Density::noise(4, 1);
+    /// let value = density.at(Point::new(0.3, 0.7, -0.2));
+    /// assert!(value >= 0. && value <= 1.);
+    /// assert_ne!(value, Density::noise(4, 2).at(Point::new(0.3, 0.7, -0.2)));
+    /// assert_eq!(density.at(Point::new(0.3, 1.7, -0.2)), 0.);
+    /// ```
+    pub fn noise(octaves: usize, seed: u64) -> Self {
+        let mut permutation: Vec<u8> = (0..=255).collect();
+        let mut rng = Rng::from_values(&[seed as f64]);
+        for i in (1..permutation.len()).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            permutation.swap(i, j);
+        }
+        Self::from_source(Source::Noise {
+            octaves: octaves.max(1),
+            permutation,
+        })
+    }
+
+    fn from_source(source: Source) -> Self {
+        Self {
+            source,
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+        }
+    }
+
+    /// Get the transformation from the space of the density to the world.
+    #[must_use]
+    pub fn transform(&self) -> &Matrix {
+        &self.transform
+    }
+
+    /// Sets the transformation from the space of the density to the world.
+    pub fn set_transform(&mut self, transform: Matrix) {
+        self.inverse = transform.inverse().expect("density transform must be invertible");
+        self.transform = transform;
+    }
+
+    /// Returns the density at a point of the world.
+    pub fn at(&self, point: Point) -> f64 {
+        let point = self.inverse * point;
+        match &self.source {
+            Source::Grid {
+                width,
+                height,
+                depth,
+                values,
+                ..
+            } => {
+                let inside = |c: f64| (0. ..=1.).contains(&c);
+                if !inside(point.x) || !inside(point.y) || !inside(point.z) {
+                    return 0.;
+                }
+                // index of the voxel and position within it along an axis
+                let cell = |c: f64, size: usize| {
+                    let c = c * (size - 1) as f64;
+                    let i = (c.floor() as usize).min(size - 2);
+                    (i, c - i as f64)
+                };
+                let (x, fx) = cell(point.x, *width);
+                let (y, fy) = cell(point.y, *height);
+                let (z, fz) = cell(point.z, *depth);
+                let value = |dx: usize, dy: usize, dz: usize| values[((z + dz) * height + y + dy) * width + x + dx];
+                let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+                let plane = |dz: usize| {
+                    let near = lerp(fx, value(0, 0, dz), value(1, 0, dz));
+                    let far = lerp(fx, value(0, 1, dz), value(1, 1, dz));
+                    lerp(fy, near, far)
+                };
+                lerp(fz, plane(0), plane(1))
+            }
+            Source::Noise { octaves, permutation } => {
+                let inside = |c: f64| (-1. ..=1.).contains(&c);
+                if !inside(point.x) || !inside(point.y) || !inside(point.z) {
+                    return 0.;
+                }
+                let mut sum = 0.;
+                let mut total = 0.;
+                for octave in 0..*octaves {
+                    let frequency = (1 << octave) as f64;
+                    let amplitude = 1. / frequency;
+                    sum += amplitude
+                        * perlin(
+                            permutation,
+                            point.x * frequency,
+                            point.y * frequency,
+                            point.z * frequency,
+                        );
+                    total += amplitude;
+                }
+                (0.5 + 0.5 * sum / total).clamp(0., 1.)
+            }
+        }
+    }
+
+    /// Returns the maximum density.
+    pub fn max(&self) -> f64 {
+        match &self.source {
+            Source::Grid { max, .. } => *max,
+            Source::Noise { .. } => 1.,
+        }
+    }
+
+    /// Returns the range of distances along a ray where the density may not
+    /// be zero (`None` if the ray misses it).
+    pub(crate) fn extent(&self, ray: &Ray) -> Option<(f64, f64)> {
+        let (min, max) = match self.source {
+            Source::Grid { .. } => (0., 1.),
+            Source::Noise { .. } => (-1., 1.),
+        };
+        let ray = ray.transform(self.inverse);
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+        let (mut tmin, mut tmax) = (f64::NEG_INFINITY, f64::INFINITY);
+        for axis in 0..3 {
+            if direction[axis] == 0. {
+                if !(min..=max).contains(&origin[axis]) {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (min - origin[axis]) / direction[axis];
+            let t1 = (max - origin[axis]) / direction[axis];
+            tmin = tmin.max(t0.min(t1));
+            tmax = tmax.min(t0.max(t1));
+        }
+        (tmin <= tmax).then_some((tmin, tmax))
+    }
+}
+
+/// Returns the improved Perlin noise (in about `[-1,1]`) at a point.
+fn perlin(permutation: &[u8], x: f64, y: f64, z: f64) -> f64 {
+    let hash = |i: i64| permutation[(i & 255) as usize] as i64;
+    let fade = |t: f64| t * t * t * (t * (t * 6. - 15.) + 10.);
+    let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+    let gradient = |hash: i64, x: f64, y: f64, z: f64| {
+        let h = hash & 15;
+        let u = if h < 8 { x } else { y };
+        let v = match h {
+            0..=3 => y,
+            12 | 14 => x,
+            _ => z,
+        };
+        (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+    };
+    let (xi, yi, zi) = (x.floor(), y.floor(), z.floor());
+    let (x, y, z) = (x - xi, y - yi, z - zi);
+    let (xi, yi, zi) = (xi as i64, yi as i64, zi as i64);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+    let a = hash(xi) + yi;
+    let (aa, ab) = (hash(a) + zi, hash(a + 1) + zi);
+    let b = hash(xi + 1) + yi;
+    let (ba, bb) = (hash(b) + zi, hash(b + 1) + zi);
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, gradient(hash(aa), x, y, z), gradient(hash(ba), x - 1., y, z)),
+            lerp(
+                u,
+                gradient(hash(ab), x, y - 1., z),
+                gradient(hash(bb), x - 1., y - 1., z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                gradient(hash(aa + 1), x, y, z - 1.),
+                gradient(hash(ba + 1), x - 1., y, z - 1.),
+            ),
+            lerp(
+                u,
+                gradient(hash(ab + 1), x, y - 1., z - 1.),
+                gradient(hash(bb + 1), x - 1., y - 1., z - 1.),
+            ),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{util::float_eq, Vector};
+
+    use super::*;
+
+    #[test]
+    fn grid() {
+        let density = Density::grid(2, 3, 2, |u, v, w| u + 2. * v - w);
+        assert!(float_eq(density.max(), 3.));
+        assert!(float_eq(density.at(Point::new(0.5, 0.5, 0.)), 1.5));
+        assert!(float_eq(density.at(Point::new(0.5, 0.25, 0.)), 1.));
+        // clamped samples
+        assert!(float_eq(density.at(Point::new(0., 0., 1.)), 0.));
+        assert!(float_eq(density.at(Point::new(0., 0., 1.1)), 0.));
+    }
+
+    #[test]
+    fn transform() {
+        let mut density = Density::grid(2, 2, 2, |u, _, _| u);
+        density.set_transform(Matrix::translation(-1., -1., -1.) * Matrix::scaling(2., 2., 2.));
+        assert_eq!(
+            density.transform(),
+            &(Matrix::translation(-1., -1., -1.) * Matrix::scaling(2., 2., 2.))
+        );
+        assert!(float_eq(density.at(Point::new(0., 0., 0.)), 0.5));
+        assert!(float_eq(density.at(Point::new(0.5, -0.9, 0.9)), 0.75));
+        assert!(float_eq(density.at(Point::new(1.5, 0., 0.)), 0.));
+    }
+
+    #[test]
+    fn extent() {
+        let mut density = Density::grid(2, 2, 2, |_, _, _| 1.);
+        density.set_transform(Matrix::scaling(2., 2., 2.));
+        let ray = Ray::new(Point::new(1., 1., -5.), Vector::new(0., 0., 1.));
+        let (t0, t1) = density.extent(&ray).unwrap();
+        assert!(float_eq(t0, 5.));
+        assert!(float_eq(t1, 7.));
+        let miss = Ray::new(Point::new(3., 1., -5.), Vector::new(0., 0., 1.));
+        assert_eq!(density.extent(&miss), None);
+        let mut noise = Density::noise(1, 0);
+        noise.set_transform(Matrix::scaling(4., 4., 4.));
+        let (t0, t1) = noise.extent(&miss).unwrap();
+        assert!(float_eq(t0, 1.));
+        assert!(float_eq(t1, 9.));
+    }
+
+    #[test]
+    fn noise() {
+        let density = Density::noise(3, 7);
+        assert_eq!(density, Density::noise(3, 7));
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        let coordinate = |i: usize| i as f64 / 10. - 1.;
+        for (x, y, z) in (0..=20).flat_map(|x| (0..=20).flat_map(move |y| (0..=20).map(move |z| (x, y, z)))) {
+            let value = density.at(Point::new(coordinate(x), coordinate(y), coordinate(z)));
+            min = min.min(value);
+            max = max.max(value);
+        }
+        assert!(min >= 0. && max <= density.max());
+        assert!(max - min > 0.3);
+        // noise is continuous
+        let a = density.at(Point::new(0.2, 0.4, -0.6));
+        let b = density.at(Point::new(0.2001, 0.4, -0.6));
+        assert!((a - b).abs() < 1e-2);
+    }
+}
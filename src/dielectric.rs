@@ -0,0 +1,226 @@
+use std::f64::consts::PI;
+
+use crate::Dispersion;
+
+/// Smooth interface of a transparent material (glass, water, diamond)
+/// reflecting and refracting the light according to the Fresnel equations.
+///
+/// The surface may be coated by a thin transparent film whose reflections
+/// interfere with those of the surface (soap bubbles, oil slicks,
+/// anti-reflective coatings).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Dielectric {
+    /// refractive index of the material depending on the wavelength
+    pub dispersion: Dispersion,
+    /// thickness in nanometers of the film coating the surface (`0` for an
+    /// uncoated surface)
+    pub film_thickness: f64,
+    /// refractive index of the film
+    pub film_index: f64,
+}
+
+/// Complex number for the amplitudes of the waves.
+#[derive(Copy, Clone, Debug)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Dielectric {
+    /// Creates an uncoated `Dielectric`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Dielectric, Dispersion};
+    /// let glass = Dielectric::new(Dispersion::constant(1.5));
+    /// // 4% of the light is reflected at normal incidence
+    /// assert!((glass.reflectance(1., 1., 1.5, 550.) - 0.04).abs() < 1e-12);
+    /// ```
+    pub fn new(dispersion: Dispersion) -> Self {
+        Self {
+            dispersion,
+            film_thickness: 0.,
+            film_index: 1.,
+        }
+    }
+
+    /// Creates a `Dielectric` coated by a film of specified thickness (in
+    /// nanometers) and refractive index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Dielectric, Dispersion};
+    /// // quarter-wave anti-reflective coating for green light
+    /// let index = 1.5f64.sqrt();
+    /// let lens = Dielectric::coated(Dispersion::constant(1.5), 550. / (4. * index), index);
+    /// assert!(lens.reflectance(1., 1., 1.5, 550.) < 1e-12);
+    /// assert!(lens.reflectance(1., 1., 1.5, 400.) > 0.001);
+    /// ```
+    pub fn coated(dispersion: Dispersion, film_thickness: f64, film_index: f64) -> Self {
+        Self {
+            dispersion,
+            film_thickness,
+            film_index,
+        }
+    }
+
+    /// Returns the refractive index of the material at a wavelength (in
+    /// nanometers).
+    pub fn refractive_index(&self, wavelength: f64) -> f64 {
+        self.dispersion.refractive_index(wavelength)
+    }
+
+    /// Returns the fraction of unpolarized light of a wavelength (in
+    /// nanometers) reflected by the surface, for light arriving with
+    /// specified cosine to the normal from a medium of index `n1` towards a
+    /// medium of index `n2`.
+    ///
+    /// Total internal reflection reflects all the light.
+    pub fn reflectance(&self, cosine: f64, n1: f64, n2: f64, wavelength: f64) -> f64 {
+        let cosine = cosine.clamp(0., 1.);
+        // n sin θ is conserved across the layers
+        let invariant = n1 * (1. - cosine * cosine).sqrt();
+        let cos = |n: f64| {
+            let sine = invariant / n;
+            Complex::new(1. - sine * sine, 0.).sqrt()
+        };
+        let (cos1, cos3) = (Complex::new(cosine, 0.), cos(n2));
+        let film = self.film_thickness > 0.;
+        let mut sum = 0.;
+        for parallel in [false, true] {
+            let r = if film {
+                let n = self.film_index;
+                let cos2 = cos(n);
+                let r12 = amplitude(n1, cos1, n, cos2, parallel);
+                let r23 = amplitude(n, cos2, n2, cos3, parallel);
+                // phase difference of a round trip through the film
+                let phase = cos2.scale(4. * PI * n * self.film_thickness / wavelength);
+                let delay = Complex::new(0., 1.).mul(phase).exp();
+                let numerator = r12.add(r23.mul(delay));
+                let denominator = Complex::new(1., 0.).add(r12.mul(r23).mul(delay));
+                numerator.div(denominator)
+            } else {
+                amplitude(n1, cos1, n2, cos3, parallel)
+            };
+            sum += r.norm2();
+        }
+        (sum / 2.).min(1.)
+    }
+}
+
+/// Returns the Fresnel amplitude reflection coefficient of an interface
+/// between media of indices `n1` and `n2` for the polarization
+/// perpendicular or parallel to the plane of incidence.
+fn amplitude(n1: f64, cos1: Complex, n2: f64, cos2: Complex, parallel: bool) -> Complex {
+    let (a, b) = if parallel {
+        (cos1.scale(n2), cos2.scale(n1))
+    } else {
+        (cos1.scale(n1), cos2.scale(n2))
+    };
+    let numerator = a.add(b.scale(-1.));
+    let denominator = a.add(b);
+    if denominator.norm2() == 0. {
+        return Complex::new(1., 0.);
+    }
+    numerator.div(denominator)
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+
+    fn div(self, other: Self) -> Self {
+        let norm2 = other.norm2();
+        Self::new(
+            (self.re * other.re + self.im * other.im) / norm2,
+            (self.im * other.re - self.re * other.im) / norm2,
+        )
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    fn norm2(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Returns the principal square root.
+    fn sqrt(self) -> Self {
+        let norm = self.norm2().sqrt();
+        let re = ((norm + self.re) / 2.).max(0.).sqrt();
+        let im = ((norm - self.re) / 2.).max(0.).sqrt();
+        Self::new(re, if self.im < 0. { -im } else { im })
+    }
+
+    fn exp(self) -> Self {
+        let magnitude = self.re.exp();
+        Self::new(magnitude * self.im.cos(), magnitude * self.im.sin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresnel() {
+        let glass = Dielectric::new(Dispersion::constant(1.5));
+        assert!((glass.reflectance(1., 1., 1.5, 550.) - 0.04).abs() < 1e-12);
+        assert!((glass.reflectance(1., 1.5, 1., 550.) - 0.04).abs() < 1e-12);
+        // grazing incidence reflects everything
+        assert!((glass.reflectance(0., 1., 1.5, 550.) - 1.).abs() < 1e-9);
+        // Brewster angle: only the perpendicular polarization is reflected
+        let brewster = 1.5f64.atan();
+        let cosine = brewster.cos();
+        let sine = brewster.sin();
+        let cos_t = (1. - (sine / 1.5).powi(2)).sqrt();
+        let rs = ((cosine - 1.5 * cos_t) / (cosine + 1.5 * cos_t)).powi(2);
+        assert!((glass.reflectance(cosine, 1., 1.5, 550.) - rs / 2.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn total_internal_reflection() {
+        let glass = Dielectric::new(Dispersion::constant(1.5));
+        // critical angle of glass is about 41.8°
+        let cosine = 40f64.to_radians().cos();
+        assert!(glass.reflectance(cosine, 1.5, 1., 550.) < 1.);
+        let cosine = 45f64.to_radians().cos();
+        assert!((glass.reflectance(cosine, 1.5, 1., 550.) - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn thin_film() {
+        // soap bubble: 300 nm film of water in air, reflectance at normal
+        // incidence R = 2r²(1 - cos δ) / (1 + r⁴ - 2r² cos δ)
+        let n = 1.33;
+        let bubble = Dielectric::coated(Dispersion::constant(1.), 300., n);
+        let r = (n - 1.) / (n + 1.);
+        for wavelength in [400., 500., 600., 700.] {
+            let delta = 4. * PI * n * 300. / wavelength;
+            let expected = 2. * r * r * (1. - delta.cos()) / (1. + r.powi(4) - 2. * r * r * delta.cos());
+            assert!((bubble.reflectance(1., 1., 1., wavelength) - expected).abs() < 1e-12);
+        }
+        // a film of zero thickness is no film
+        let film = Dielectric::coated(Dispersion::constant(1.5), 0., 1.33);
+        let bare = Dielectric::new(Dispersion::constant(1.5));
+        assert_eq!(
+            film.reflectance(0.7, 1., 1.5, 500.),
+            bare.reflectance(0.7, 1., 1.5, 500.)
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{Color, Light, LightSample, Point, Vector};
+
+/// Infinitely distant light whose rays all travel along the same direction
+/// (e.g. the sun).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DirectionalLight {
+    /// direction the light travels along
+    pub direction: Vector,
+    /// intensity of the light
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    /// Creates a `DirectionalLight` from specified direction and intensity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, DirectionalLight, Light, Point, Vector};
+    /// let light = DirectionalLight::new(Vector::new(0., -1., 0.), Color::white());
+    /// let samples = light.samples(Point::new(1., 2., 3.));
+    /// assert_eq!(samples[0].direction, Vector::new(0., 1., 0.));
+    /// assert_eq!(samples[0].distance, f64::INFINITY);
+    /// ```
+    pub fn new(direction: Vector, intensity: Color) -> Self {
+        Self { direction, intensity }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn samples(&self, _point: Point) -> Vec<LightSample> {
+        vec![LightSample {
+            direction: -self.direction.normalized(),
+            distance: f64::INFINITY,
+            intensity: self.intensity,
+            pdf: f64::INFINITY,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples() {
+        let light = DirectionalLight::new(Vector::new(1., -1., 0.), Color::new(0.5, 0.5, 0.5));
+        for point in [Point::new(0., 0., 0.), Point::new(100., -20., 3.)] {
+            let samples = light.samples(point);
+            assert_eq!(samples.len(), 1);
+            assert_eq!(samples[0].direction, Vector::new(-1., 1., 0.).normalized());
+            assert!(samples[0].distance.is_infinite());
+            assert_eq!(samples[0].intensity, Color::new(0.5, 0.5, 0.5));
+        }
+    }
+}
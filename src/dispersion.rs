@@ -0,0 +1,100 @@
+/// Variation of the refractive index of a transparent material with the
+/// wavelength of the light, splitting white light into its colors.
+///
+/// Wavelengths are given in nanometers, the coefficients of the models use
+/// wavelengths in micrometers as is customary in optical glass catalogs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Dispersion {
+    /// Cauchy's equation `n = a + b / λ²`
+    Cauchy {
+        /// refractive index at infinite wavelength
+        a: f64,
+        /// strength of the dispersion (µm²)
+        b: f64,
+    },
+    /// Sellmeier equation `n² = 1 + Σ bᵢ λ² / (λ² - cᵢ)`
+    Sellmeier {
+        /// strengths of the absorption resonances
+        b: [f64; 3],
+        /// squared wavelengths of the absorption resonances (µm²)
+        c: [f64; 3],
+    },
+}
+
+impl Dispersion {
+    /// Borosilicate crown glass (Schott N-BK7).
+    pub const BK7: Self = Self::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+
+    /// Dense flint glass (Schott N-SF11), strongly dispersive.
+    pub const SF11: Self = Self::Sellmeier {
+        b: [1.73759695, 0.313747346, 1.89878101],
+        c: [0.013188707, 0.0623068142, 155.23629],
+    };
+
+    /// Creates a `Dispersion` of constant refractive index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Dispersion;
+    /// let water = Dispersion::constant(1.333);
+    /// assert_eq!(water.refractive_index(450.), 1.333);
+    /// assert_eq!(water.refractive_index(650.), 1.333);
+    /// ```
+    pub fn constant(refractive_index: f64) -> Self {
+        Self::Cauchy {
+            a: refractive_index,
+            b: 0.,
+        }
+    }
+
+    /// Returns the refractive index at a wavelength (in nanometers).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Dispersion;
+    /// // refractive index of BK7 glass at the helium d line
+    /// let n = Dispersion::BK7.refractive_index(587.56);
+    /// assert!((n - 1.5168).abs() < 1e-4);
+    /// // blue light is refracted more than red light
+    /// assert!(Dispersion::BK7.refractive_index(450.) > Dispersion::BK7.refractive_index(650.));
+    /// ```
+    pub fn refractive_index(&self, wavelength: f64) -> f64 {
+        let micrometers = wavelength * 1e-3;
+        let squared = micrometers * micrometers;
+        match self {
+            Self::Cauchy { a, b } => a + b / squared,
+            Self::Sellmeier { b, c } => {
+                let sum: f64 = b.iter().zip(c.iter()).map(|(b, c)| b * squared / (squared - c)).sum();
+                (1. + sum).sqrt()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::float_eq;
+
+    use super::*;
+
+    #[test]
+    fn cauchy() {
+        // fused silica approximation
+        let silica = Dispersion::Cauchy { a: 1.4580, b: 0.00354 };
+        assert!(float_eq(silica.refractive_index(1000.), 1.4580 + 0.00354));
+        assert!(float_eq(silica.refractive_index(500.), 1.4580 + 0.00354 * 4.));
+    }
+
+    #[test]
+    fn sellmeier() {
+        // catalog values at the hydrogen F and C lines
+        assert!((Dispersion::BK7.refractive_index(486.13) - 1.52238).abs() < 1e-4);
+        assert!((Dispersion::BK7.refractive_index(656.27) - 1.51432).abs() < 1e-4);
+        assert!((Dispersion::SF11.refractive_index(587.56) - 1.78472).abs() < 1e-4);
+    }
+}
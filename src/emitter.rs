@@ -0,0 +1,214 @@
+use std::f64::consts::PI;
+
+use crate::{util::orthonormal_basis, Color, Point, SpotLight, Vector};
+
+/// Physically based description of a light source with a position, used by
+/// the integrators tracing paths from the lights.
+///
+/// Emitters follow the inverse-square law, the radiant intensity of the light
+/// is emitted in every direction without cosine falloff (as the lights of the
+/// world do).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Emitter {
+    shape: EmitterShape,
+    intensity: Color,
+}
+
+/// Geometry of an [`Emitter`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum EmitterShape {
+    Point(Point),
+    Spot(SpotLight),
+    Area { corner: Point, uvec: Vector, vvec: Vector },
+}
+
+impl Emitter {
+    /// Creates an `Emitter` located at a single point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Emitter, Point, Vector};
+    /// let emitter = Emitter::point(Point::new(0., 1., 0.), Color::white());
+    /// assert_eq!(emitter.sample_position(0.5, 0.5), (Point::new(0., 1., 0.), 1.));
+    /// assert_eq!(emitter.intensity(Point::new(0., 1., 0.), Vector::new(1., 0., 0.)), Color::white());
+    /// ```
+    pub fn point(position: Point, intensity: Color) -> Self {
+        Self {
+            shape: EmitterShape::Point(position),
+            intensity,
+        }
+    }
+
+    /// Creates an `Emitter` from a spot light, the intensity falls off
+    /// between the cones of the light.
+    pub fn spot(light: SpotLight) -> Self {
+        Self {
+            shape: EmitterShape::Spot(light),
+            intensity: light.intensity,
+        }
+    }
+
+    /// Creates an `Emitter` from a parallelogram, the intensity is spread
+    /// uniformly over its area.
+    pub fn area(corner: Point, uvec: Vector, vvec: Vector, intensity: Color) -> Self {
+        Self {
+            shape: EmitterShape::Area { corner, uvec, vvec },
+            intensity: intensity * (1. / uvec.cross(vvec).len()),
+        }
+    }
+
+    /// Returns an emitter chosen uniformly from a uniform value in `[0,1)`
+    /// and the probability of choosing it, `None` if there are no emitters.
+    pub(crate) fn choose(emitters: &[Emitter], u: f64) -> Option<(Emitter, f64)> {
+        if emitters.is_empty() {
+            return None;
+        }
+        let index = ((u * emitters.len() as f64) as usize).min(emitters.len() - 1);
+        Some((emitters[index], 1. / emitters.len() as f64))
+    }
+
+    /// Returns the unit normal of area emitters.
+    pub fn normal(&self) -> Option<Vector> {
+        match self.shape {
+            EmitterShape::Area { uvec, vvec, .. } => Some(uvec.cross(vvec).normalized()),
+            _ => None,
+        }
+    }
+
+    /// Returns a position on the emitter from two uniform values in `[0,1)`
+    /// and its density per unit area (`1` for emitters located at a point).
+    pub fn sample_position(&self, u1: f64, u2: f64) -> (Point, f64) {
+        match self.shape {
+            EmitterShape::Point(position) => (position, 1.),
+            EmitterShape::Spot(light) => (light.position, 1.),
+            EmitterShape::Area { corner, uvec, vvec } => (corner + uvec * u1 + vvec * u2, 1. / uvec.cross(vvec).len()),
+        }
+    }
+
+    /// Returns an emitted direction from two uniform values in `[0,1)`,
+    /// uniformly distributed over the sphere (or the outer cone of spot
+    /// lights).
+    pub fn sample_direction(&self, u1: f64, u2: f64) -> Vector {
+        let (axis, cos_max) = match self.shape {
+            EmitterShape::Spot(light) => (light.direction.normalized(), light.outer_angle.cos()),
+            _ => (Vector::new(0., 0., 1.), -1.),
+        };
+        let cosine = 1. - u1 * (1. - cos_max);
+        let sine = (1. - cosine * cosine).max(0.).sqrt();
+        let phi = 2. * PI * u2;
+        let (tangent, bitangent) = orthonormal_basis(axis);
+        tangent * (sine * phi.cos()) + bitangent * (sine * phi.sin()) + axis * cosine
+    }
+
+    /// Returns the density (per steradian) of emitting specified direction.
+    pub fn direction_pdf(&self, direction: Vector) -> f64 {
+        match self.shape {
+            EmitterShape::Spot(light) => {
+                let cos_max = light.outer_angle.cos();
+                if direction.normalized().dot(light.direction.normalized()) < cos_max {
+                    return 0.;
+                }
+                1. / (2. * PI * (1. - cos_max))
+            }
+            _ => 1. / (4. * PI),
+        }
+    }
+
+    /// Returns the intensity emitted from a position of the emitter in
+    /// specified direction (per unit of area for area emitters).
+    pub fn intensity(&self, position: Point, direction: Vector) -> Color {
+        match self.shape {
+            EmitterShape::Spot(light) => self.intensity * light.falloff(position + direction),
+            _ => self.intensity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::{float_eq, Rng};
+
+    use super::*;
+
+    #[test]
+    fn point() {
+        let emitter = Emitter::point(Point::new(1., 2., 3.), Color::red());
+        assert_eq!(emitter.normal(), None);
+        assert_eq!(emitter.sample_position(0.1, 0.9), (Point::new(1., 2., 3.), 1.));
+        assert!(float_eq(
+            emitter.direction_pdf(Vector::new(0., -1., 0.)),
+            1. / (4. * PI)
+        ));
+        assert_eq!(
+            emitter.intensity(Point::new(1., 2., 3.), Vector::new(0., 1., 0.)),
+            Color::red()
+        );
+    }
+
+    #[test]
+    fn spot() {
+        let light = SpotLight::new(
+            Point::new(0., 10., 0.),
+            Vector::new(0., -1., 0.),
+            PI / 8.,
+            PI / 4.,
+            Color::white(),
+        );
+        let emitter = Emitter::spot(light);
+        let position = Point::new(0., 10., 0.);
+        assert_eq!(emitter.intensity(position, Vector::new(0., -1., 0.)), Color::white());
+        assert_eq!(emitter.intensity(position, Vector::new(1., 0., 0.)), Color::black());
+        assert!(float_eq(emitter.direction_pdf(Vector::new(1., 0., 0.)), 0.));
+        let expected = 1. / (2. * PI * (1. - (PI / 4.).cos()));
+        assert!(float_eq(emitter.direction_pdf(Vector::new(0., -1., 0.)), expected));
+        let mut rng = Rng::from_values(&[1.]);
+        for _ in 0..100 {
+            let direction = emitter.sample_direction(rng.next_f64(), rng.next_f64());
+            assert!(float_eq(direction.len(), 1.));
+            assert!(emitter.direction_pdf(direction) > 0.);
+        }
+    }
+
+    #[test]
+    fn area() {
+        let emitter = Emitter::area(
+            Point::new(-1., 2., -1.),
+            Vector::new(2., 0., 0.),
+            Vector::new(0., 0., 2.),
+            Color::white(),
+        );
+        assert_eq!(emitter.normal(), Some(Vector::new(0., -1., 0.)));
+        let direction = Vector::new(0., -1., 0.);
+        assert_eq!(
+            emitter.intensity(Point::new(0., 2., 0.), direction),
+            Color::white() * 0.25
+        );
+        let (position, pdf) = emitter.sample_position(0.5, 0.25);
+        assert_eq!(position, Point::new(0., 2., -0.5));
+        assert!(float_eq(pdf, 0.25));
+    }
+
+    #[test]
+    fn choose() {
+        let a = Emitter::point(Point::new(0., 0., 0.), Color::red());
+        let b = Emitter::point(Point::new(1., 0., 0.), Color::blue());
+        assert_eq!(Emitter::choose(&[], 0.5), None);
+        assert_eq!(Emitter::choose(&[a, b], 0.25), Some((a, 0.5)));
+        assert_eq!(Emitter::choose(&[a, b], 0.75), Some((b, 0.5)));
+    }
+
+    #[test]
+    fn sphere_directions() {
+        let emitter = Emitter::point(Point::new(0., 0., 0.), Color::white());
+        let mut rng = Rng::from_values(&[2.]);
+        let count = 10_000;
+        let mut sum = Vector::new(0., 0., 0.);
+        for _ in 0..count {
+            let direction = emitter.sample_direction(rng.next_f64(), rng.next_f64());
+            assert!(float_eq(direction.len(), 1.));
+            sum += direction;
+        }
+        assert!((sum / count as f64).len() < 0.03);
+    }
+}
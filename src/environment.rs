@@ -0,0 +1,320 @@
+use std::f64::consts::PI;
+
+use crate::{util::Rng, Canvas, Color, Light, LightSample, Matrix, Point, Vector};
+
+/// Distant lighting surrounding the scene given by an equirectangular
+/// (latitude/longitude) image.
+///
+/// The top row of the image maps to the `+y` direction, the bottom row to
+/// `-y` and the center of the image looks towards `-z`.
+///
+/// The environment is seen by rays escaping the scene and illuminates the
+/// scene as a [`Light`] whose samples are importance sampled according to
+/// the luminance of the image.
+#[derive(Clone, Debug)]
+pub struct Environment {
+    map: Canvas,
+    intensity: f64,
+    samples: usize,
+    transform: Matrix,
+    inverse: Matrix,
+    /// cumulative distribution of the rows
+    marginal: Vec<f64>,
+    /// cumulative distribution of the columns within each row
+    conditional: Vec<Vec<f64>>,
+}
+
+impl Environment {
+    const SAMPLES: usize = 16;
+
+    /// Creates an `Environment` from an equirectangular image.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Canvas, Color, Environment, Vector};
+    /// let mut map = Canvas::new(4, 2);
+    /// *map.get_mut(1, 0) = Color::red();
+    /// let environment = Environment::new(map);
+    /// assert_eq!(environment.radiance(Vector::new(-1., 1., 0.)), Color::red());
+    /// assert_eq!(environment.radiance(Vector::new(0., -1., 0.)), Color::black());
+    /// ```
+    pub fn new(map: Canvas) -> Self {
+        assert!(map.width() > 0);
+        assert!(map.height() > 0);
+        let (width, height) = (map.width(), map.height());
+        // pixel weights account for the solid angle shrinking towards poles
+        let mut conditional = Vec::with_capacity(height);
+        let mut marginal = Vec::with_capacity(height);
+        let mut total = 0.;
+        for y in 0..height {
+            let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+            let mut row = Vec::with_capacity(width);
+            let mut sum = 0.;
+            for x in 0..width {
+                sum += map.get(x, y).luminance().max(0.) * sin_theta;
+                row.push(sum);
+            }
+            total += sum;
+            marginal.push(total);
+            conditional.push(row);
+        }
+        Self {
+            map,
+            intensity: 1.,
+            samples: Self::SAMPLES,
+            transform: Matrix::identity(),
+            inverse: Matrix::identity(),
+            marginal,
+            conditional,
+        }
+    }
+
+    /// Get the equirectangular image.
+    #[must_use]
+    pub fn map(&self) -> &Canvas {
+        &self.map
+    }
+
+    /// Get the factor scaling the radiance of the image.
+    #[must_use]
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+
+    /// Sets the factor scaling the radiance of the image.
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+
+    /// Get the number of light samples used for shading.
+    #[must_use]
+    pub fn sample_count(&self) -> usize {
+        self.samples
+    }
+
+    /// Sets the number of light samples used for shading.
+    pub fn set_sample_count(&mut self, samples: usize) {
+        self.samples = samples;
+    }
+
+    /// Sets the orientation of the environment (e.g. a rotation around the
+    /// `y` axis).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the transform is not invertible.
+    pub fn set_transform(&mut self, transform: Matrix) {
+        self.inverse = transform.inverse().expect("environment transform must be invertible");
+        self.transform = transform;
+    }
+
+    /// Returns the radiance arriving from specified world space direction.
+    pub fn radiance(&self, direction: Vector) -> Color {
+        let (u, v) = self.uv(direction);
+        let (x, y) = self.pixel(u, v);
+        *self.map.get(x, y) * self.intensity
+    }
+
+    /// Returns the probability density (per steradian) of sampling specified
+    /// world space direction.
+    pub fn pdf(&self, direction: Vector) -> f64 {
+        let total = *self.marginal.last().unwrap();
+        if total <= 0. {
+            return 1. / (4. * PI);
+        }
+        let (u, v) = self.uv(direction);
+        let (width, height) = (self.map.width(), self.map.height());
+        let (x, y) = self.pixel(u, v);
+        let row = &self.conditional[y];
+        let weight = row[x] - if x > 0 { row[x - 1] } else { 0. };
+        let sin_theta = (PI * (y as f64 + 0.5) / height as f64).sin();
+        // density over the image divided by the solid angle of a pixel
+        let pixel = (2. * PI / width as f64) * (PI / height as f64) * sin_theta;
+        weight / total / pixel
+    }
+
+    /// Returns a world space direction sampled proportionally to the
+    /// luminance of the image from two uniform values in `[0,1)`.
+    pub fn sample(&self, u1: f64, u2: f64) -> Vector {
+        let total = *self.marginal.last().unwrap();
+        let (width, height) = (self.map.width(), self.map.height());
+        if total <= 0. {
+            return self.direction(u1, u2);
+        }
+        let (y, dv) = search(&self.marginal, u1 * total);
+        let row = &self.conditional[y];
+        let (x, du) = search(row, u2 * row[width - 1]);
+        let u = (x as f64 + du) / width as f64;
+        let v = (y as f64 + dv) / height as f64;
+        self.direction(u, v)
+    }
+
+    /// Returns the image coordinates in `[0,1]` of a world space direction.
+    fn uv(&self, direction: Vector) -> (f64, f64) {
+        let d = (self.inverse * direction).normalized();
+        let theta = d.y.clamp(-1., 1.).acos();
+        let phi = d.x.atan2(-d.z);
+        (0.5 + phi / (2. * PI), theta / PI)
+    }
+
+    /// Returns the pixel at image coordinates, wrapping horizontally.
+    fn pixel(&self, u: f64, v: f64) -> (usize, usize) {
+        let (width, height) = (self.map.width(), self.map.height());
+        let x = (u * width as f64) as usize % width;
+        let y = ((v * height as f64) as usize).min(height - 1);
+        (x, y)
+    }
+
+    /// Returns the world space direction of image coordinates.
+    fn direction(&self, u: f64, v: f64) -> Vector {
+        let theta = v * PI;
+        let phi = (u - 0.5) * 2. * PI;
+        let d = Vector::new(theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos());
+        (self.transform * d).normalized()
+    }
+}
+
+impl Light for Environment {
+    /// Samples are importance sampled (seeded by the illuminated point) and
+    /// weighted by the inverse of their density so that their average
+    /// estimates the irradiance.
+    fn samples(&self, point: Point) -> Vec<LightSample> {
+        let mut rng = Rng::from_values(&[point.x, point.y, point.z]);
+        (0..self.samples)
+            .filter_map(|_| self.sample_along(point, self.sample(rng.next_f64(), rng.next_f64())))
+            .collect()
+    }
+
+    /// Every direction reaches the environment at an infinite distance.
+    fn sample_along(&self, _point: Point, direction: Vector) -> Option<LightSample> {
+        let pdf = self.pdf(direction);
+        let intensity = if pdf > 0. {
+            self.radiance(direction) * (1. / pdf)
+        } else {
+            Color::black()
+        };
+        Some(LightSample {
+            direction: direction.normalized(),
+            distance: f64::INFINITY,
+            intensity,
+            pdf,
+        })
+    }
+}
+
+/// Returns the index of the first value of a cumulative distribution greater
+/// than `value` and the relative position of `value` within its bin.
+fn search(cdf: &[f64], value: f64) -> (usize, f64) {
+    let index = cdf.partition_point(|&c| c <= value).min(cdf.len() - 1);
+    let start = if index > 0 { cdf[index - 1] } else { 0. };
+    let offset = if cdf[index] > start {
+        ((value - start) / (cdf[index] - start)).clamp(0., 1.)
+    } else {
+        0.5
+    };
+    (index, offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::float_eq;
+
+    use super::*;
+
+    #[test]
+    fn radiance() {
+        let mut map = Canvas::new(4, 2);
+        *map.get_mut(0, 0) = Color::red();
+        *map.get_mut(2, 0) = Color::green();
+        *map.get_mut(1, 1) = Color::blue();
+        let mut environment = Environment::new(map);
+        let forward = Vector::new(0., 0.2, -1.);
+        let back = Vector::new(0., 0.2, 1.);
+        assert_eq!(environment.radiance(forward), Color::green());
+        assert_eq!(environment.radiance(back), Color::red());
+        assert_eq!(environment.radiance(Vector::new(-1., -0.2, -1.)), Color::blue());
+        environment.set_intensity(2.);
+        assert_eq!(environment.radiance(forward), Color::new(0., 2., 0.));
+        environment.set_transform(Matrix::rotation_y(PI));
+        assert_eq!(environment.radiance(back), Color::new(0., 2., 0.));
+        assert_eq!(environment.radiance(Vector::new(1., -0.2, 1.)), Color::new(0., 0., 2.));
+    }
+
+    #[test]
+    fn uv_direction() {
+        let environment = Environment::new(Canvas::new(8, 4));
+        for (u, v) in [(0.5, 0.5), (0.25, 0.25), (0.9, 0.7), (0.1, 0.1)] {
+            let (u2, v2) = environment.uv(environment.direction(u, v));
+            assert!(float_eq(u, u2));
+            assert!(float_eq(v, v2));
+        }
+        assert_eq!(environment.direction(0.5, 0.5), Vector::new(0., 0., -1.));
+        assert_eq!(environment.direction(0.5, 0.), Vector::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn pdf_uniform() {
+        let environment = Environment::new(Canvas::with_color(64, 32, Color::white()));
+        for direction in [Vector::new(0., 0., 1.), Vector::new(1., 0.3, 0.)] {
+            let pdf = environment.pdf(direction);
+            assert!((pdf - 1. / (4. * PI)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn pdf_black() {
+        let environment = Environment::new(Canvas::new(8, 4));
+        assert!(float_eq(environment.pdf(Vector::new(0., 1., 0.)), 1. / (4. * PI)));
+    }
+
+    #[test]
+    fn sample_importance() {
+        // single bright pixel, every sample must fall within it
+        let mut map = Canvas::new(8, 4);
+        *map.get_mut(5, 1) = Color::white();
+        let environment = Environment::new(map);
+        let mut rng = Rng::from_values(&[1.]);
+        for _ in 0..100 {
+            let direction = environment.sample(rng.next_f64(), rng.next_f64());
+            assert_eq!(environment.radiance(direction), Color::white());
+            assert!(environment.pdf(direction) > 0.);
+        }
+    }
+
+    #[test]
+    fn samples_irradiance() {
+        // uniform environment of radiance 1: irradiance on a surface is π
+        let mut environment = Environment::new(Canvas::with_color(64, 32, Color::white()));
+        environment.set_sample_count(4096);
+        let normal = Vector::new(0., 1., 0.);
+        let samples = environment.samples(Point::new(0., 0., 0.));
+        let irradiance: f64 = samples
+            .iter()
+            .map(|sample| sample.intensity.r * sample.direction.dot(normal).max(0.))
+            .sum::<f64>()
+            / samples.len() as f64;
+        assert!((irradiance - PI).abs() < 0.1);
+        assert!(samples.iter().all(|sample| sample.distance.is_infinite()));
+        assert!(samples
+            .iter()
+            .all(|sample| sample.pdf == environment.pdf(sample.direction)));
+    }
+
+    #[test]
+    fn sample_along() {
+        let mut map = Canvas::with_color(8, 4, Color::white());
+        *map.get_mut(0, 0) = Color::new(4., 4., 4.);
+        let environment = Environment::new(map);
+        let direction = Vector::new(1., 0.2, 0.3);
+        let sample = environment.sample_along(Point::new(1., 2., 3.), direction).unwrap();
+        assert_eq!(sample.direction, direction.normalized());
+        assert!(sample.distance.is_infinite());
+        assert!(float_eq(sample.pdf, environment.pdf(direction)));
+        assert_eq!(sample.intensity * sample.pdf, Color::white());
+    }
+}
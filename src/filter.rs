@@ -0,0 +1,245 @@
+/// Reconstruction filter weighting the samples of a canvas by their offset
+/// to the center of the pixels around them.
+///
+/// Filters are separable: the weight of an offset is the product of the
+/// weights of its horizontal and vertical components (in pixels), samples
+/// farther than the radius along either axis have no weight.
+///
+/// The radius is at least half a pixel so that every sample reaches the
+/// pixel it falls in, smaller radii are rounded up.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Filter {
+    /// constant weight, each sample contributes to the pixels it falls in
+    /// for a radius of `0.5`
+    Box {
+        /// extent of the filter (in pixels)
+        radius: f64,
+    },
+    /// weight decreasing linearly to zero at the radius
+    Tent {
+        /// extent of the filter (in pixels)
+        radius: f64,
+    },
+    /// Gaussian of specified standard deviation shifted down to reach zero
+    /// at the radius
+    Gaussian {
+        /// extent of the filter (in pixels)
+        radius: f64,
+        /// standard deviation (in pixels)
+        sigma: f64,
+    },
+    /// Mitchell-Netravali cubic filter stretched over the radius, trading
+    /// blurring (`b`) for ringing (`c`), `b + 2c = 1` being recommended
+    MitchellNetravali {
+        /// extent of the filter (in pixels)
+        radius: f64,
+        /// blurring parameter
+        b: f64,
+        /// ringing parameter
+        c: f64,
+    },
+}
+
+impl Filter {
+    const MIN_RADIUS: f64 = 0.5;
+
+    /// Creates a box filter of specified radius (in pixels).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the radius is not positive.
+    pub fn box_filter(radius: f64) -> Self {
+        Self::Box {
+            radius: Self::checked_radius(radius),
+        }
+    }
+
+    /// Creates a tent filter of specified radius (in pixels).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the radius is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Filter;
+    /// assert_eq!(Filter::tent(2.), Filter::Tent { radius: 2. });
+    /// // rounded up to half a pixel
+    /// assert_eq!(Filter::tent(0.1).radius(), 0.5);
+    /// ```
+    pub fn tent(radius: f64) -> Self {
+        Self::Tent {
+            radius: Self::checked_radius(radius),
+        }
+    }
+
+    /// Creates a Gaussian filter of specified radius and standard deviation
+    /// (in pixels).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the radius or the standard deviation is not positive.
+    pub fn gaussian(radius: f64, sigma: f64) -> Self {
+        assert!(sigma > 0., "filter standard deviation must be positive");
+        Self::Gaussian {
+            radius: Self::checked_radius(radius),
+            sigma,
+        }
+    }
+
+    /// Creates a Mitchell-Netravali filter of specified radius (in pixels)
+    /// and blurring and ringing parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the radius is not positive.
+    pub fn mitchell_netravali(radius: f64, b: f64, c: f64) -> Self {
+        Self::MitchellNetravali {
+            radius: Self::checked_radius(radius),
+            b,
+            c,
+        }
+    }
+
+    fn checked_radius(radius: f64) -> f64 {
+        assert!(radius > 0., "filter radius must be positive");
+        radius.max(Self::MIN_RADIUS)
+    }
+
+    /// Returns the extent of the filter (in pixels), at least half a pixel.
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Self::Box { radius }
+            | Self::Tent { radius }
+            | Self::Gaussian { radius, .. }
+            | Self::MitchellNetravali { radius, .. } => radius.max(Self::MIN_RADIUS),
+        }
+    }
+
+    /// Returns the weight of a sample at specified offset (in pixels) from
+    /// the center of a pixel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Filter;
+    /// let tent = Filter::Tent { radius: 1. };
+    /// assert_eq!(tent.weight(0., 0.), 1.);
+    /// assert_eq!(tent.weight(0.5, 0.), 0.5);
+    /// assert_eq!(tent.weight(0.5, 0.5), 0.25);
+    /// assert_eq!(tent.weight(1.5, 0.), 0.);
+    /// ```
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    /// Returns the weight of an offset along one axis.
+    fn weight_1d(&self, offset: f64) -> f64 {
+        let x = offset.abs();
+        let radius = self.radius();
+        if x > radius {
+            return 0.;
+        }
+        match *self {
+            Self::Box { .. } => 1.,
+            Self::Tent { .. } => 1. - x / radius,
+            Self::Gaussian { sigma, .. } => {
+                let gaussian = |x: f64| (-x * x / (2. * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(radius)).max(0.)
+            }
+            Self::MitchellNetravali { b, c, .. } => {
+                // the cubic is defined over [-2,2]
+                let x = 2. * x / radius;
+                let value = if x < 1. {
+                    (12. - 9. * b - 6. * c) * x * x * x + (-18. + 12. * b + 6. * c) * x * x + (6. - 2. * b)
+                } else {
+                    (-b - 6. * c) * x * x * x
+                        + (6. * b + 30. * c) * x * x
+                        + (-12. * b - 48. * c) * x
+                        + (8. * b + 24. * c)
+                };
+                value / 6.
+            }
+        }
+    }
+}
+
+impl Default for Filter {
+    /// Box filter of radius `0.5`: the samples only contribute to the pixel
+    /// they fall in.
+    fn default() -> Self {
+        Self::Box { radius: 0.5 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::float_eq;
+
+    use super::*;
+
+    #[test]
+    fn box_filter() {
+        let filter = Filter::default();
+        assert!(float_eq(filter.radius(), 0.5));
+        assert!(float_eq(filter.weight(0.2, -0.4), 1.));
+        assert!(float_eq(filter.weight(0.6, 0.), 0.));
+        assert!(float_eq(filter.weight(0., -0.6), 0.));
+    }
+
+    #[test]
+    fn small_radius() {
+        assert_eq!(Filter::box_filter(0.2), Filter::default());
+        assert!(float_eq(Filter::gaussian(0.2, 0.1).radius(), 0.5));
+        // radii set directly are rounded up too
+        let tent = Filter::Tent { radius: 0. };
+        assert!(float_eq(tent.radius(), 0.5));
+        assert!(float_eq(tent.weight(0.25, 0.), 0.5));
+    }
+
+    #[test]
+    #[should_panic(expected = "filter radius must be positive")]
+    fn zero_radius() {
+        Filter::tent(0.);
+    }
+
+    #[test]
+    fn gaussian() {
+        let filter = Filter::Gaussian {
+            radius: 1.5,
+            sigma: 0.5,
+        };
+        assert!(float_eq(filter.weight(0., 0.), (1. - (-4.5f64).exp()).powi(2)));
+        assert!(float_eq(filter.weight(1.5, 0.), 0.));
+        assert!(filter.weight(0.5, 0.) > filter.weight(1., 0.));
+        assert!(float_eq(filter.weight(0.3, 0.7), filter.weight(-0.7, 0.3)));
+    }
+
+    #[test]
+    fn mitchell_netravali() {
+        let filter = Filter::MitchellNetravali {
+            radius: 2.,
+            b: 1. / 3.,
+            c: 1. / 3.,
+        };
+        // weights at the integer offsets sum to one
+        for (x, y) in [(0., 0.), (0.3, 0.5), (0.9, 0.2)] {
+            let sum: f64 = (-2..=2)
+                .flat_map(|i| (-2..=2).map(move |j| (x + i as f64, y + j as f64)))
+                .map(|(dx, dy)| filter.weight(dx, dy))
+                .sum();
+            assert!(float_eq(sum, 1.));
+        }
+        assert!(float_eq(filter.weight(2., 0.), 0.));
+        // negative lobes
+        assert!(filter.weight(1.5, 0.) < 0.);
+        // stretched over the radius
+        let wide = Filter::MitchellNetravali {
+            radius: 4.,
+            b: 1. / 3.,
+            c: 1. / 3.,
+        };
+        assert!(float_eq(wide.weight(3., 0.), filter.weight(1.5, 0.)));
+    }
+}
@@ -0,0 +1,353 @@
+use std::mem;
+
+use crate::{Attributes, Bounds, Intersection, Point, Ray, Shape, Vector};
+
+/// Collection of shapes sharing a common transform.
+///
+/// Children keep their own materials, the material of the group itself is
+/// never used for shading.
+///
+/// Rays missing the bounds of the group or of a child skip them, moving and
+/// unbounded children (whose bounds are infinite) are always tested.
+/// [`Group::divide`] organizes the children in a bounding volume hierarchy,
+/// which [`Instance`](crate::Instance)s of the group share.
+#[derive(Debug)]
+pub struct Group {
+    children: Vec<Box<dyn Shape>>,
+    /// bounds of the children in the object space of the group
+    child_bounds: Vec<Bounds>,
+    bounds: Bounds,
+    attributes: Attributes,
+}
+
+impl Group {
+    const NORMAL_OFFSET: f64 = 1e-4;
+
+    /// Creates an empty `Group`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Group, Point, Quadric, Ray, Shape, Vector};
+    /// let mut group = Group::new();
+    /// group.add(Quadric::sphere());
+    /// let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+    /// assert_eq!(group.intersect(&ray).len(), 2);
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            children: vec![],
+            child_bounds: vec![],
+            bounds: Bounds::empty(),
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Adds a shape to the group.
+    pub fn add(&mut self, shape: impl Shape + 'static) {
+        let bounds = shape.parent_bounds();
+        self.push(Box::new(shape), bounds);
+    }
+
+    fn push(&mut self, shape: Box<dyn Shape>, bounds: Bounds) {
+        self.children.push(shape);
+        self.child_bounds.push(bounds);
+        self.bounds = self.bounds.union(&bounds);
+    }
+
+    /// Get the children of the group.
+    #[must_use]
+    pub fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    /// Organizes the children in a bounding volume hierarchy: groups of at
+    /// least `threshold` children are split in two subgroups across the
+    /// longest side of their bounds, recursively.
+    ///
+    /// Children straddling the split or with infinite bounds stay where they
+    /// are. Groups added as children are left as they are, they are divided
+    /// before being added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Group, Matrix, Point, Quadric, Ray, Shape, Vector};
+    /// let mut group = Group::new();
+    /// for i in 0..8 {
+    ///     let mut sphere = Quadric::sphere();
+    ///     sphere.set_transform(Matrix::translation(3. * i as f64, 0., 0.));
+    ///     group.add(sphere);
+    /// }
+    /// group.divide(2);
+    /// assert_eq!(group.children().len(), 2);
+    /// let ray = Ray::new(Point::new(9., 0., -5.), Vector::new(0., 0., 1.));
+    /// assert_eq!(group.intersect(&ray).len(), 2);
+    /// ```
+    pub fn divide(&mut self, threshold: usize) {
+        if self.children.len() < threshold {
+            return;
+        }
+        let finite = self
+            .child_bounds
+            .iter()
+            .filter(|bounds| bounds.is_finite())
+            .fold(Bounds::empty(), |union, bounds| union.union(bounds));
+        if finite.is_empty() {
+            return;
+        }
+        let (left, right) = finite.split();
+        let sides: Vec<_> = self
+            .child_bounds
+            .iter()
+            .map(|bounds| match bounds.is_finite() {
+                true if left.contains(bounds) => Some(0),
+                true if right.contains(bounds) => Some(1),
+                _ => None,
+            })
+            .collect();
+        // a side holding every child would be split again forever
+        if (0..2).any(|side| sides.iter().all(|&s| s == Some(side))) {
+            return;
+        }
+        let children = mem::take(&mut self.children);
+        let child_bounds = mem::take(&mut self.child_bounds);
+        let mut halves = [Group::new(), Group::new()];
+        for ((child, bounds), side) in children.into_iter().zip(child_bounds).zip(sides) {
+            match side {
+                Some(side) => halves[side].push(child, bounds),
+                None => self.push(child, bounds),
+            }
+        }
+        for mut half in halves {
+            if !half.children.is_empty() {
+                half.divide(threshold);
+                let bounds = half.bounds;
+                self.push(Box::new(half), bounds);
+            }
+        }
+    }
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Group {
+    fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    fn attributes_mut(&mut self) -> &mut Attributes {
+        &mut self.attributes
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        if !self.bounds.intersects(ray) {
+            return vec![];
+        }
+        let mut intersections: Vec<_> = self
+            .children
+            .iter()
+            .zip(&self.child_bounds)
+            .filter(|(_, bounds)| bounds.intersects(ray))
+            .flat_map(|(child, _)| child.intersect(ray))
+            .collect();
+        intersections.sort_by(|a, b| a.t.total_cmp(&b.t));
+        intersections
+    }
+
+    /// Groups have no surface of their own: returns the normal of the child
+    /// whose surface passes closest to the point, measured along the normal
+    /// of each child (`+y` if no child surface lies along them, as for empty
+    /// groups).
+    ///
+    /// Intersections already carry the normal of the child they hit, which
+    /// is what rendering uses.
+    fn local_normal_at(&self, point: Point, time: f64) -> Vector {
+        self.children
+            .iter()
+            .filter_map(|child| {
+                let normal = child.normal_at(point, time);
+                // a ray along the normal crosses the surface of the child
+                // through the point if it lies on it, it starts slightly
+                // before the point for shapes only intersected forward
+                let ray = Ray::new(point - normal * Self::NORMAL_OFFSET, normal).with_time(time);
+                child
+                    .intersect(&ray)
+                    .iter()
+                    .map(|intersection| (intersection.t - Self::NORMAL_OFFSET).abs())
+                    .min_by(f64::total_cmp)
+                    .map(|distance| (distance, normal))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map_or(Vector::new(0., 1., 0.), |(_, normal)| normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{util::float_eq, Matrix, Quadric};
+
+    use super::*;
+
+    #[test]
+    fn new() {
+        let group = Group::new();
+        assert!(group.children().is_empty());
+        assert_eq!(group.transform(), &Matrix::identity());
+    }
+
+    #[test]
+    fn intersect_empty() {
+        let group = Group::new();
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        assert!(group.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_children() {
+        let mut group = Group::new();
+        let s1 = Quadric::sphere();
+        let mut s2 = Quadric::sphere();
+        s2.set_transform(Matrix::translation(0., 0., -3.));
+        let mut s3 = Quadric::sphere();
+        s3.set_transform(Matrix::translation(5., 0., 0.));
+        group.add(s1);
+        group.add(s2);
+        group.add(s3);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = group.intersect(&ray);
+        assert_eq!(xs.len(), 4);
+        assert!(float_eq(xs[0].t, 1.));
+        assert!(float_eq(xs[1].t, 3.));
+        assert!(float_eq(xs[2].t, 4.));
+        assert!(float_eq(xs[3].t, 6.));
+        assert!(std::ptr::addr_eq(xs[0].object, group.children()[1].as_ref()));
+        assert!(std::ptr::addr_eq(xs[2].object, group.children()[0].as_ref()));
+    }
+
+    #[test]
+    fn normal() {
+        let mut group = Group::new();
+        group.set_transform(Matrix::rotation_y(PI / 2.));
+        let mut inner = Group::new();
+        inner.set_transform(Matrix::scaling(1., 2., 3.));
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::translation(5., 0., 0.));
+        inner.add(sphere);
+        group.add(inner);
+        group.add(Quadric::sphere());
+        // normal of the child surface hit by a ray
+        let ray = Ray::new(Point::new(1., 0.5, -10.), Vector::new(0.05, 0.05, 1.).normalized());
+        let hit = group.intersect(&ray)[0];
+        assert!(hit.t > 0. && !std::ptr::addr_eq(hit.object, group.children()[1].as_ref()));
+        assert_eq!(group.normal_at(ray.position(hit.t), 0.), hit.normal);
+        let normal = group.normal_at(Point::new(0., 0., -1.), 0.);
+        assert_eq!(normal, Vector::new(0., 0., -1.));
+        assert_eq!(
+            Group::new().normal_at(Point::new(1., 2., 3.), 0.),
+            Vector::new(0., 1., 0.)
+        );
+    }
+
+    #[test]
+    fn normal_seam() {
+        // the point lies on the first sphere, a hair away from the second
+        let mut group = Group::new();
+        group.add(Quadric::sphere());
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::translation(0.6, 0., 1.801));
+        group.add(sphere);
+        let point = Point::new(0.6, 0., 0.8);
+        assert_eq!(group.normal_at(point, 0.), Vector::new(0.6, 0., 0.8));
+        let point = Point::new(0.6, 0., 0.801);
+        assert_eq!(group.normal_at(point, 0.), Vector::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn bounds() {
+        let mut group = Group::new();
+        assert!(group.bounds().is_empty());
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::translation(2., 0., 0.));
+        group.add(sphere);
+        let mut ellipsoid = Quadric::ellipsoid(1., 2., 1.);
+        ellipsoid.set_transform(Matrix::rotation_z(PI / 2.));
+        group.add(ellipsoid);
+        assert_eq!(
+            group.bounds(),
+            Bounds::new(Point::new(-2., -1., -1.), Point::new(3., 1., 1.))
+        );
+        group.set_transform(Matrix::translation(0., 5., 0.));
+        assert_eq!(
+            group.parent_bounds(),
+            Bounds::new(Point::new(-2., 4., -1.), Point::new(3., 6., 1.))
+        );
+        group.add(Quadric::plane());
+        assert_eq!(group.bounds(), Bounds::infinite());
+    }
+
+    #[test]
+    fn divide() {
+        let mut group = Group::new();
+        for i in 0..10 {
+            let mut sphere = Quadric::sphere();
+            sphere.set_transform(Matrix::translation(3. * i as f64, 0., 0.));
+            group.add(sphere);
+        }
+        group.add(Quadric::plane());
+        let rays: Vec<_> = (0..40)
+            .map(|i| Ray::new(Point::new(i as f64 * 0.75, 0.5, -5.), Vector::new(0., -0.2, 1.)))
+            .collect();
+        let before: Vec<Vec<f64>> = rays
+            .iter()
+            .map(|ray| group.intersect(ray).iter().map(|x| x.t).collect())
+            .collect();
+        group.divide(3);
+        // the unbounded plane stays, the spheres are split in two subgroups
+        assert_eq!(group.children().len(), 3);
+        assert_eq!(group.children()[0].bounds(), Bounds::infinite());
+        assert_eq!(
+            group.children()[1].bounds(),
+            Bounds::new(Point::new(-1., -1., -1.), Point::new(13., 1., 1.))
+        );
+        let after: Vec<Vec<f64>> = rays
+            .iter()
+            .map(|ray| group.intersect(ray).iter().map(|x| x.t).collect())
+            .collect();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn divide_overlapping() {
+        let mut group = Group::new();
+        for _ in 0..4 {
+            group.add(Quadric::sphere());
+        }
+        group.divide(1);
+        assert_eq!(group.children().len(), 4);
+    }
+
+    #[test]
+    fn intersect_transformed() {
+        let mut group = Group::new();
+        group.set_transform(Matrix::scaling(2., 2., 2.));
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::translation(5., 0., 0.));
+        group.add(sphere);
+        let ray = Ray::new(Point::new(10., 0., -10.), Vector::new(0., 0., 1.));
+        let xs = group.intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        assert!(float_eq(xs[0].t, 8.));
+        assert_eq!(xs[0].normal, Vector::new(0., 0., -1.));
+    }
+}
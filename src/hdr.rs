@@ -0,0 +1,288 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{Canvas, Color};
+
+impl Canvas {
+    /// Creates a canvas from a Radiance HDR (RGBE) image.
+    ///
+    /// Both flat and run-length encoded scanlines are supported, only the
+    /// standard `-Y height +X width` orientation is accepted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Canvas, Color};
+    /// let mut canvas = Canvas::new(2, 1);
+    /// *canvas.get_mut(1, 0) = Color::new(4., 2., 0.5);
+    /// let canvas = Canvas::from_hdr(&canvas.hdr()).unwrap();
+    /// assert_eq!(canvas.get(0, 0), &Color::black());
+    /// assert!((canvas.get(1, 0).r - 4.).abs() < 0.1);
+    /// ```
+    pub fn from_hdr(bytes: &[u8]) -> Result<Canvas, HdrError> {
+        let (width, height, mut data) = header(bytes)?;
+        let mut canvas = Canvas::new(width, height);
+        let mut scanline = vec![[0u8; 4]; width];
+        for y in 0..height {
+            data = read_scanline(data, &mut scanline)?;
+            for (x, rgbe) in scanline.iter().enumerate() {
+                *canvas.get_mut(x, y) = decode(*rgbe);
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// Turns canvas into a Radiance HDR (RGBE) image with flat scanlines.
+    pub fn hdr(&self) -> Vec<u8> {
+        let mut hdr = Vec::new();
+        hdr.extend_from_slice(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n");
+        hdr.extend_from_slice(format!("-Y {} +X {}\n", self.height(), self.width()).as_bytes());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                hdr.extend_from_slice(&encode(*self.get(x, y)));
+            }
+        }
+        hdr
+    }
+}
+
+/// Parses the header and returns the image size and the pixel data.
+fn header(bytes: &[u8]) -> Result<(usize, usize, &[u8]), HdrError> {
+    let mut lines = bytes.split(|&b| b == b'\n');
+    let mut consumed = 0;
+    let mut next = |consumed: &mut usize| {
+        let line = lines.next().ok_or(HdrError("truncated header"))?;
+        *consumed += line.len() + 1;
+        std::str::from_utf8(line).map_err(|_| HdrError("invalid header"))
+    };
+    let magic = next(&mut consumed)?;
+    if magic != "#?RADIANCE" && magic != "#?RGBE" {
+        return Err(HdrError("missing magic number"));
+    }
+    loop {
+        let line = next(&mut consumed)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(HdrError("unsupported format"));
+            }
+        }
+    }
+    let resolution = next(&mut consumed)?;
+    let tokens: Vec<&str> = resolution.split_whitespace().collect();
+    let (height, width) = match tokens[..] {
+        ["-Y", height, "+X", width] => (height.parse(), width.parse()),
+        _ => return Err(HdrError("unsupported orientation")),
+    };
+    let (height, width) = match (height, width) {
+        (Ok(height), Ok(width)) => (height, width),
+        _ => return Err(HdrError("invalid resolution")),
+    };
+    let data = bytes.get(consumed..).unwrap_or_default();
+    // reject sizes the data cannot hold before allocating the canvas
+    let scanline = if is_rle_width(width) {
+        Some(4 + 8 * width.div_ceil(127))
+    } else {
+        width.checked_mul(4)
+    };
+    match scanline.and_then(|scanline| scanline.checked_mul(height)) {
+        Some(len) if len <= data.len() => Ok((width, height, data)),
+        Some(_) => Err(HdrError("truncated data")),
+        None => Err(HdrError("invalid resolution")),
+    }
+}
+
+/// Returns `true` if scanlines of specified width may be run-length encoded.
+fn is_rle_width(width: usize) -> bool {
+    (8..0x8000).contains(&width)
+}
+
+/// Reads a scanline and returns the remaining data.
+fn read_scanline<'a>(data: &'a [u8], scanline: &mut [[u8; 4]]) -> Result<&'a [u8], HdrError> {
+    let width = scanline.len();
+    let rle = is_rle_width(width) && data.len() >= 4 && data[0] == 2 && data[1] == 2 && data[2] & 0x80 == 0;
+    if !rle {
+        let len = width * 4;
+        let pixels = data.get(..len).ok_or(HdrError("truncated data"))?;
+        for (pixel, rgbe) in scanline.iter_mut().zip(pixels.chunks_exact(4)) {
+            pixel.copy_from_slice(rgbe);
+        }
+        return Ok(&data[len..]);
+    }
+    if ((data[2] as usize) << 8 | data[3] as usize) != width {
+        return Err(HdrError("scanline width mismatch"));
+    }
+    let mut data = &data[4..];
+    // each component is run-length encoded separately
+    for component in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let (&count, rest) = data.split_first().ok_or(HdrError("truncated data"))?;
+            if count > 128 {
+                let count = count as usize - 128;
+                let (&value, rest) = rest.split_first().ok_or(HdrError("truncated data"))?;
+                let run = scanline.get_mut(x..x + count).ok_or(HdrError("invalid run length"))?;
+                run.iter_mut().for_each(|pixel| pixel[component] = value);
+                data = rest;
+                x += count;
+            } else {
+                let count = count as usize;
+                if count == 0 {
+                    return Err(HdrError("invalid run length"));
+                }
+                let values = rest.get(..count).ok_or(HdrError("truncated data"))?;
+                let run = scanline.get_mut(x..x + count).ok_or(HdrError("invalid run length"))?;
+                run.iter_mut()
+                    .zip(values)
+                    .for_each(|(pixel, value)| pixel[component] = *value);
+                data = &rest[count..];
+                x += count;
+            }
+        }
+    }
+    Ok(data)
+}
+
+fn decode(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::black();
+    }
+    let f = 2f64.powi(rgbe[3] as i32 - (128 + 8));
+    Color::new(
+        (rgbe[0] as f64 + 0.5) * f,
+        (rgbe[1] as f64 + 0.5) * f,
+        (rgbe[2] as f64 + 0.5) * f,
+    )
+}
+
+fn encode(color: Color) -> [u8; 4] {
+    let v = color.r.max(color.g).max(color.b);
+    if v < 1e-32 {
+        return [0; 4];
+    }
+    // v = m * 2^e with m in [0.5, 1)
+    let e = (v.log2().floor() + 1.) as i32;
+    if e < -127 {
+        return [0; 4];
+    }
+    // larger values saturate
+    let e = e.min(127);
+    let scale = 256. / 2f64.powi(e);
+    let component = |c: f64| (c.max(0.) * scale).min(255.) as u8;
+    [
+        component(color.r),
+        component(color.g),
+        component(color.b),
+        (e + 128) as u8,
+    ]
+}
+
+/// Error returned when decoding an invalid or unsupported HDR image.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct HdrError(&'static str);
+
+impl Display for HdrError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "invalid hdr image: {}", self.0)
+    }
+}
+
+impl Error for HdrError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(resolution: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = b"#?RADIANCE\n# comment\nFORMAT=32-bit_rle_rgbe\n\n".to_vec();
+        bytes.extend_from_slice(resolution.as_bytes());
+        bytes.push(b'\n');
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(super::decode([0, 0, 0, 0]), Color::black());
+        assert_eq!(
+            super::decode([128, 64, 0, 129]),
+            Color::new(1.00390625, 0.50390625, 0.00390625)
+        );
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!(super::encode(Color::black()), [0, 0, 0, 0]);
+        assert_eq!(super::encode(Color::new(1., 0.5, 0.25)), [128, 64, 32, 129]);
+        assert_eq!(super::encode(Color::new(0.5, 0., 0.)), [128, 0, 0, 128]);
+        // exponents out of range saturate or underflow to black
+        assert_eq!(super::encode(Color::new(1e300, 0., 0.)), [255, 0, 0, 255]);
+        assert_eq!(super::encode(Color::new(f64::INFINITY, 1., 0.)), [255, 0, 0, 255]);
+        assert_eq!(super::encode(Color::new(1e-40, 0., 0.)), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn flat() {
+        let bytes = image("-Y 1 +X 2", &[128, 64, 0, 129, 0, 0, 0, 0]);
+        let canvas = Canvas::from_hdr(&bytes).unwrap();
+        assert_eq!(canvas.width(), 2);
+        assert_eq!(canvas.height(), 1);
+        assert_eq!(canvas.get(0, 0), &Color::new(1.00390625, 0.50390625, 0.00390625));
+        assert_eq!(canvas.get(1, 0), &Color::black());
+    }
+
+    #[test]
+    fn rle() {
+        // 8 pixels: red runs, green literals, blue and exponent runs
+        let mut data = vec![2, 2, 0, 8];
+        data.extend_from_slice(&[128 + 8, 128]);
+        data.extend_from_slice(&[8, 0, 32, 64, 96, 128, 160, 192, 224]);
+        data.extend_from_slice(&[128 + 4, 0, 128 + 4, 255]);
+        data.extend_from_slice(&[128 + 8, 129]);
+        let canvas = Canvas::from_hdr(&image("-Y 1 +X 8", &data)).unwrap();
+        assert_eq!(canvas.get(0, 0), &super::decode([128, 0, 0, 129]));
+        assert_eq!(canvas.get(3, 0), &super::decode([128, 96, 0, 129]));
+        assert_eq!(canvas.get(7, 0), &super::decode([128, 224, 255, 129]));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut canvas = Canvas::new(3, 2);
+        *canvas.get_mut(0, 0) = Color::new(100., 50., 25.);
+        *canvas.get_mut(2, 1) = Color::new(0.001, 0.002, 0.004);
+        let decoded = Canvas::from_hdr(&canvas.hdr()).unwrap();
+        for (a, b) in canvas.iter().zip(decoded.iter()) {
+            let tolerance = a.r.max(a.g).max(a.b) / 100.;
+            assert!((a.r - b.r).abs() <= tolerance);
+            assert!((a.g - b.g).abs() <= tolerance);
+            assert!((a.b - b.b).abs() <= tolerance);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Canvas::from_hdr(b"P3\n").unwrap_err(), HdrError("missing magic number"));
+        let bytes = b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0";
+        assert_eq!(Canvas::from_hdr(bytes).unwrap_err(), HdrError("unsupported format"));
+        let bytes = image("+Y 1 +X 1", &[0, 0, 0, 0]);
+        assert_eq!(
+            Canvas::from_hdr(&bytes).unwrap_err(),
+            HdrError("unsupported orientation")
+        );
+        let bytes = image("-Y 2 +X 1", &[0, 0, 0, 0]);
+        assert_eq!(Canvas::from_hdr(&bytes).unwrap_err(), HdrError("truncated data"));
+        let bytes = image("-Y 1 +X 2", &[0, 0, 0, 0, 0, 0]);
+        assert_eq!(Canvas::from_hdr(&bytes).unwrap_err(), HdrError("truncated data"));
+        let bytes = image("-Y 3 +X 10", &[2, 2, 0, 10, 128 + 10, 0]);
+        assert_eq!(Canvas::from_hdr(&bytes).unwrap_err(), HdrError("truncated data"));
+        let bytes = image("-Y 1000000000 +X 1000000000", &[0, 0, 0, 0]);
+        assert_eq!(Canvas::from_hdr(&bytes).unwrap_err(), HdrError("truncated data"));
+        let bytes = image(&format!("-Y {} +X {}", usize::MAX, usize::MAX), &[0, 0, 0, 0]);
+        assert_eq!(Canvas::from_hdr(&bytes).unwrap_err(), HdrError("invalid resolution"));
+        assert_eq!(
+            HdrError("truncated data").to_string(),
+            "invalid hdr image: truncated data"
+        );
+    }
+}
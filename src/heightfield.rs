@@ -0,0 +1,353 @@
+use crate::{util::float_eq, Attributes, Bounds, Canvas, Intersection, Point, Ray, Shape, Vector};
+
+/// Terrain defined by a regular grid of elevation samples.
+///
+/// In object space the heightfield covers the unit square `[0,1]` along the
+/// `x` and `z` axes, elevations are given along the `y` axis. Each cell of
+/// the grid is a bilinear patch interpolating its four corner samples.
+///
+/// Rays are intersected by walking the cells crossed by the ray (2D grid DDA)
+/// from the closest to the farthest, only the closest hit is reported.
+#[derive(Clone, Debug)]
+pub struct Heightfield {
+    width: usize,
+    depth: usize,
+    heights: Vec<f64>,
+    min: f64,
+    max: f64,
+    attributes: Attributes,
+}
+
+impl Heightfield {
+    /// Creates a `Heightfield` of `width` by `depth` samples whose elevations
+    /// are given by `elevation(u, v)` with `u` and `v` in `[0,1]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `depth` is lower than 2.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Heightfield, Point, Ray, Shape, Vector};
+    /// let heightfield = Heightfield::new(11, 11, |u, _| u);
+    /// let ray = Ray::new(Point::new(0.25, 5., 0.5), Vector::new(0., -1., 0.));
+    /// let xs = heightfield.intersect(&ray);
+    /// assert!((xs[0].t - 4.75).abs() < 1e-5);
+    /// ```
+    pub fn new(width: usize, depth: usize, elevation: impl Fn(f64, f64) -> f64) -> Self {
+        assert!(width >= 2);
+        assert!(depth >= 2);
+        let mut heights = Vec::with_capacity(width * depth);
+        for z in 0..depth {
+            for x in 0..width {
+                let u = x as f64 / (width - 1) as f64;
+                let v = z as f64 / (depth - 1) as f64;
+                heights.push(elevation(u, v));
+            }
+        }
+        let min = heights.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = heights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Self {
+            width,
+            depth,
+            heights,
+            min,
+            max,
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Creates a `Heightfield` whose elevations are the luminance of the
+    /// canvas pixels.
+    ///
+    /// Pixel `(x, y)` of the canvas is mapped to sample `(x, z)` of the grid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the canvas width or height is lower than 2.
+    pub fn from_canvas(canvas: &Canvas) -> Self {
+        let (width, height) = (canvas.width(), canvas.height());
+        Self::new(width, height, |u, v| {
+            let x = (u * (width - 1) as f64).round() as usize;
+            let y = (v * (height - 1) as f64).round() as usize;
+            canvas.get(x, y).luminance()
+        })
+    }
+
+    /// Get the number of samples along the `x` axis.
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the number of samples along the `z` axis.
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the elevation of specified sample.
+    pub fn height(&self, x: usize, z: usize) -> f64 {
+        assert!(x < self.width);
+        assert!(z < self.depth);
+        self.heights[x + z * self.width]
+    }
+
+    fn cells(&self) -> (usize, usize) {
+        (self.width - 1, self.depth - 1)
+    }
+
+    /// Returns the cell containing `(x, z)` and the cell relative coordinates.
+    fn cell(&self, x: f64, z: f64) -> (usize, usize, f64, f64) {
+        let (nx, nz) = self.cells();
+        let i = ((x * nx as f64).floor().max(0.) as usize).min(nx - 1);
+        let j = ((z * nz as f64).floor().max(0.) as usize).min(nz - 1);
+        (i, j, x * nx as f64 - i as f64, z * nz as f64 - j as f64)
+    }
+
+    /// Returns the bilinear coefficients `(h00, e, f, g)` of a cell such that
+    /// `h(u, v) = h00 + e*u + f*v + g*u*v`.
+    fn patch(&self, i: usize, j: usize) -> (f64, f64, f64, f64) {
+        let h00 = self.height(i, j);
+        let h10 = self.height(i + 1, j);
+        let h01 = self.height(i, j + 1);
+        let h11 = self.height(i + 1, j + 1);
+        (h00, h10 - h00, h01 - h00, h00 - h10 - h01 + h11)
+    }
+
+    /// Returns the normal of the patch of a cell at cell relative coordinates.
+    fn normal(&self, i: usize, j: usize, u: f64, v: f64) -> Vector {
+        let (nx, nz) = self.cells();
+        let (_, e, f, g) = self.patch(i, j);
+        let dx = (e + g * v) * nx as f64;
+        let dz = (f + g * u) * nz as f64;
+        Vector::new(-dx, 1., -dz).normalized()
+    }
+
+    /// Returns the closest intersection of the ray with the patch of a cell
+    /// within `[t_min, t_max]`.
+    fn intersect_cell(&self, ray: &Ray, i: usize, j: usize, t_min: f64, t_max: f64) -> Option<f64> {
+        let (nx, nz) = self.cells();
+        let (h00, e, f, g) = self.patch(i, j);
+        let (au, bu) = (ray.origin.x * nx as f64 - i as f64, ray.direction.x * nx as f64);
+        let (av, bv) = (ray.origin.z * nz as f64 - j as f64, ray.direction.z * nz as f64);
+        let a = -g * bu * bv;
+        let b = ray.direction.y - (e * bu + f * bv + g * (au * bv + bu * av));
+        let c = ray.origin.y - (h00 + e * au + f * av + g * au * av);
+        let roots = if float_eq(a, 0.) {
+            if b == 0. {
+                return None;
+            }
+            [-c / b, f64::NAN]
+        } else {
+            let discriminant = b * b - 4. * a * c;
+            if discriminant < 0. {
+                return None;
+            }
+            let t1 = (-b - discriminant.sqrt()) / (2. * a);
+            let t2 = (-b + discriminant.sqrt()) / (2. * a);
+            [t1.min(t2), t1.max(t2)]
+        };
+        roots.into_iter().find(|t| *t >= t_min && *t <= t_max)
+    }
+}
+
+impl Shape for Heightfield {
+    fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    fn attributes_mut(&mut self) -> &mut Attributes {
+        &mut self.attributes
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds::new(Point::new(0., self.min, 0.), Point::new(1., self.max, 1.))
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let (t_min, t_max) = bounds(ray, self.min, self.max);
+        let t_min = t_min.max(0.);
+        if t_min > t_max {
+            return vec![];
+        }
+
+        // grid DDA from the cell where the ray enters the bounding box
+        let (nx, nz) = self.cells();
+        let entry = ray.position(t_min);
+        let (mut i, mut j, _, _) = self.cell(entry.x, entry.z);
+        let (step_x, delta_x, mut next_x) = traversal(ray.origin.x, ray.direction.x, i, nx);
+        let (step_z, delta_z, mut next_z) = traversal(ray.origin.z, ray.direction.z, j, nz);
+        let mut t = t_min;
+        while t <= t_max {
+            let exit = next_x.min(next_z).min(t_max);
+            if let Some(t) = self.intersect_cell(ray, i, j, t, exit) {
+                let point = ray.position(t);
+                let u = point.x * nx as f64 - i as f64;
+                let v = point.z * nz as f64 - j as f64;
+                return vec![Intersection::new(t, self, self.normal(i, j, u, v))];
+            }
+            if exit >= t_max {
+                break;
+            }
+            if next_x < next_z {
+                if (step_x < 0 && i == 0) || (step_x > 0 && i + 1 == nx) {
+                    break;
+                }
+                i = (i as isize + step_x) as usize;
+                next_x += delta_x;
+            } else {
+                if (step_z < 0 && j == 0) || (step_z > 0 && j + 1 == nz) {
+                    break;
+                }
+                j = (j as isize + step_z) as usize;
+                next_z += delta_z;
+            }
+            t = exit;
+        }
+        vec![]
+    }
+
+    fn local_normal_at(&self, point: Point, _time: f64) -> Vector {
+        let (i, j, u, v) = self.cell(point.x, point.z);
+        self.normal(i, j, u, v)
+    }
+}
+
+/// Returns the `t` range of the ray within the box `[0,1]x[min,max]x[0,1]`.
+fn bounds(ray: &Ray, min: f64, max: f64) -> (f64, f64) {
+    let (x_min, x_max) = slab(ray.origin.x, ray.direction.x, 0., 1.);
+    let (y_min, y_max) = slab(ray.origin.y, ray.direction.y, min, max);
+    let (z_min, z_max) = slab(ray.origin.z, ray.direction.z, 0., 1.);
+    (x_min.max(y_min).max(z_min), x_max.min(y_max).min(z_max))
+}
+
+fn slab(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+    if direction == 0. {
+        if origin < min || origin > max {
+            return (f64::INFINITY, f64::NEG_INFINITY);
+        }
+        return (f64::NEG_INFINITY, f64::INFINITY);
+    }
+    let t1 = (min - origin) / direction;
+    let t2 = (max - origin) / direction;
+    (t1.min(t2), t1.max(t2))
+}
+
+/// Returns the cell step, the `t` distance between two cell boundaries and
+/// the `t` of the next cell boundary along one axis.
+fn traversal(origin: f64, direction: f64, cell: usize, cells: usize) -> (isize, f64, f64) {
+    let size = 1. / cells as f64;
+    if direction > 0. {
+        let next = (cell + 1) as f64 * size;
+        (1, size / direction, (next - origin) / direction)
+    } else if direction < 0. {
+        let next = cell as f64 * size;
+        (-1, -size / direction, (next - origin) / direction)
+    } else {
+        (0, f64::INFINITY, f64::INFINITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Color, Matrix};
+
+    use super::*;
+
+    #[test]
+    fn new() {
+        let heightfield = Heightfield::new(3, 2, |u, v| u + 2. * v);
+        assert_eq!(heightfield.width(), 3);
+        assert_eq!(heightfield.depth(), 2);
+        assert!(float_eq(heightfield.height(0, 0), 0.));
+        assert!(float_eq(heightfield.height(1, 0), 0.5));
+        assert!(float_eq(heightfield.height(2, 0), 1.));
+        assert!(float_eq(heightfield.height(0, 1), 2.));
+        assert!(float_eq(heightfield.height(2, 1), 3.));
+        assert_eq!(
+            heightfield.bounds(),
+            Bounds::new(Point::new(0., 0., 0.), Point::new(1., 3., 1.))
+        );
+    }
+
+    #[test]
+    fn from_canvas() {
+        let mut canvas = Canvas::new(2, 3);
+        *canvas.get_mut(1, 2) = Color::white();
+        *canvas.get_mut(0, 1) = Color::new(0.5, 0.5, 0.5);
+        let heightfield = Heightfield::from_canvas(&canvas);
+        assert_eq!(heightfield.width(), 2);
+        assert_eq!(heightfield.depth(), 3);
+        assert!(float_eq(heightfield.height(1, 2), 1.));
+        assert!(float_eq(heightfield.height(0, 1), 0.5));
+        assert!(float_eq(heightfield.height(0, 0), 0.));
+    }
+
+    #[test]
+    fn intersect_flat() {
+        let heightfield = Heightfield::new(4, 4, |_, _| 0.5);
+        let ray = Ray::new(Point::new(0.3, 2., 0.7), Vector::new(0., -1., 0.));
+        let xs = heightfield.intersect(&ray);
+        assert_eq!(xs.len(), 1);
+        assert!(float_eq(xs[0].t, 1.5));
+        assert_eq!(xs[0].normal, Vector::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn intersect_slope() {
+        let heightfield = Heightfield::new(5, 5, |u, _| u);
+        let ray = Ray::new(Point::new(0.25, 2., 0.5), Vector::new(0., -1., 0.));
+        let xs = heightfield.intersect(&ray);
+        assert!(float_eq(xs[0].t, 1.75));
+        assert_eq!(xs[0].normal, Vector::new(-1., 1., 0.).normalized());
+    }
+
+    #[test]
+    fn intersect_miss() {
+        let heightfield = Heightfield::new(5, 5, |u, _| u);
+        let ray = Ray::new(Point::new(1.5, 2., 0.5), Vector::new(0., -1., 0.));
+        assert!(heightfield.intersect(&ray).is_empty());
+        let ray = Ray::new(Point::new(0.5, 2., 0.5), Vector::new(0., 1., 0.));
+        assert!(heightfield.intersect(&ray).is_empty());
+        let ray = Ray::new(Point::new(0.75, 0.9, 0.5), Vector::new(0., 1., 0.));
+        assert!(heightfield.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_traversal() {
+        // single bump in the middle of a flat 5x5 grid
+        let heightfield = Heightfield::new(5, 5, |u, v| if u == 0.5 && v == 0.5 { 1. } else { 0. });
+        let ray = Ray::new(Point::new(-1., 0.5, 0.5), Vector::new(1., 0., 0.));
+        let xs = heightfield.intersect(&ray);
+        assert_eq!(xs.len(), 1);
+        assert!(float_eq(xs[0].t, 1.375));
+
+        let ray = Ray::new(Point::new(2., 0.5, 0.5), Vector::new(-1., 0., 0.));
+        let xs = heightfield.intersect(&ray);
+        assert!(float_eq(xs[0].t, 1.375));
+
+        let ray = Ray::new(Point::new(-1., 0.5, 0.1), Vector::new(1., 0., 0.));
+        assert!(heightfield.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_diagonal() {
+        let heightfield = Heightfield::new(9, 9, |u, v| u * v);
+        let ray = Ray::new(Point::new(-1., 0.25, -1.), Vector::new(1., 0., 1.));
+        let xs = heightfield.intersect(&ray);
+        assert!(float_eq(xs[0].t, 1.5));
+        let point = ray.position(xs[0].t);
+        assert_eq!(point, Point::new(0.5, 0.25, 0.5));
+    }
+
+    #[test]
+    fn intersect_transformed() {
+        let mut heightfield = Heightfield::new(4, 4, |_, _| 1.);
+        heightfield.set_transform(Matrix::scaling(10., 2., 10.));
+        let ray = Ray::new(Point::new(5., 5., 5.), Vector::new(0., -1., 0.));
+        let xs = heightfield.intersect(&ray);
+        assert!(float_eq(xs[0].t, 3.));
+    }
+}
@@ -0,0 +1,85 @@
+/// Weighting of the samples of several strategies in multiple importance
+/// sampling.
+///
+/// The weight of a sample of a strategy is computed from the densities of
+/// all strategies for the sample, each multiplied by its number of samples.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Heuristic {
+    /// weights proportional to the densities
+    Balance,
+    /// weights proportional to the squared densities, lowers variance when
+    /// one strategy is much better than the other
+    #[default]
+    Power,
+}
+
+impl Heuristic {
+    /// Returns the weight of a sample of a strategy from its weighted density
+    /// and the weighted density of the other strategy.
+    ///
+    /// Samples of strategies with an infinite density (e.g. point lights)
+    /// cannot be produced by other strategies and get the full weight.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Heuristic;
+    /// assert_eq!(Heuristic::Balance.weight(3., 1.), 0.75);
+    /// assert_eq!(Heuristic::Power.weight(2., 1.), 0.8);
+    /// assert_eq!(Heuristic::Power.weight(f64::INFINITY, 1.), 1.);
+    /// ```
+    pub fn weight(&self, pdf: f64, other: f64) -> f64 {
+        if pdf.is_infinite() {
+            return 1.;
+        }
+        if pdf <= 0. {
+            return 0.;
+        }
+        // ratio form avoids overflows of large densities
+        1. / (1. + self.power(other / pdf))
+    }
+
+    /// Returns a ratio of densities raised to the exponent of the heuristic.
+    pub(crate) fn power(&self, ratio: f64) -> f64 {
+        match self {
+            Self::Balance => ratio,
+            Self::Power => ratio * ratio,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::float_eq;
+
+    use super::*;
+
+    #[test]
+    fn balance() {
+        assert!(float_eq(Heuristic::Balance.weight(1., 1.), 0.5));
+        assert!(float_eq(Heuristic::Balance.weight(1., 3.), 0.25));
+        assert!(float_eq(Heuristic::Balance.weight(2., 0.), 1.));
+        assert!(float_eq(Heuristic::Balance.weight(0., 2.), 0.));
+    }
+
+    #[test]
+    fn power() {
+        assert!(float_eq(Heuristic::Power.weight(1., 1.), 0.5));
+        assert!(float_eq(Heuristic::Power.weight(1., 3.), 0.1));
+        assert!(float_eq(Heuristic::Power.weight(1e300, 1e300), 0.5));
+    }
+
+    #[test]
+    fn sum() {
+        for heuristic in [Heuristic::Balance, Heuristic::Power] {
+            let (a, b) = (0.7, 2.3);
+            assert!(float_eq(heuristic.weight(a, b) + heuristic.weight(b, a), 1.));
+        }
+    }
+
+    #[test]
+    fn infinite() {
+        assert!(float_eq(Heuristic::Balance.weight(f64::INFINITY, 5.), 1.));
+        assert!(float_eq(Heuristic::Power.weight(5., f64::INFINITY), 0.));
+    }
+}
@@ -0,0 +1,201 @@
+use std::rc::Rc;
+
+use crate::{Attributes, Bounds, Intersection, Material, Point, Ray, Shape, Vector};
+
+/// Placement of shared geometry in the scene.
+///
+/// The geometry (usually a [`Group`](crate::Group)) is reference counted so
+/// that any number of instances can reuse it without duplicating it, each
+/// instance having its own transform and optionally overriding the materials
+/// of the geometry.
+#[derive(Clone, Debug)]
+pub struct Instance {
+    geometry: Rc<dyn Shape>,
+    attributes: Attributes,
+    material: Option<Material>,
+}
+
+impl Instance {
+    /// Creates an `Instance` of specified geometry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::rc::Rc;
+    /// # use ray_tracer_challenge::{Instance, Matrix, Point, Quadric, Ray, Shape, Vector};
+    /// let geometry: Rc<dyn Shape> = Rc::new(Quadric::sphere());
+    /// let mut left = Instance::new(geometry.clone());
+    /// left.set_transform(Matrix::translation(-2., 0., 0.));
+    /// let mut right = Instance::new(geometry.clone());
+    /// right.set_transform(Matrix::translation(2., 0., 0.));
+    /// let ray = Ray::new(Point::new(2., 0., -5.), Vector::new(0., 0., 1.));
+    /// assert!(left.intersect(&ray).is_empty());
+    /// assert_eq!(right.intersect(&ray).len(), 2);
+    /// ```
+    pub fn new(geometry: Rc<dyn Shape>) -> Self {
+        Self {
+            geometry,
+            attributes: Attributes::default(),
+            material: None,
+        }
+    }
+
+    /// Get the shared geometry of the instance.
+    #[must_use]
+    pub fn geometry(&self) -> &Rc<dyn Shape> {
+        &self.geometry
+    }
+
+    /// Removes the material override set by
+    /// [`Shape::set_material`].
+    pub fn clear_material(&mut self) {
+        self.material = None;
+    }
+}
+
+impl Shape for Instance {
+    fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    fn attributes_mut(&mut self) -> &mut Attributes {
+        &mut self.attributes
+    }
+
+    /// Returns the material override if any, or the material of the geometry.
+    fn material(&self) -> &Material {
+        self.material.as_ref().unwrap_or_else(|| self.geometry.material())
+    }
+
+    /// Sets the material overriding the materials of the geometry.
+    fn set_material(&mut self, material: Material) {
+        self.material = Some(material);
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.geometry.parent_bounds()
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut intersections = self.geometry.intersect(ray);
+        if self.material.is_some() {
+            // shading must use the override instead of the hit geometry
+            for intersection in intersections.iter_mut() {
+                intersection.object = self;
+            }
+        }
+        intersections
+    }
+
+    fn local_normal_at(&self, point: Point, time: f64) -> Vector {
+        self.geometry.normal_at(point, time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{util::float_eq, Color, Group, Matrix, Quadric};
+
+    use super::*;
+
+    #[test]
+    fn shared() {
+        let geometry: Rc<dyn Shape> = Rc::new(Quadric::sphere());
+        let instances: Vec<_> = (0..10).map(|_| Instance::new(geometry.clone())).collect();
+        assert_eq!(Rc::strong_count(&geometry), 11);
+        assert!(Rc::ptr_eq(instances[0].geometry(), &geometry));
+    }
+
+    #[test]
+    fn bounds() {
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::scaling(2., 1., 1.));
+        let mut instance = Instance::new(Rc::new(sphere));
+        instance.set_transform(Matrix::translation(0., 3., 0.));
+        assert_eq!(
+            instance.bounds(),
+            Bounds::new(Point::new(-2., -1., -1.), Point::new(2., 1., 1.))
+        );
+        assert_eq!(
+            instance.parent_bounds(),
+            Bounds::new(Point::new(-2., 2., -1.), Point::new(2., 4., 1.))
+        );
+    }
+
+    #[test]
+    fn intersect_transformed() {
+        let geometry: Rc<dyn Shape> = Rc::new(Quadric::sphere());
+        let mut instance = Instance::new(geometry.clone());
+        instance.set_transform(Matrix::translation(0., 0., 2.) * Matrix::scaling(2., 2., 2.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = instance.intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        assert!(float_eq(xs[0].t, 5.));
+        assert!(float_eq(xs[1].t, 9.));
+        assert_eq!(xs[0].normal, Vector::new(0., 0., -1.));
+        assert!(std::ptr::addr_eq(xs[0].object, geometry.as_ref()));
+    }
+
+    #[test]
+    fn intersect_group() {
+        let mut group = Group::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::translation(1., 0., 0.));
+        group.add(sphere);
+        group.set_transform(Matrix::scaling(1., 1., 0.5));
+        let mut instance = Instance::new(Rc::new(group));
+        instance.set_transform(Matrix::translation(-1., 0., 0.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = instance.intersect(&ray);
+        assert!(float_eq(xs[0].t, 4.5));
+        assert!(float_eq(xs[1].t, 5.5));
+        assert_eq!(xs[0].normal, Vector::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn material_override() {
+        let mut sphere = Quadric::sphere();
+        let material = Material {
+            color: Color::red(),
+            ..Material::default()
+        };
+        sphere.set_material(material.clone());
+        let mut instance = Instance::new(Rc::new(sphere));
+        assert_eq!(instance.material(), &material);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        assert_eq!(instance.intersect(&ray)[0].object.material(), &material);
+
+        let other = Material {
+            color: Color::blue(),
+            ..Material::default()
+        };
+        instance.set_material(other.clone());
+        assert_eq!(instance.material(), &other);
+        assert_eq!(instance.intersect(&ray)[0].object.material(), &other);
+
+        instance.clear_material();
+        assert_eq!(instance.intersect(&ray)[0].object.material(), &material);
+    }
+
+    #[test]
+    fn normal() {
+        let mut instance = Instance::new(Rc::new(Quadric::sphere()));
+        instance.set_transform(Matrix::translation(0., 1., 0.));
+        let value = 2f64.sqrt() / 2.;
+        let normal = instance.normal_at(Point::new(0., 1. + value, -value), 0.);
+        assert_eq!(normal, Vector::new(0., value, -value));
+    }
+
+    #[test]
+    fn normal_group() {
+        let mut group = Group::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::translation(1., 0., 0.));
+        group.add(sphere);
+        let mut instance = Instance::new(Rc::new(group));
+        instance.set_transform(Matrix::translation(0., 2., 0.) * Matrix::scaling(1., 2., 1.));
+        let ray = Ray::new(Point::new(1.3, 2.5, -5.), Vector::new(0., 0., 1.));
+        let hit = instance.intersect(&ray)[0];
+        assert_eq!(instance.normal_at(ray.position(hit.t), 0.), hit.normal);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{Shape, Vector};
+
+/// Intersection between a ray and a [`Shape`].
+#[derive(Copy, Clone, Debug)]
+pub struct Intersection<'a> {
+    /// distance along the ray
+    pub t: f64,
+    /// intersected shape
+    pub object: &'a dyn Shape,
+    /// surface normal at the intersection point
+    pub normal: Vector,
+}
+
+impl<'a> Intersection<'a> {
+    /// Creates an `Intersection` at distance `t` of specified object with
+    /// specified surface normal.
+    pub fn new(t: f64, object: &'a dyn Shape, normal: Vector) -> Self {
+        Self { t, object, normal }
+    }
+
+    /// Returns the visible intersection (lowest non-negative `t`) if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Intersection, Sdf, Vector};
+    /// let sdf = Sdf::sphere(1.);
+    /// let normal = Vector::new(0., 0., -1.);
+    /// let xs = [
+    ///     Intersection::new(5., &sdf, normal),
+    ///     Intersection::new(-3., &sdf, normal),
+    ///     Intersection::new(2., &sdf, normal),
+    /// ];
+    /// assert_eq!(Intersection::hit(&xs).unwrap().t, 2.);
+    /// ```
+    pub fn hit(intersections: &[Intersection<'a>]) -> Option<Intersection<'a>> {
+        intersections
+            .iter()
+            .filter(|i| i.t >= 0.)
+            .min_by(|a, b| a.t.total_cmp(&b.t))
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{util::float_eq, Sdf};
+
+    use super::*;
+
+    #[test]
+    fn new() {
+        let sdf = Sdf::sphere(1.);
+        let normal = Vector::new(0., 1., 0.);
+        let intersection = Intersection::new(3.5, &sdf, normal);
+        assert!(float_eq(intersection.t, 3.5));
+        assert_eq!(intersection.normal, normal);
+    }
+
+    #[test]
+    fn hit_positive() {
+        let sdf = Sdf::sphere(1.);
+        let normal = Vector::new(0., 1., 0.);
+        let xs = [Intersection::new(2., &sdf, normal), Intersection::new(1., &sdf, normal)];
+        assert!(float_eq(Intersection::hit(&xs).unwrap().t, 1.));
+    }
+
+    #[test]
+    fn hit_some_negative() {
+        let sdf = Sdf::sphere(1.);
+        let normal = Vector::new(0., 1., 0.);
+        let xs = [
+            Intersection::new(1., &sdf, normal),
+            Intersection::new(-1., &sdf, normal),
+        ];
+        assert!(float_eq(Intersection::hit(&xs).unwrap().t, 1.));
+    }
+
+    #[test]
+    fn hit_all_negative() {
+        let sdf = Sdf::sphere(1.);
+        let normal = Vector::new(0., 1., 0.);
+        let xs = [
+            Intersection::new(-2., &sdf, normal),
+            Intersection::new(-1., &sdf, normal),
+        ];
+        assert!(Intersection::hit(&xs).is_none());
+    }
+
+    #[test]
+    fn hit_lowest() {
+        let sdf = Sdf::sphere(1.);
+        let normal = Vector::new(0., 1., 0.);
+        let xs = [
+            Intersection::new(5., &sdf, normal),
+            Intersection::new(7., &sdf, normal),
+            Intersection::new(-3., &sdf, normal),
+            Intersection::new(2., &sdf, normal),
+        ];
+        assert!(float_eq(Intersection::hit(&xs).unwrap().t, 2.));
+    }
+}
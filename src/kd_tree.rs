@@ -0,0 +1,144 @@
+use crate::Point;
+
+/// Balanced kd-tree of items located at points.
+///
+/// The tree is stored implicitly in a vector: the root of each subtree is the
+/// median element of its range, the left and right subtrees lie before and
+/// after it.
+#[derive(Clone, Debug)]
+pub(crate) struct KdTree<T> {
+    nodes: Vec<Node<T>>,
+}
+
+/// Element of a [`KdTree`].
+#[derive(Clone, Debug)]
+struct Node<T> {
+    point: Point,
+    /// axis splitting the subtree rooted at the node
+    axis: usize,
+    item: T,
+}
+
+impl<T> KdTree<T> {
+    /// Creates a `KdTree` from items and their positions.
+    pub(crate) fn new(items: Vec<(Point, T)>) -> Self {
+        let mut nodes: Vec<Node<T>> = items
+            .into_iter()
+            .map(|(point, item)| Node { point, axis: 0, item })
+            .collect();
+        Self::build(&mut nodes);
+        Self { nodes }
+    }
+
+    /// Returns the number of items of the tree.
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Calls `f` with each item within `radius` of `point` and its squared
+    /// distance to the point.
+    pub(crate) fn within(&self, point: Point, radius: f64, mut f: impl FnMut(&T, f64)) {
+        self.search(&self.nodes, point, radius * radius, &mut f);
+    }
+
+    /// Splits the nodes at the median along the axis of largest extent and
+    /// builds both halves recursively.
+    fn build(nodes: &mut [Node<T>]) {
+        if nodes.len() <= 1 {
+            return;
+        }
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for node in nodes.iter() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(coordinate(node.point, axis));
+                max[axis] = max[axis].max(coordinate(node.point, axis));
+            }
+        }
+        let axis = (0..3)
+            .max_by(|&a, &b| (max[a] - min[a]).total_cmp(&(max[b] - min[b])))
+            .unwrap_or(0);
+        let middle = nodes.len() / 2;
+        nodes.select_nth_unstable_by(middle, |a, b| {
+            coordinate(a.point, axis).total_cmp(&coordinate(b.point, axis))
+        });
+        nodes[middle].axis = axis;
+        let (left, right) = nodes.split_at_mut(middle);
+        Self::build(left);
+        Self::build(&mut right[1..]);
+    }
+
+    fn search(&self, nodes: &[Node<T>], point: Point, radius2: f64, f: &mut impl FnMut(&T, f64)) {
+        if nodes.is_empty() {
+            return;
+        }
+        let middle = nodes.len() / 2;
+        let node = &nodes[middle];
+        let vector = node.point - point;
+        let distance2 = vector.dot(vector);
+        if distance2 <= radius2 {
+            f(&node.item, distance2);
+        }
+        if nodes.len() == 1 {
+            return;
+        }
+        let offset = coordinate(point, node.axis) - coordinate(node.point, node.axis);
+        let (near, far) = if offset < 0. {
+            (&nodes[..middle], &nodes[middle + 1..])
+        } else {
+            (&nodes[middle + 1..], &nodes[..middle])
+        };
+        self.search(near, point, radius2, f);
+        if offset * offset <= radius2 {
+            self.search(far, point, radius2, f);
+        }
+    }
+}
+
+/// Returns the coordinate of a point along an axis (`0` for `x`, `1` for `y`
+/// and `2` for `z`).
+fn coordinate(point: Point, axis: usize) -> f64 {
+    match axis {
+        0 => point.x,
+        1 => point.y,
+        _ => point.z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::Rng;
+
+    use super::*;
+
+    #[test]
+    fn empty() {
+        let tree: KdTree<usize> = KdTree::new(vec![]);
+        assert_eq!(tree.len(), 0);
+        tree.within(Point::new(0., 0., 0.), 10., |_, _| panic!("tree is empty"));
+    }
+
+    #[test]
+    fn within() {
+        let mut rng = Rng::from_values(&[1.]);
+        let points: Vec<Point> = (0..1000)
+            .map(|_| Point::new(rng.next_f64(), rng.next_f64() * 2., rng.next_f64() * 0.5))
+            .collect();
+        let tree = KdTree::new(points.iter().copied().enumerate().map(|(i, p)| (p, i)).collect());
+        assert_eq!(tree.len(), 1000);
+        for _ in 0..20 {
+            let center = Point::new(rng.next_f64(), rng.next_f64() * 2., rng.next_f64() * 0.5);
+            let mut found = vec![];
+            tree.within(center, 0.2, |&i, distance2| {
+                let vector = points[i] - center;
+                assert!((vector.dot(vector) - distance2).abs() < 1e-12);
+                found.push(i);
+            });
+            found.sort_unstable();
+            let expected: Vec<usize> = (0..points.len())
+                .filter(|&i| (points[i] - center).len() <= 0.2)
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+}
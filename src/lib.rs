@@ -5,19 +5,141 @@
 #![deny(warnings)]
 #![deny(missing_docs)]
 
+pub(crate) mod kd_tree;
+pub(crate) mod spectrum;
 pub(crate) mod util;
 
+mod ambient_occlusion;
+pub use ambient_occlusion::AmbientOcclusion;
+
+mod area_light;
+pub use area_light::AreaLight;
+
+mod attenuation;
+pub use attenuation::Attenuation;
+
+mod attributes;
+pub use attributes::Attributes;
+
+mod bidirectional_path_tracer;
+pub use bidirectional_path_tracer::BidirectionalPathTracer;
+
+mod blob;
+pub use blob::Blob;
+
+mod bounds;
+pub use bounds::Bounds;
+
+mod bsdf;
+pub use bsdf::Bsdf;
+
+mod camera;
+pub use camera::Camera;
+
 mod canvas;
 pub use canvas::Canvas;
 
 mod color;
 pub use color::Color;
 
+mod computations;
+pub use computations::Computations;
+
+mod density;
+pub use density::Density;
+
+mod dielectric;
+pub use dielectric::Dielectric;
+
+mod directional_light;
+pub use directional_light::DirectionalLight;
+
+mod dispersion;
+pub use dispersion::Dispersion;
+
+mod emitter;
+pub use emitter::Emitter;
+
+mod environment;
+pub use environment::Environment;
+
+mod filter;
+pub use filter::Filter;
+
+mod group;
+pub use group::Group;
+
+mod hdr;
+pub use hdr::HdrError;
+
+mod heightfield;
+pub use heightfield::Heightfield;
+
+mod heuristic;
+pub use heuristic::Heuristic;
+
+mod instance;
+pub use instance::Instance;
+
+mod intersection;
+pub use intersection::Intersection;
+
+mod light;
+pub use light::{Light, LightSample};
+
+mod material;
+pub use material::Material;
+
 mod matrix;
 pub use matrix::Matrix;
 
+mod medium;
+pub use medium::Medium;
+
+mod microfacet;
+pub use microfacet::Microfacet;
+
+mod motion;
+pub use motion::Motion;
+
+mod path_tracer;
+pub use path_tracer::PathTracer;
+
+mod photon_map;
+pub use photon_map::PhotonMap;
+
 mod point;
 pub use point::Point;
 
+mod point_light;
+pub use point_light::PointLight;
+
+mod projection;
+pub use projection::Projection;
+
+mod quadric;
+pub use quadric::Quadric;
+
+mod ray;
+pub use ray::Ray;
+
+mod sdf;
+pub use sdf::Sdf;
+
+mod shape;
+pub use shape::Shape;
+
+mod spectral_path_tracer;
+pub use spectral_path_tracer::SpectralPathTracer;
+
+mod spot_light;
+pub use spot_light::SpotLight;
+
+mod stereo_rig;
+pub use stereo_rig::StereoRig;
+
 mod vector;
 pub use vector::Vector;
+
+mod world;
+pub use world::World;
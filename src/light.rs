@@ -0,0 +1,69 @@
+use std::fmt::Debug;
+
+use crate::{Color, Emitter, Point, Vector};
+
+/// Source of light illuminating the shapes of a [`World`](crate::World).
+pub trait Light: Debug {
+    /// Returns the samples of the light as seen from specified point.
+    ///
+    /// The light contribution at the point is the average of the
+    /// contributions of its samples.
+    fn samples(&self, point: Point) -> Vec<LightSample>;
+
+    /// Returns the sample the light would produce at specified point along
+    /// specified direction, `None` if the direction misses the light.
+    ///
+    /// Used to weight the contribution of directions sampled independently
+    /// of the light (e.g. by a BSDF). Lights reduced to a point or a
+    /// direction cannot be reached by such directions and return `None`.
+    fn sample_along(&self, _point: Point, _direction: Vector) -> Option<LightSample> {
+        None
+    }
+
+    /// Returns the physical description of the light used by integrators
+    /// tracing paths from the lights, `None` for lights which cannot emit
+    /// such paths (lights at infinity and lights whose intensity does not
+    /// follow the inverse-square law).
+    fn emitter(&self) -> Option<Emitter> {
+        None
+    }
+}
+
+/// Light reaching a point from a single position of a [`Light`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LightSample {
+    /// unit vector from the illuminated point towards the light
+    pub direction: Vector,
+    /// distance from the illuminated point to the light
+    pub distance: f64,
+    /// intensity of the light reaching the point (radiance divided by the
+    /// density of the sample)
+    pub intensity: Color,
+    /// density (per steradian) of sampling the direction, infinite for lights
+    /// reduced to a point or a direction
+    pub pdf: f64,
+}
+
+impl LightSample {
+    /// Creates the sample of a light located at `position` as seen from
+    /// `point` (with infinite density).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, LightSample, Point, Vector};
+    /// let sample = LightSample::at(Point::new(0., 0., -10.), Point::new(0., 0., -5.), Color::white());
+    /// assert_eq!(sample.direction, Vector::new(0., 0., -1.));
+    /// assert_eq!(sample.distance, 5.);
+    /// ```
+    pub fn at(position: Point, point: Point, intensity: Color) -> Self {
+        let vector = position - point;
+        let distance = vector.len();
+        Self {
+            direction: vector / distance,
+            distance,
+            intensity,
+            pdf: f64::INFINITY,
+        }
+    }
+}
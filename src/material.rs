@@ -0,0 +1,338 @@
+use crate::{util::float_eq, Color, Dielectric, Light, Medium, Microfacet, Point, Vector};
+
+/// Surface attributes of a shape for the Phong reflection model.
+#[derive(Clone, Debug)]
+pub struct Material {
+    /// surface color
+    pub color: Color,
+    /// ambient reflection (light reflected from other objects)
+    pub ambient: f64,
+    /// diffuse reflection (light reflected from matte surface)
+    pub diffuse: f64,
+    /// specular reflection (reflection of the light source itself)
+    pub specular: f64,
+    /// size and tightness of the specular highlight
+    pub shininess: f64,
+    /// light emitted by the surface (black for surfaces that are not light
+    /// sources)
+    pub emission: Color,
+    /// physically based reflectance replacing the diffuse and specular
+    /// reflections in the Monte Carlo integrators (the surface color is the
+    /// base color)
+    pub microfacet: Option<Microfacet>,
+    /// participating medium filling the shape, its surface then only bounds
+    /// the medium and does not reflect light in the path tracer
+    pub medium: Option<Medium>,
+    /// transparent interface refracting the light in the spectral path
+    /// tracer (other integrators shade the surface with its other
+    /// attributes)
+    pub dielectric: Option<Dielectric>,
+}
+
+impl Material {
+    /// Creates a physically based `Material` of specified base color,
+    /// roughness and metalness.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Material, Microfacet};
+    /// let gold = Material::pbr(Color::new(1., 0.78, 0.34), 0.3, 1.);
+    /// assert_eq!(gold.color, Color::new(1., 0.78, 0.34));
+    /// assert_eq!(gold.microfacet, Some(Microfacet::new(0.3, 1.)));
+    /// ```
+    pub fn pbr(base_color: Color, roughness: f64, metalness: f64) -> Self {
+        Self {
+            color: base_color,
+            microfacet: Some(Microfacet::new(roughness, metalness)),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a translucent `Material` (skin, wax, marble) scattering the
+    /// light below its surface, of specified color and mean free path of the
+    /// light inside it per channel.
+    ///
+    /// The shape is filled with the [`Medium`] of matching albedo (see
+    /// [`Medium::from_albedo`]), the path tracer follows the light through
+    /// it with a random walk.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Material, Medium};
+    /// let skin = Material::subsurface(Color::new(0.8, 0.5, 0.4), Color::new(0.5, 0.2, 0.1));
+    /// assert_eq!(skin.color, Color::new(0.8, 0.5, 0.4));
+    /// assert!(skin.medium.is_some());
+    /// ```
+    pub fn subsurface(color: Color, mean_free_path: Color) -> Self {
+        Self {
+            color,
+            medium: Some(Medium::from_albedo(color, mean_free_path)),
+            ..Self::default()
+        }
+    }
+
+    /// Creates a transparent `Material` refracting the light at its surface.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Dielectric, Dispersion, Material};
+    /// let glass = Material::dielectric(Dielectric::new(Dispersion::BK7));
+    /// assert_eq!(glass.dielectric, Some(Dielectric::new(Dispersion::BK7)));
+    /// ```
+    pub fn dielectric(dielectric: Dielectric) -> Self {
+        Self {
+            dielectric: Some(dielectric),
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if the surface emits light.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Material};
+    /// let mut material = Material::default();
+    /// assert!(!material.is_emissive());
+    /// material.emission = Color::new(4., 4., 4.);
+    /// assert!(material.is_emissive());
+    /// ```
+    #[must_use]
+    pub fn is_emissive(&self) -> bool {
+        self.emission != Color::black()
+    }
+
+    /// Returns the color of the material at specified point lit by specified
+    /// light using the Phong reflection model.
+    ///
+    /// `intensity` is the fraction of the light reaching the point (`0` when
+    /// fully in shadow), it scales the diffuse and specular contributions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Material, Point, PointLight, Vector};
+    /// let material = Material::default();
+    /// let light = PointLight::new(Point::new(0., 0., -10.), Color::white());
+    /// let eyev = Vector::new(0., 0., -1.);
+    /// let normalv = Vector::new(0., 0., -1.);
+    /// let color = material.lighting(&light, Point::new(0., 0., 0.), eyev, normalv, 1.);
+    /// assert_eq!(color, Color::new(1.9, 1.9, 1.9));
+    /// ```
+    pub fn lighting(&self, light: &dyn Light, point: Point, eyev: Vector, normalv: Vector, intensity: f64) -> Color {
+        let samples = light.samples(point);
+        if samples.is_empty() {
+            return Color::black();
+        }
+        let mut ambient = Color::black();
+        let mut sum = Color::black();
+        for sample in samples.iter() {
+            let effective_color = self.color * sample.intensity;
+            ambient += effective_color * self.ambient;
+            let light_dot_normal = sample.direction.dot(normalv);
+            if light_dot_normal < 0. {
+                continue;
+            }
+            sum += effective_color * self.diffuse * light_dot_normal;
+            let reflect_dot_eye = (-sample.direction).reflect(normalv).dot(eyev);
+            if reflect_dot_eye > 0. {
+                sum += sample.intensity * self.specular * reflect_dot_eye.powf(self.shininess);
+            }
+        }
+        (ambient + sum * intensity) * (1. / samples.len() as f64)
+    }
+}
+
+impl Default for Material {
+    /// Creates the default material.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Material};
+    /// let material = Material::default();
+    /// assert_eq!(material.color, Color::white());
+    /// assert_eq!(material.ambient, 0.1);
+    /// assert_eq!(material.diffuse, 0.9);
+    /// assert_eq!(material.specular, 0.9);
+    /// assert_eq!(material.shininess, 200.);
+    /// assert_eq!(material.emission, Color::black());
+    /// ```
+    fn default() -> Self {
+        Self {
+            color: Color::white(),
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shininess: 200.,
+            emission: Color::black(),
+            microfacet: None,
+            medium: None,
+            dielectric: None,
+        }
+    }
+}
+
+impl PartialEq for Material {
+    fn eq(&self, other: &Self) -> bool {
+        self.color == other.color
+            && float_eq(self.ambient, other.ambient)
+            && float_eq(self.diffuse, other.diffuse)
+            && float_eq(self.specular, other.specular)
+            && float_eq(self.shininess, other.shininess)
+            && self.emission == other.emission
+            && self.microfacet == other.microfacet
+            && self.medium == other.medium
+            && self.dielectric == other.dielectric
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AreaLight, PointLight};
+
+    use super::*;
+
+    fn lighting(eyev: Vector, normalv: Vector, light: PointLight, intensity: f64) -> Color {
+        Material::default().lighting(&light, Point::new(0., 0., 0.), eyev, normalv, intensity)
+    }
+
+    #[test]
+    fn default() {
+        let material = Material::default();
+        assert_eq!(material.color, Color::new(1., 1., 1.));
+        assert!(float_eq(material.ambient, 0.1));
+        assert!(float_eq(material.diffuse, 0.9));
+        assert!(float_eq(material.specular, 0.9));
+        assert!(float_eq(material.shininess, 200.));
+        assert_eq!(material.emission, Color::black());
+        assert_eq!(material.microfacet, None);
+        assert_eq!(material.medium, None);
+        assert_eq!(material.dielectric, None);
+    }
+
+    #[test]
+    fn pbr() {
+        let material = Material::pbr(Color::red(), 0.25, 0.5);
+        assert_eq!(material.color, Color::red());
+        assert_eq!(material.microfacet, Some(Microfacet::new(0.25, 0.5)));
+        assert_ne!(material, Material::pbr(Color::red(), 0.25, 0.));
+    }
+
+    #[test]
+    fn subsurface() {
+        let material = Material::subsurface(Color::white(), Color::new(1., 2., 4.));
+        assert_eq!(material.color, Color::white());
+        let medium = Medium::from_albedo(Color::white(), Color::new(1., 2., 4.));
+        assert_eq!(material.medium, Some(medium));
+        assert_eq!(material.microfacet, None);
+    }
+
+    #[test]
+    fn eq() {
+        let material = Material::default();
+        let other = Material {
+            ambient: 1.,
+            ..Material::default()
+        };
+        assert_eq!(material, Material::default());
+        assert_ne!(material, other);
+    }
+
+    #[test]
+    fn lighting_eye_between() {
+        let light = PointLight::new(Point::new(0., 0., -10.), Color::white());
+        let color = lighting(Vector::new(0., 0., -1.), Vector::new(0., 0., -1.), light, 1.);
+        assert_eq!(color, Color::new(1.9, 1.9, 1.9));
+    }
+
+    #[test]
+    fn lighting_eye_offset() {
+        let value = 2f64.sqrt() / 2.;
+        let light = PointLight::new(Point::new(0., 0., -10.), Color::white());
+        let color = lighting(Vector::new(0., value, -value), Vector::new(0., 0., -1.), light, 1.);
+        assert_eq!(color, Color::new(1., 1., 1.));
+    }
+
+    #[test]
+    fn lighting_light_offset() {
+        let light = PointLight::new(Point::new(0., 10., -10.), Color::white());
+        let color = lighting(Vector::new(0., 0., -1.), Vector::new(0., 0., -1.), light, 1.);
+        assert_eq!(color, Color::new(0.7364, 0.7364, 0.7364));
+    }
+
+    #[test]
+    fn lighting_reflection() {
+        let value = 2f64.sqrt() / 2.;
+        let light = PointLight::new(Point::new(0., 10., -10.), Color::white());
+        let color = lighting(Vector::new(0., -value, -value), Vector::new(0., 0., -1.), light, 1.);
+        assert_eq!(color, Color::new(1.6364, 1.6364, 1.6364));
+    }
+
+    #[test]
+    fn lighting_behind() {
+        let light = PointLight::new(Point::new(0., 0., 10.), Color::white());
+        let color = lighting(Vector::new(0., 0., -1.), Vector::new(0., 0., -1.), light, 1.);
+        assert_eq!(color, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_shadow() {
+        let light = PointLight::new(Point::new(0., 0., -10.), Color::white());
+        let color = lighting(Vector::new(0., 0., -1.), Vector::new(0., 0., -1.), light, 0.);
+        assert_eq!(color, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_intensity() {
+        let material = Material {
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.,
+            color: Color::white(),
+            ..Material::default()
+        };
+        let light = PointLight::new(Point::new(0., 0., -10.), Color::white());
+        let point = Point::new(0., 0., -1.);
+        let eyev = Vector::new(0., 0., -1.);
+        let normalv = Vector::new(0., 0., -1.);
+        for (intensity, expected) in [(1., 1.), (0.5, 0.55), (0., 0.1)] {
+            let color = material.lighting(&light, point, eyev, normalv, intensity);
+            assert_eq!(color, Color::new(expected, expected, expected));
+        }
+    }
+
+    #[test]
+    fn lighting_area_light() {
+        let mut light = AreaLight::new(
+            Point::new(-0.5, -0.5, -5.),
+            Vector::new(1., 0., 0.),
+            2,
+            Vector::new(0., 1., 0.),
+            2,
+            Color::white(),
+        );
+        light.set_jitter(false);
+        let material = Material {
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.,
+            color: Color::white(),
+            ..Material::default()
+        };
+        let eye = Point::new(0., 0., -5.);
+        let tests = [
+            (Point::new(0., 0., -1.), 0.9965),
+            (Point::new(0., 2f64.sqrt() / 2., -(2f64.sqrt()) / 2.), 0.62318),
+        ];
+        for (point, expected) in tests {
+            let eyev = (eye - point).normalized();
+            let normalv = Vector::new(point.x, point.y, point.z);
+            let color = material.lighting(&light, point, eyev, normalv, 1.);
+            assert_eq!(color, Color::new(expected, expected, expected));
+        }
+    }
+}
@@ -192,6 +192,33 @@ impl Matrix {
         Matrix { elements }
     }
 
+    /// Returns the view transformation matrix orienting the world relative to
+    /// an eye located at `from` looking at `to` with the `up` direction
+    /// pointing approximately upward.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Matrix, Point, Vector};
+    /// let from = Point::new(0., 0., 0.);
+    /// let to = Point::new(0., 0., -1.);
+    /// let up = Vector::new(0., 1., 0.);
+    /// assert_eq!(Matrix::view_transform(from, to, up), Matrix::identity());
+    /// ```
+    pub fn view_transform(from: Point, to: Point, up: Vector) -> Matrix {
+        let forward = (to - from).normalized();
+        let left = forward.cross(up.normalized());
+        let true_up = left.cross(forward);
+        #[rustfmt::skip]
+        let orientation = Matrix::new([
+               left.x,     left.y,     left.z, 0.,
+            true_up.x,  true_up.y,  true_up.z, 0.,
+           -forward.x, -forward.y, -forward.z, 0.,
+                   0.,         0.,         0., 1.,
+        ]);
+        orientation * Matrix::translation(-from.x, -from.y, -from.z)
+    }
+
     /// Get element.
     ///
     /// # Examples
@@ -956,6 +983,45 @@ mod tests {
         assert_eq!(transform * point, Point::new(2., 3., 7.));
     }
 
+    #[test]
+    fn view_transform_default() {
+        let from = Point::new(0., 0., 0.);
+        let to = Point::new(0., 0., -1.);
+        let up = Vector::new(0., 1., 0.);
+        assert_eq!(Matrix::view_transform(from, to, up), Matrix::identity());
+    }
+
+    #[test]
+    fn view_transform_positive_z() {
+        let from = Point::new(0., 0., 0.);
+        let to = Point::new(0., 0., 1.);
+        let up = Vector::new(0., 1., 0.);
+        assert_eq!(Matrix::view_transform(from, to, up), Matrix::scaling(-1., 1., -1.));
+    }
+
+    #[test]
+    fn view_transform_moves_world() {
+        let from = Point::new(0., 0., 8.);
+        let to = Point::new(0., 0., 0.);
+        let up = Vector::new(0., 1., 0.);
+        assert_eq!(Matrix::view_transform(from, to, up), Matrix::translation(0., 0., -8.));
+    }
+
+    #[test]
+    fn view_transform_arbitrary() {
+        let from = Point::new(1., 3., 2.);
+        let to = Point::new(4., -2., 8.);
+        let up = Vector::new(1., 1., 0.);
+        #[rustfmt::skip]
+        let expected = Matrix::new([
+            -0.50709, 0.50709,  0.67612, -2.36643,
+             0.76772, 0.60609,  0.12122, -2.82843,
+            -0.35857, 0.59761, -0.71714,  0.00000,
+             0.00000, 0.00000,  0.00000,  1.00000,
+        ]);
+        assert_eq!(Matrix::view_transform(from, to, up), expected);
+    }
+
     #[test]
     fn chaining_transformation() {
         let point = Point::new(1., 0., 1.);
@@ -0,0 +1,399 @@
+use std::f64::consts::PI;
+
+use crate::{
+    util::{orthonormal_basis, Rng},
+    Color, Density, Ray, Vector,
+};
+
+/// Participating medium absorbing and scattering the light traveling through
+/// it (fog, smoke, murky water).
+///
+/// Coefficients are given per unit distance for each channel, the direction
+/// of scattered light follows the Henyey-Greenstein phase function.
+///
+/// Media with a [`Density`] are heterogeneous: their coefficients are scaled
+/// by the density at each point. Distances to scattering events are then
+/// sampled by delta tracking and transmittance is estimated by ratio
+/// tracking, both against the maximum extinction of the medium.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Medium {
+    /// fraction of the light absorbed per unit distance
+    pub absorption: Color,
+    /// fraction of the light scattered per unit distance
+    pub scattering: Color,
+    /// mean cosine of the scattering angle, in `(-1,1)`: positive values
+    /// scatter forward, negative values backward and `0` uniformly
+    pub asymmetry: f64,
+    /// density scaling the coefficients (uniform density `1` if `None`)
+    pub density: Option<Density>,
+}
+
+impl Medium {
+    const MAX_ASYMMETRY: f64 = 0.99;
+
+    /// Creates a `Medium` from its absorption and scattering coefficients
+    /// and the asymmetry of its phase function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Medium};
+    /// let fog = Medium::new(Color::new(0.1, 0.1, 0.1), Color::new(0.4, 0.4, 0.4), 0.3);
+    /// assert_eq!(fog.extinction(), Color::new(0.5, 0.5, 0.5));
+    /// assert_eq!(fog.transmittance(2.), Color::new(0.36788, 0.36788, 0.36788));
+    /// ```
+    pub fn new(absorption: Color, scattering: Color, asymmetry: f64) -> Self {
+        Self {
+            absorption,
+            scattering,
+            asymmetry: asymmetry.clamp(-Self::MAX_ASYMMETRY, Self::MAX_ASYMMETRY),
+            density: None,
+        }
+    }
+
+    /// Creates an isotropic `Medium` from the mean free path of the light
+    /// (average distance between scattering events) and the fraction of the
+    /// light leaving a thick slab of the medium after any number of
+    /// scattering events, per channel.
+    ///
+    /// The single scattering albedo is obtained by inverting the
+    /// multiple scattering albedo (Chiang et al., practical and controllable
+    /// subsurface scattering for production path tracing).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Medium};
+    /// let wax = Medium::from_albedo(Color::new(1., 0.5, 0.), Color::new(0.5, 0.25, 0.1));
+    /// assert_eq!(wax.extinction(), Color::new(2., 4., 10.));
+    /// // white scatters all the light, black absorbs most of it
+    /// assert!((wax.scattering.r - 2.).abs() < 1e-6);
+    /// assert!(wax.scattering.b < 1e-3);
+    /// ```
+    pub fn from_albedo(albedo: Color, mean_free_path: Color) -> Self {
+        let single = |a: f64| {
+            let a = a.clamp(0., 1.);
+            let s = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+            (1. - s * s).clamp(0., 1.)
+        };
+        let extinction = |d: f64| 1. / d.max(f64::MIN_POSITIVE);
+        let extinction = Color::new(
+            extinction(mean_free_path.r),
+            extinction(mean_free_path.g),
+            extinction(mean_free_path.b),
+        );
+        let scattering = extinction * Color::new(single(albedo.r), single(albedo.g), single(albedo.b));
+        Self::new(extinction - scattering, scattering, 0.)
+    }
+
+    /// Returns the fraction of the light absorbed or scattered per unit
+    /// distance at unit density.
+    pub fn extinction(&self) -> Color {
+        self.absorption + self.scattering
+    }
+
+    /// Returns the fraction of the light traveling over specified distance
+    /// at unit density without being absorbed or scattered.
+    pub fn transmittance(&self, distance: f64) -> Color {
+        let extinction = self.extinction();
+        let channel = |sigma: f64| if sigma <= 0. { 1. } else { (-sigma * distance).exp() };
+        Color::new(channel(extinction.r), channel(extinction.g), channel(extinction.b))
+    }
+
+    /// Returns the density (per steradian) of light arriving from `wi`
+    /// scattered towards `wo`, both directions pointing away from the
+    /// scattering point.
+    pub fn phase(&self, wo: Vector, wi: Vector) -> f64 {
+        let g = self.asymmetry;
+        // light traveling along -wi and leaving along wo
+        let cosine = -wo.dot(wi);
+        let denominator = 1. + g * g - 2. * g * cosine;
+        (1. - g * g) / (4. * PI * denominator * denominator.sqrt())
+    }
+
+    /// Returns a direction `wi` sampled from `wo` proportionally to the
+    /// phase function from two uniform values in `[0,1)`.
+    pub fn sample_phase(&self, wo: Vector, u1: f64, u2: f64) -> Vector {
+        let g = self.asymmetry;
+        let cosine = if g.abs() < 1e-3 {
+            1. - 2. * u1
+        } else {
+            let term = (1. - g * g) / (1. - g + 2. * g * u1);
+            (1. + g * g - term * term) / (2. * g)
+        };
+        let cosine = cosine.clamp(-1., 1.);
+        let sine = (1. - cosine * cosine).sqrt();
+        let phi = 2. * PI * u2;
+        // the light travels along -wi, at the scattering angle from wo
+        let (tangent, bitangent) = orthonormal_basis(wo);
+        -(tangent * (sine * phi.cos()) + bitangent * (sine * phi.sin()) + wo * cosine)
+    }
+
+    /// Returns the fraction of the light traveling along a ray over
+    /// specified distance without being absorbed or scattered, estimated by
+    /// ratio tracking in heterogeneous media.
+    pub(crate) fn transmittance_along(&self, ray: &Ray, distance: f64, rng: &mut Rng) -> Color {
+        let density = match &self.density {
+            Some(density) => density,
+            None => return self.transmittance(distance),
+        };
+        let majorant = max_channel(self.extinction()) * density.max();
+        let (start, end) = match density.extent(ray) {
+            Some((start, end)) if majorant > 0. => (start.max(0.), end.min(distance)),
+            _ => return Color::white(),
+        };
+        let mut transmittance = Color::white();
+        let mut t = start;
+        loop {
+            t -= (1. - rng.next_f64()).ln() / majorant;
+            if t >= end {
+                return transmittance;
+            }
+            let extinction = self.extinction() * density.at(ray.position(t));
+            transmittance *= (Color::white() * majorant - extinction) * (1. / majorant);
+        }
+    }
+
+    /// Samples the distance to the next scattering event along a ray.
+    ///
+    /// Returns the distance (`None` if the ray travels `max_distance`
+    /// without scattering) and the weight of the sample (the ratio of the
+    /// light reaching the sample to its density, times the scattering
+    /// coefficient for scattering events).
+    pub(crate) fn sample(&self, ray: &Ray, max_distance: f64, rng: &mut Rng) -> (Option<f64>, Color) {
+        let density = match &self.density {
+            Some(density) => density,
+            None => return self.sample_distance(max_distance, rng.next_f64(), rng.next_f64()),
+        };
+        let majorant = max_channel(self.extinction()) * density.max();
+        let (start, end) = match density.extent(ray) {
+            Some((start, end)) if majorant > 0. => (start.max(0.), end.min(max_distance)),
+            _ => return (None, Color::white()),
+        };
+        // delta tracking: tentative collisions against the majorant are real
+        // scattering events or null collisions, absorption is accounted for
+        // by the weights
+        let mut weight = Color::white();
+        let mut t = start;
+        loop {
+            t -= (1. - rng.next_f64()).ln() / majorant;
+            if t >= end {
+                return (None, weight);
+            }
+            let value = density.at(ray.position(t));
+            let scattering = self.scattering * value;
+            let null = Color::white() * majorant - self.extinction() * value;
+            let (p_scattering, p_null) = (max_channel(scattering), max_channel(null));
+            if p_scattering + p_null <= 0. {
+                return (None, Color::black());
+            }
+            let total = p_scattering + p_null;
+            if rng.next_f64() * total < p_scattering {
+                return (Some(t), weight * scattering * (total / (majorant * p_scattering)));
+            }
+            weight *= null * (total / (majorant * p_null));
+        }
+    }
+
+    /// Samples the distance to the next scattering event in a homogeneous
+    /// medium from two uniform values in `[0,1)`, the channel driving the
+    /// distance is chosen by `u1`.
+    fn sample_distance(&self, max_distance: f64, u1: f64, u2: f64) -> (Option<f64>, Color) {
+        let extinction = self.extinction();
+        let channels = [extinction.r, extinction.g, extinction.b];
+        let sigma = channels[((u1 * 3.) as usize).min(2)];
+        let distance = if sigma > 0. {
+            -(1. - u2).ln() / sigma
+        } else {
+            f64::INFINITY
+        };
+        let scattered = distance < max_distance;
+        let distance = distance.min(max_distance);
+        let transmittance = self.transmittance(distance);
+        let densities = [transmittance.r, transmittance.g, transmittance.b];
+        // density averaged over the channels
+        let pdf = channels
+            .iter()
+            .zip(densities.iter())
+            .map(|(&sigma, &transmittance)| {
+                if scattered {
+                    sigma * transmittance
+                } else {
+                    transmittance
+                }
+            })
+            .sum::<f64>()
+            / 3.;
+        if pdf <= 0. {
+            return (None, Color::black());
+        }
+        if scattered {
+            (Some(distance), self.scattering * transmittance * (1. / pdf))
+        } else {
+            (None, transmittance * (1. / pdf))
+        }
+    }
+}
+
+/// Returns the largest channel of a color.
+fn max_channel(color: Color) -> f64 {
+    color.r.max(color.g).max(color.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{util::float_eq, Point};
+
+    use super::*;
+
+    #[test]
+    fn new() {
+        let medium = Medium::new(Color::red(), Color::blue(), 2.);
+        assert_eq!(medium.extinction(), Color::new(1., 0., 1.));
+        assert!(float_eq(medium.asymmetry, 0.99));
+    }
+
+    #[test]
+    fn from_albedo() {
+        let medium = Medium::from_albedo(Color::new(0., 0.5, 1.), Color::new(1., 2., 4.));
+        assert_eq!(medium.extinction(), Color::new(1., 0.5, 0.25));
+        assert!(medium.scattering.r < 1e-5);
+        assert!((medium.scattering.g - 0.5 * 0.91173).abs() < 1e-4);
+        assert!(float_eq(medium.scattering.b, 0.25));
+        assert!(float_eq(medium.asymmetry, 0.));
+        assert_eq!(medium.density, None);
+    }
+
+    #[test]
+    fn transmittance() {
+        let medium = Medium::new(Color::new(1., 0., 0.), Color::new(0., 0., 0.5), 0.);
+        assert_eq!(medium.transmittance(0.), Color::white());
+        assert_eq!(medium.transmittance(2.), Color::new((-2f64).exp(), 1., (-1f64).exp()));
+        assert_eq!(medium.transmittance(f64::INFINITY), Color::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn phase_normalized() {
+        let wo = Vector::new(0., 0., 1.);
+        for g in [-0.7, 0., 0.3, 0.8] {
+            let medium = Medium::new(Color::black(), Color::white(), g);
+            let steps = 20_000;
+            let mut sum = 0.;
+            for i in 0..steps {
+                // integrate over the cosine, the phase is symmetric around wo
+                let cosine = -1. + 2. * (i as f64 + 0.5) / steps as f64;
+                let sine = (1. - cosine * cosine).sqrt();
+                sum += medium.phase(wo, Vector::new(sine, 0., cosine)) * 2. / steps as f64;
+            }
+            assert!((sum * 2. * PI - 1.).abs() < 1e-3);
+        }
+        let isotropic = Medium::new(Color::black(), Color::white(), 0.);
+        assert!(float_eq(isotropic.phase(wo, wo), 1. / (4. * PI)));
+    }
+
+    #[test]
+    fn sample_phase() {
+        // the mean cosine of the scattering angle is the asymmetry
+        let wo = Vector::new(1., 2., -1.).normalized();
+        let mut rng = Rng::from_values(&[1.]);
+        for g in [-0.5, 0., 0.8] {
+            let medium = Medium::new(Color::black(), Color::white(), g);
+            let count = 20_000;
+            let mut sum = 0.;
+            for _ in 0..count {
+                let wi = medium.sample_phase(wo, rng.next_f64(), rng.next_f64());
+                assert!(float_eq(wi.len(), 1.));
+                sum += -wo.dot(wi);
+            }
+            assert!((sum / count as f64 - g).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn sample_distance() {
+        // the weights of the samples average to the transmittance and the
+        // scattered fraction of the light
+        let medium = Medium::new(Color::new(0.5, 0.2, 0.), Color::new(0.5, 0.2, 0.1), 0.);
+        let mut rng = Rng::from_values(&[2.]);
+        let count = 100_000;
+        let mut transmitted = Color::black();
+        let mut scattered = Color::black();
+        for _ in 0..count {
+            match medium.sample_distance(2., rng.next_f64(), rng.next_f64()) {
+                (Some(distance), weight) => {
+                    assert!(distance < 2.);
+                    scattered += weight * (1. / medium.scattering.r.max(1e-9));
+                }
+                (None, weight) => transmitted += weight,
+            }
+        }
+        let transmitted = transmitted * (1. / count as f64);
+        let expected = medium.transmittance(2.);
+        assert!((transmitted.r - expected.r).abs() < 0.01);
+        assert!((transmitted.g - expected.g).abs() < 0.01);
+        assert!((transmitted.b - expected.b).abs() < 0.01);
+        // red: ∫ σs e^(-σt t) dt / σs = (1 - e^(-2)) / σt
+        let scattered = scattered * (1. / count as f64);
+        assert!((scattered.r - (1. - (-2f64).exp())).abs() < 0.02);
+    }
+
+    fn heterogeneous(absorption: Color, scattering: Color) -> Medium {
+        // density rising linearly along x over the unit cube
+        Medium {
+            density: Some(Density::grid(2, 2, 2, |u, _, _| u)),
+            ..Medium::new(absorption, scattering, 0.)
+        }
+    }
+
+    #[test]
+    fn ratio_tracking() {
+        // optical depth ∫ σ t dt over [0,1] is σ / 2
+        let medium = heterogeneous(Color::new(1., 0.5, 0.), Color::black());
+        let ray = Ray::new(Point::new(-1., 0.5, 0.5), Vector::new(1., 0., 0.));
+        let mut rng = Rng::from_values(&[3.]);
+        let count = 20_000;
+        let mut sum = Color::black();
+        for _ in 0..count {
+            sum += medium.transmittance_along(&ray, 2., &mut rng);
+        }
+        let sum = sum * (1. / count as f64);
+        assert!((sum.r - (-0.5f64).exp()).abs() < 0.01);
+        assert!((sum.g - (-0.25f64).exp()).abs() < 0.01);
+        assert!(float_eq(sum.b, 1.));
+        // the ray misses the density
+        let miss = Ray::new(Point::new(-1., 2., 0.5), Vector::new(1., 0., 0.));
+        assert_eq!(medium.transmittance_along(&miss, 10., &mut rng), Color::white());
+    }
+
+    #[test]
+    fn delta_tracking() {
+        let ray = Ray::new(Point::new(0., 0.5, 0.5), Vector::new(1., 0., 0.));
+        let mut rng = Rng::from_values(&[4.]);
+        let count = 20_000;
+        // the escaping weights average to the transmittance
+        let medium = heterogeneous(Color::new(1., 0.5, 0.), Color::new(0.5, 0.5, 0.5));
+        let mut transmitted = Color::black();
+        for _ in 0..count {
+            if let (None, weight) = medium.sample(&ray, 5., &mut rng) {
+                transmitted += weight;
+            }
+        }
+        let transmitted = transmitted * (1. / count as f64);
+        let expected = Color::new((-0.75f64).exp(), (-0.5f64).exp(), (-0.25f64).exp());
+        assert!((transmitted.r - expected.r).abs() < 0.02);
+        assert!((transmitted.g - expected.g).abs() < 0.02);
+        assert!((transmitted.b - expected.b).abs() < 0.02);
+        // without absorption the light is scattered with probability 1 - T
+        let medium = heterogeneous(Color::black(), Color::white());
+        let mut scattered = 0;
+        for _ in 0..count {
+            if let (Some(distance), weight) = medium.sample(&ray, 5., &mut rng) {
+                assert!(distance <= 1.);
+                assert_eq!(weight, Color::white());
+                scattered += 1;
+            }
+        }
+        let expected = 1. - (-0.5f64).exp();
+        assert!((scattered as f64 / count as f64 - expected).abs() < 0.01);
+    }
+}
@@ -0,0 +1,313 @@
+use std::f64::consts::PI;
+
+use crate::{
+    util::{cosine_hemisphere, float_eq, orthonormal_basis},
+    Color, Vector,
+};
+
+/// Physically based reflectance of a [`Material`](crate::Material) for the
+/// metal/roughness workflow.
+///
+/// Specular reflection follows the Cook-Torrance model with the GGX (Trowbridge-Reitz)
+/// distribution of microfacets, the Smith height-correlated masking-shadowing
+/// and a Fresnel term blending a dielectric of specified index of refraction
+/// with a conductor whose reflectance at normal incidence is the base color.
+/// Dielectrics add a Lambertian lobe of the base color for the light that is
+/// not reflected.
+#[derive(Copy, Clone, Debug)]
+pub struct Microfacet {
+    /// perceptual roughness in `[0,1]` (the GGX parameter is its square)
+    pub roughness: f64,
+    /// `0` for dielectrics, `1` for conductors
+    pub metalness: f64,
+    /// index of refraction of the dielectric
+    pub ior: f64,
+}
+
+impl Microfacet {
+    const IOR: f64 = 1.5;
+    const MIN_ALPHA: f64 = 1e-3;
+
+    /// Creates a `Microfacet` of specified roughness and metalness (clamped
+    /// in `[0,1]`) with the index of refraction of glass (`1.5`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Microfacet;
+    /// let microfacet = Microfacet::new(0.5, 2.);
+    /// assert_eq!(microfacet.roughness, 0.5);
+    /// assert_eq!(microfacet.metalness, 1.);
+    /// assert_eq!(microfacet.ior, 1.5);
+    /// ```
+    pub fn new(roughness: f64, metalness: f64) -> Self {
+        Self {
+            roughness: roughness.clamp(0., 1.),
+            metalness: metalness.clamp(0., 1.),
+            ior: Self::IOR,
+        }
+    }
+
+    /// Returns the fraction of the light reflected by a microfacet for
+    /// specified cosine between the light and the microfacet normal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Microfacet};
+    /// let gold = Color::new(1., 0.78, 0.34);
+    /// assert_eq!(Microfacet::new(0.5, 1.).fresnel(gold, 1.), gold);
+    /// ```
+    pub fn fresnel(&self, base_color: Color, cosine: f64) -> Color {
+        let dielectric = self.dielectric_fresnel(cosine);
+        let weight = (1. - cosine.clamp(0., 1.)).powi(5);
+        let conductor = base_color + (Color::white() - base_color) * weight;
+        Color::white() * (dielectric * (1. - self.metalness)) + conductor * self.metalness
+    }
+
+    /// Returns the fraction of the light arriving from `wi` scattered towards
+    /// `wo` (per steradian).
+    pub(crate) fn eval(&self, base_color: Color, normal: Vector, wo: Vector, wi: Vector) -> Color {
+        let (cos_o, cos_i) = (wo.dot(normal), wi.dot(normal));
+        if cos_i <= 0. || cos_o <= 0. {
+            return Color::black();
+        }
+        let half = (wo + wi).normalized();
+        let cos_h = wo.dot(half);
+        let distribution = self.distribution(half.dot(normal));
+        let masking = 1. / (1. + self.lambda(cos_o) + self.lambda(cos_i));
+        let specular = self.fresnel(base_color, cos_h) * (distribution * masking / (4. * cos_o * cos_i));
+        let diffuse = (1. - self.metalness) * (1. - self.dielectric_fresnel(cos_h)) / PI;
+        specular + base_color * diffuse
+    }
+
+    /// Returns the density (per steradian) of sampling `wi` from `wo`.
+    pub(crate) fn pdf(&self, base_color: Color, normal: Vector, wo: Vector, wi: Vector) -> f64 {
+        let (cos_o, cos_i) = (wo.dot(normal), wi.dot(normal));
+        if cos_i <= 0. || cos_o <= 0. {
+            return 0.;
+        }
+        let half = (wo + wi).normalized();
+        let cos_nh = half.dot(normal);
+        // change of variables from the half vector to the reflected direction
+        let specular = self.distribution(cos_nh) * cos_nh / (4. * wo.dot(half));
+        let probability = self.specular_probability(base_color, cos_o);
+        probability * specular + (1. - probability) * cos_i / PI
+    }
+
+    /// Returns a direction `wi` sampled from `wo` from three uniform values in
+    /// `[0,1)`, `None` if the sampled direction lies below the surface.
+    pub(crate) fn sample(
+        &self,
+        base_color: Color,
+        normal: Vector,
+        wo: Vector,
+        u1: f64,
+        u2: f64,
+        u3: f64,
+    ) -> Option<Vector> {
+        let wi = if u1 < self.specular_probability(base_color, wo.dot(normal)) {
+            // half vector distributed as D(h) cos θh
+            let alpha = self.alpha();
+            let tan2 = alpha * alpha * u2 / (1. - u2).max(f64::EPSILON);
+            let cosine = 1. / (1. + tan2).sqrt();
+            let sine = (1. - cosine * cosine).max(0.).sqrt();
+            let phi = 2. * PI * u3;
+            let (tangent, bitangent) = orthonormal_basis(normal);
+            let half = tangent * (sine * phi.cos()) + bitangent * (sine * phi.sin()) + normal * cosine;
+            (-wo).reflect(half)
+        } else {
+            cosine_hemisphere(normal, u2, u3)
+        };
+        if wi.dot(normal) <= 0. {
+            return None;
+        }
+        Some(wi)
+    }
+
+    /// Returns the GGX parameter.
+    fn alpha(&self) -> f64 {
+        (self.roughness * self.roughness).max(Self::MIN_ALPHA)
+    }
+
+    /// Returns the density of microfacet normals at specified cosine with the
+    /// surface normal.
+    fn distribution(&self, cosine: f64) -> f64 {
+        if cosine <= 0. {
+            return 0.;
+        }
+        let alpha2 = self.alpha() * self.alpha();
+        let denominator = cosine * cosine * (alpha2 - 1.) + 1.;
+        alpha2 / (PI * denominator * denominator)
+    }
+
+    /// Returns the Smith auxiliary function of a direction at specified
+    /// cosine with the surface normal.
+    fn lambda(&self, cosine: f64) -> f64 {
+        let cos2 = cosine * cosine;
+        let tan2 = (1. - cos2).max(0.) / cos2;
+        ((1. + self.alpha() * self.alpha() * tan2).sqrt() - 1.) / 2.
+    }
+
+    /// Returns the Fresnel reflectance of the dielectric (unpolarized light
+    /// arriving from the outside).
+    fn dielectric_fresnel(&self, cosine: f64) -> f64 {
+        let cos_i = cosine.clamp(0., 1.);
+        let sin2_t = (1. - cos_i * cos_i) / (self.ior * self.ior);
+        if sin2_t >= 1. {
+            return 1.;
+        }
+        let cos_t = (1. - sin2_t).sqrt();
+        let s = (cos_i - self.ior * cos_t) / (cos_i + self.ior * cos_t);
+        let p = (self.ior * cos_i - cos_t) / (self.ior * cos_i + cos_t);
+        (s * s + p * p) / 2.
+    }
+
+    /// Returns the probability of sampling the specular lobe.
+    fn specular_probability(&self, base_color: Color, cosine: f64) -> f64 {
+        let specular = self.fresnel(base_color, cosine).luminance().max(0.);
+        let diffuse = (1. - self.metalness) * (1. - self.dielectric_fresnel(cosine)) * base_color.luminance().max(0.);
+        if specular + diffuse <= 0. {
+            return 1.;
+        }
+        specular / (specular + diffuse)
+    }
+}
+
+impl PartialEq for Microfacet {
+    fn eq(&self, other: &Self) -> bool {
+        float_eq(self.roughness, other.roughness)
+            && float_eq(self.metalness, other.metalness)
+            && float_eq(self.ior, other.ior)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::Rng;
+
+    use super::*;
+
+    /// Returns `∫ f cos dω` estimated by sampling the BSDF.
+    fn albedo(microfacet: &Microfacet, base_color: Color, wo: Vector) -> Color {
+        let normal = Vector::new(0., 1., 0.);
+        let mut rng = Rng::from_values(&[wo.x, wo.y]);
+        let count = 50_000;
+        let mut sum = Color::black();
+        for _ in 0..count {
+            let (u1, u2, u3) = (rng.next_f64(), rng.next_f64(), rng.next_f64());
+            if let Some(wi) = microfacet.sample(base_color, normal, wo, u1, u2, u3) {
+                let pdf = microfacet.pdf(base_color, normal, wo, wi);
+                sum += microfacet.eval(base_color, normal, wo, wi) * (wi.dot(normal) / pdf);
+            }
+        }
+        sum * (1. / count as f64)
+    }
+
+    #[test]
+    fn new() {
+        let microfacet = Microfacet::new(-1., 0.3);
+        assert!(float_eq(microfacet.roughness, 0.));
+        assert!(float_eq(microfacet.metalness, 0.3));
+        assert!(float_eq(microfacet.ior, 1.5));
+        assert_eq!(microfacet, Microfacet::new(0., 0.3));
+        assert_ne!(microfacet, Microfacet::new(0.1, 0.3));
+    }
+
+    #[test]
+    fn fresnel() {
+        let dielectric = Microfacet::new(0.5, 0.);
+        let base_color = Color::new(1., 0.5, 0.);
+        assert_eq!(dielectric.fresnel(base_color, 1.), Color::new(0.04, 0.04, 0.04));
+        assert_eq!(dielectric.fresnel(base_color, 0.), Color::white());
+        let conductor = Microfacet::new(0.5, 1.);
+        assert_eq!(conductor.fresnel(base_color, 1.), base_color);
+        assert_eq!(conductor.fresnel(base_color, 0.), Color::white());
+    }
+
+    #[test]
+    fn distribution_normalized() {
+        // ∫ D(h) cos θh dω = 1
+        for roughness in [0.2, 0.5, 1.] {
+            let microfacet = Microfacet::new(roughness, 0.);
+            let steps = 2000;
+            let dtheta = PI / 2. / steps as f64;
+            let integral: f64 = (0..steps)
+                .map(|i| {
+                    let theta = (i as f64 + 0.5) * dtheta;
+                    microfacet.distribution(theta.cos()) * theta.cos() * theta.sin() * 2. * PI * dtheta
+                })
+                .sum();
+            assert!((integral - 1.).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn reciprocity() {
+        let microfacet = Microfacet::new(0.4, 0.5);
+        let base_color = Color::new(0.8, 0.6, 0.2);
+        let normal = Vector::new(0., 1., 0.);
+        let a = Vector::new(1., 2., 0.5).normalized();
+        let b = Vector::new(-0.5, 1., 0.2).normalized();
+        assert_eq!(
+            microfacet.eval(base_color, normal, a, b),
+            microfacet.eval(base_color, normal, b, a)
+        );
+        assert_eq!(microfacet.eval(base_color, normal, a, -b), Color::black());
+        assert!(float_eq(microfacet.pdf(base_color, normal, a, -b), 0.));
+    }
+
+    #[test]
+    fn energy_conservation() {
+        let wo = Vector::new(1., 1., 0.).normalized();
+        for roughness in [0.1, 0.5, 1.] {
+            for metalness in [0., 1.] {
+                let albedo = albedo(&Microfacet::new(roughness, metalness), Color::white(), wo);
+                assert!(albedo.r <= 1.01);
+            }
+        }
+        // single scattering loses the energy of rough conductors
+        let smooth = albedo(&Microfacet::new(0.1, 1.), Color::white(), wo);
+        let rough = albedo(&Microfacet::new(1., 1.), Color::white(), wo);
+        assert!(smooth.r > 0.99);
+        assert!(rough.r < smooth.r);
+    }
+
+    #[test]
+    fn sample_pdf() {
+        // sampling the BSDF and uniformly sampling the hemisphere agree
+        let microfacet = Microfacet::new(0.6, 0.3);
+        let base_color = Color::new(0.9, 0.5, 0.3);
+        let normal = Vector::new(0., 1., 0.);
+        let wo = Vector::new(0.5, 1., 0.).normalized();
+        let mut rng = Rng::from_values(&[3.]);
+        let count = 200_000;
+        let mut uniform = Color::black();
+        for _ in 0..count {
+            let z = rng.next_f64();
+            let r = (1. - z * z).sqrt();
+            let phi = 2. * PI * rng.next_f64();
+            let wi = Vector::new(r * phi.cos(), z, r * phi.sin());
+            uniform += microfacet.eval(base_color, normal, wo, wi) * (z * 2. * PI);
+        }
+        uniform *= 1. / count as f64;
+        let sampled = albedo(&microfacet, base_color, wo);
+        assert!((uniform.r - sampled.r).abs() < 0.02);
+        assert!((uniform.b - sampled.b).abs() < 0.02);
+    }
+
+    #[test]
+    fn sample_above_surface() {
+        let microfacet = Microfacet::new(0.3, 0.);
+        let normal = Vector::new(0., 1., 0.);
+        let wo = Vector::new(1., 0.1, 0.).normalized();
+        let mut rng = Rng::from_values(&[4.]);
+        for _ in 0..1000 {
+            let (u1, u2, u3) = (rng.next_f64(), rng.next_f64(), rng.next_f64());
+            if let Some(wi) = microfacet.sample(Color::white(), normal, wo, u1, u2, u3) {
+                assert!(wi.dot(normal) > 0.);
+                assert!(microfacet.pdf(Color::white(), normal, wo, wi) > 0.);
+            }
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{Matrix, Vector};
+
+/// Transform of a shape moving from a start transform at time `0` (opening
+/// of the shutter) to an end transform at time `1` (closing of the shutter).
+///
+/// Both transforms are decomposed into a translation, a rotation and a
+/// stretch (scaling and shearing) interpolated separately: translations and
+/// stretches linearly, rotations along the shortest arc, so that spinning
+/// shapes keep their size. Both transforms must have the same orientation
+/// (either both or neither mirroring), so that every interpolated transform
+/// is invertible.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Motion {
+    start: Matrix,
+    end: Matrix,
+    translations: [Vector; 2],
+    rotations: [Quaternion; 2],
+    stretches: [Matrix; 2],
+}
+
+/// Unit quaternion representing a rotation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Quaternion {
+    w: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+impl Motion {
+    const ITERATIONS: usize = 100;
+
+    /// Creates a `Motion` from the start and end transforms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transform is not invertible, or if only one of them
+    /// mirrors space.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Matrix, Motion};
+    /// use std::f64::consts::PI;
+    /// let start = Matrix::translation(0., 0., 0.);
+    /// let end = Matrix::translation(4., 0., 0.) * Matrix::rotation_y(PI / 2.);
+    /// let motion = Motion::new(start, end);
+    /// assert_eq!(motion.transform_at(0.), start);
+    /// assert_eq!(motion.transform_at(1.), end);
+    /// assert_eq!(
+    ///     motion.transform_at(0.5),
+    ///     Matrix::translation(2., 0., 0.) * Matrix::rotation_y(PI / 4.)
+    /// );
+    /// ```
+    pub fn new(start: Matrix, end: Matrix) -> Self {
+        let (t0, r0, s0) = decompose(start);
+        let (t1, r1, s1) = decompose(end);
+        // stretches of the same orientation are both positive or both
+        // negative definite, and so are their interpolations
+        assert!(
+            s0.determinant().signum() == s1.determinant().signum(),
+            "motion transforms must have the same orientation"
+        );
+        Self {
+            start,
+            end,
+            translations: [t0, t1],
+            rotations: [r0, r1],
+            stretches: [s0, s1],
+        }
+    }
+
+    /// Get the transform at the opening of the shutter.
+    #[must_use]
+    pub fn start(&self) -> &Matrix {
+        &self.start
+    }
+
+    /// Get the transform at the closing of the shutter.
+    #[must_use]
+    pub fn end(&self) -> &Matrix {
+        &self.end
+    }
+
+    /// Returns the transform at specified time, clamped to `[0,1]`.
+    pub fn transform_at(&self, time: f64) -> Matrix {
+        if time <= 0. {
+            return self.start;
+        }
+        if time >= 1. {
+            return self.end;
+        }
+        let [t0, t1] = self.translations;
+        let translation = t0 + (t1 - t0) * time;
+        let rotation = self.rotations[0].slerp(self.rotations[1], time).matrix();
+        let mut stretch = Matrix::identity();
+        for row in 0..3 {
+            for col in 0..3 {
+                let (a, b) = (self.stretches[0].get(row, col), self.stretches[1].get(row, col));
+                *stretch.get_mut(row, col) = a + (b - a) * time;
+            }
+        }
+        Matrix::translation(translation.x, translation.y, translation.z) * rotation * stretch
+    }
+}
+
+/// Returns the translation, rotation and stretch of a transform, its product
+/// in this order.
+fn decompose(transform: Matrix) -> (Vector, Quaternion, Matrix) {
+    let translation = Vector::new(*transform.get(0, 3), *transform.get(1, 3), *transform.get(2, 3));
+    let mut linear = transform;
+    for i in 0..3 {
+        *linear.get_mut(i, 3) = 0.;
+        *linear.get_mut(3, i) = 0.;
+    }
+    *linear.get_mut(3, 3) = 1.;
+    // polar decomposition: averaging the matrix with its inverse transpose
+    // converges to the closest rotation
+    let mut rotation = linear;
+    for _ in 0..Motion::ITERATIONS {
+        let inverse = rotation
+            .inverse()
+            .expect("shape transform must be invertible")
+            .transpose();
+        let mut next = Matrix::identity();
+        let mut change: f64 = 0.;
+        for row in 0..3 {
+            for col in 0..3 {
+                let value = (rotation.get(row, col) + inverse.get(row, col)) / 2.;
+                change = change.max((value - rotation.get(row, col)).abs());
+                *next.get_mut(row, col) = value;
+            }
+        }
+        rotation = next;
+        if change < 1e-12 {
+            break;
+        }
+    }
+    // mirroring is left to the stretch
+    if rotation.determinant() < 0. {
+        for row in 0..3 {
+            for col in 0..3 {
+                *rotation.get_mut(row, col) *= -1.;
+            }
+        }
+    }
+    let stretch = rotation.transpose() * linear;
+    (translation, Quaternion::from_matrix(rotation), stretch)
+}
+
+impl Quaternion {
+    fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        let norm = (w * w + x * x + y * y + z * z).sqrt();
+        Self {
+            w: w / norm,
+            x: x / norm,
+            y: y / norm,
+            z: z / norm,
+        }
+    }
+
+    /// Returns the quaternion of a rotation matrix (Shoemake).
+    fn from_matrix(m: Matrix) -> Self {
+        let m = |row: usize, col: usize| *m.get(row, col);
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+        if trace > 0. {
+            let s = (trace + 1.).sqrt() * 2.;
+            Self::new(
+                s / 4.,
+                (m(2, 1) - m(1, 2)) / s,
+                (m(0, 2) - m(2, 0)) / s,
+                (m(1, 0) - m(0, 1)) / s,
+            )
+        } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+            let s = (1. + m(0, 0) - m(1, 1) - m(2, 2)).sqrt() * 2.;
+            Self::new(
+                (m(2, 1) - m(1, 2)) / s,
+                s / 4.,
+                (m(0, 1) + m(1, 0)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+            )
+        } else if m(1, 1) > m(2, 2) {
+            let s = (1. + m(1, 1) - m(0, 0) - m(2, 2)).sqrt() * 2.;
+            Self::new(
+                (m(0, 2) - m(2, 0)) / s,
+                (m(0, 1) + m(1, 0)) / s,
+                s / 4.,
+                (m(1, 2) + m(2, 1)) / s,
+            )
+        } else {
+            let s = (1. + m(2, 2) - m(0, 0) - m(1, 1)).sqrt() * 2.;
+            Self::new(
+                (m(1, 0) - m(0, 1)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+                (m(1, 2) + m(2, 1)) / s,
+                s / 4.,
+            )
+        }
+    }
+
+    /// Returns the rotation matrix of the quaternion.
+    fn matrix(self) -> Matrix {
+        let Self { w, x, y, z } = self;
+        #[rustfmt::skip]
+        let elements = [
+            1. - 2. * (y * y + z * z), 2. * (x * y - w * z), 2. * (x * z + w * y), 0.,
+            2. * (x * y + w * z), 1. - 2. * (x * x + z * z), 2. * (y * z - w * x), 0.,
+            2. * (x * z - w * y), 2. * (y * z + w * x), 1. - 2. * (x * x + y * y), 0.,
+            0., 0., 0., 1.,
+        ];
+        Matrix::new(elements)
+    }
+
+    /// Returns the spherical interpolation along the shortest arc between
+    /// two rotations.
+    fn slerp(self, other: Self, t: f64) -> Self {
+        let mut dot = self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z;
+        // q and -q are the same rotation
+        let other = if dot < 0. {
+            dot = -dot;
+            Self {
+                w: -other.w,
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+            }
+        } else {
+            other
+        };
+        let (a, b) = if dot > 0.9995 {
+            (1. - t, t)
+        } else {
+            let theta = dot.acos();
+            (((1. - t) * theta).sin() / theta.sin(), (t * theta).sin() / theta.sin())
+        };
+        Self::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::Point;
+
+    use super::*;
+
+    #[test]
+    fn decompose() {
+        let transform = Matrix::translation(1., -2., 3.)
+            * Matrix::rotation_z(0.7)
+            * Matrix::rotation_x(-1.2)
+            * Matrix::scaling(2., 0.5, 3.)
+            * Matrix::shearing(0.2, 0., 0.1, 0., 0., 0.3);
+        let (translation, rotation, stretch) = super::decompose(transform);
+        assert_eq!(translation, Vector::new(1., -2., 3.));
+        let rotation = rotation.matrix();
+        assert_eq!(rotation * rotation.transpose(), Matrix::identity());
+        assert!((rotation.determinant() - 1.).abs() < 1e-9);
+        assert_eq!(
+            Matrix::translation(translation.x, translation.y, translation.z) * rotation * stretch,
+            transform
+        );
+        // stretches are symmetric
+        assert_eq!(stretch, stretch.transpose());
+    }
+
+    #[test]
+    fn mirrored() {
+        let transform = Matrix::rotation_y(0.4) * Matrix::scaling(-1., 1., 1.);
+        let motion = Motion::new(transform, transform);
+        assert_eq!(motion.transform_at(0.3), transform);
+        let motion = Motion::new(transform, Matrix::rotation_y(-0.4) * Matrix::scaling(-3., 2., 1.));
+        for i in 0..=10 {
+            assert!(motion.transform_at(i as f64 / 10.).determinant() < 0.);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "motion transforms must have the same orientation")]
+    fn orientation() {
+        Motion::new(Matrix::identity(), Matrix::scaling(-1., 1., 1.));
+    }
+
+    #[test]
+    fn interpolate() {
+        let start = Matrix::translation(1., 0., 0.) * Matrix::scaling(1., 1., 1.);
+        let end = Matrix::translation(1., 4., 0.) * Matrix::rotation_z(PI) * Matrix::scaling(3., 3., 3.);
+        let motion = Motion::new(start, end);
+        assert_eq!(motion.start(), &start);
+        assert_eq!(motion.end(), &end);
+        assert_eq!(motion.transform_at(-1.), start);
+        assert_eq!(motion.transform_at(2.), end);
+        let half = motion.transform_at(0.5);
+        let expected = Matrix::translation(1., 2., 0.) * Matrix::rotation_z(PI / 2.) * Matrix::scaling(2., 2., 2.);
+        assert_eq!(half, expected);
+        // rotation keeps the distance to the center while the scale grows
+        for i in 0..=10 {
+            let time = i as f64 / 10.;
+            let center = motion.transform_at(time) * Point::new(0., 0., 0.);
+            let edge = motion.transform_at(time) * Point::new(1., 0., 0.);
+            assert!(((edge - center).len() - (1. + 2. * time)).abs() < 1e-9);
+        }
+        // shortest arc
+        let motion = Motion::new(Matrix::rotation_y(0.1), Matrix::rotation_y(2. * PI - 0.1));
+        assert_eq!(motion.transform_at(0.5), Matrix::identity());
+    }
+}
@@ -0,0 +1,933 @@
+use crate::{
+    util::Rng, Bsdf, Camera, Canvas, Color, Computations, Heuristic, Intersection, Light, Medium, PhotonMap, Point,
+    Ray, Vector, World,
+};
+
+/// Monte Carlo path tracing integrator computing global illumination.
+///
+/// Surfaces scatter light according to the [`Bsdf`] of their material. At
+/// each bounce the lights of the world are sampled (next event estimation)
+/// and the path is extended in a direction sampled from the BSDF. Lights
+/// reached by these directions (area lights and the environment) combine both
+/// strategies with multiple importance sampling. Paths longer than the
+/// roulette depth are terminated by Russian roulette.
+///
+/// Light emitted by emissive shapes is only gathered by the paths hitting
+/// them.
+///
+/// Caustics can be rendered by a photon mapping pass instead: photons traced
+/// from the lights with an [`Emitter`](crate::Emitter) are stored in a
+/// [`PhotonMap`] where they land on diffuse surfaces after specular bounces,
+/// the light they carry is then estimated at the diffuse surfaces hit by the
+/// paths and no longer gathered through specular surfaces.
+///
+/// Paths travel through the [`Medium`] of the world and of the shapes filled
+/// with one: the distance to the next scattering event is sampled from the
+/// extinction of the medium, the light is then scattered according to its
+/// phase function and the lights are sampled through the media.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PathTracer {
+    samples: usize,
+    max_depth: usize,
+    roulette_depth: usize,
+    heuristic: Heuristic,
+    seed: u64,
+    caustic_photons: usize,
+    caustic_radius: f64,
+}
+
+/// Scattering event of a path used to weight the lights reached by the next
+/// segment.
+pub(crate) struct Vertex {
+    pub(crate) point: Point,
+    /// density of the sampled direction
+    pub(crate) pdf: f64,
+    /// number of samples of each light
+    pub(crate) counts: Vec<usize>,
+}
+
+/// Scattering of the light at a vertex of a path.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Scattering<'a> {
+    /// reflection off a surface, `normal` on the side of `wo`
+    Surface { bsdf: Bsdf, normal: Vector, wo: Vector },
+    /// scattering inside a participating medium
+    Medium { medium: &'a Medium, wo: Vector },
+}
+
+/// Event ending a segment of a path.
+enum Event<'a> {
+    /// the ray leaves the world
+    Escaped,
+    /// the light is scattered at a point of a medium
+    Medium(Point, &'a Medium),
+    /// the ray hits a surface which is not the boundary of a medium
+    Surface(Computations<'a>),
+}
+
+impl PathTracer {
+    const SAMPLES: usize = 16;
+    const MAX_DEPTH: usize = 16;
+    const ROULETTE_DEPTH: usize = 3;
+    const CAUSTIC_RADIUS: f64 = 0.1;
+
+    /// Creates a `PathTracer` with default settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Heuristic, PathTracer};
+    /// let tracer = PathTracer::new();
+    /// assert_eq!(tracer.samples_per_pixel(), 16);
+    /// assert_eq!(tracer.max_depth(), 16);
+    /// assert_eq!(tracer.roulette_depth(), 3);
+    /// assert_eq!(tracer.heuristic(), Heuristic::Power);
+    /// assert_eq!(tracer.caustic_photons(), 0);
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            samples: Self::SAMPLES,
+            max_depth: Self::MAX_DEPTH,
+            roulette_depth: Self::ROULETTE_DEPTH,
+            heuristic: Heuristic::default(),
+            seed: 0,
+            caustic_photons: 0,
+            caustic_radius: Self::CAUSTIC_RADIUS,
+        }
+    }
+
+    /// Get the number of paths traced per pixel.
+    #[must_use]
+    pub fn samples_per_pixel(&self) -> usize {
+        self.samples
+    }
+
+    /// Sets the number of paths traced per pixel.
+    pub fn set_samples_per_pixel(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    /// Get the maximum number of bounces of a path.
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Sets the maximum number of bounces of a path.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Get the number of bounces after which paths are subject to Russian
+    /// roulette.
+    #[must_use]
+    pub fn roulette_depth(&self) -> usize {
+        self.roulette_depth
+    }
+
+    /// Sets the number of bounces after which paths are subject to Russian
+    /// roulette.
+    pub fn set_roulette_depth(&mut self, roulette_depth: usize) {
+        self.roulette_depth = roulette_depth;
+    }
+
+    /// Get the heuristic weighting light and BSDF samples.
+    #[must_use]
+    pub fn heuristic(&self) -> Heuristic {
+        self.heuristic
+    }
+
+    /// Sets the heuristic weighting light and BSDF samples.
+    pub fn set_heuristic(&mut self, heuristic: Heuristic) {
+        self.heuristic = heuristic;
+    }
+
+    /// Get the seed of the random sequences.
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets the seed of the random sequences, renders with the same seed are
+    /// identical.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Get the number of photons emitted to render caustics (`0` when
+    /// caustics are gathered by the paths).
+    #[must_use]
+    pub fn caustic_photons(&self) -> usize {
+        self.caustic_photons
+    }
+
+    /// Sets the number of photons emitted to render caustics, `0` disables
+    /// the photon mapping pass.
+    pub fn set_caustic_photons(&mut self, caustic_photons: usize) {
+        self.caustic_photons = caustic_photons;
+    }
+
+    /// Get the radius around a point within which photons contribute to the
+    /// caustics at the point.
+    #[must_use]
+    pub fn caustic_radius(&self) -> f64 {
+        self.caustic_radius
+    }
+
+    /// Sets the radius around a point within which photons contribute to the
+    /// caustics at the point, larger radii blur caustics but reduce noise.
+    pub fn set_caustic_radius(&mut self, caustic_radius: f64) {
+        self.caustic_radius = caustic_radius;
+    }
+
+    /// Renders the world by averaging the paths traced through random
+    /// positions within each pixel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Color, Material, PathTracer, Quadric, Shape, World};
+    /// let mut world = World::new();
+    /// let mut light = Quadric::sphere();
+    /// light.set_material(Material {
+    ///     emission: Color::white(),
+    ///     ..Material::default()
+    /// });
+    /// world.add_object(light);
+    /// let camera = Camera::new(4, 4, 1.);
+    /// let image = PathTracer::new().render(&world, &camera);
+    /// assert_eq!(image.width(), 4);
+    /// ```
+    pub fn render(&self, world: &World, camera: &Camera) -> Canvas {
+        let mut image = Canvas::new(camera.hsize(), camera.vsize());
+        let lights: Vec<&dyn Light> = world.light_sources().collect();
+        // the photons only cover the caustics of the lights with an emitter
+        let map = (self.caustic_photons > 0)
+            .then(|| PhotonMap::caustics(world, self.caustic_photons, self.max_depth, self.seed));
+        let uncovered: Vec<&dyn Light> = lights
+            .iter()
+            .copied()
+            .filter(|light| light.emitter().is_none())
+            .collect();
+        let caustics = map.as_ref().map(|map| (map, uncovered.as_slice()));
+        for y in 0..camera.vsize() {
+            for x in 0..camera.hsize() {
+                let mut rng = Rng::from_values(&[x as f64, y as f64, self.seed as f64]);
+                let mut color = Color::black();
+                for _ in 0..self.samples {
+                    let ray = camera.sample_ray(x, y, &mut rng);
+                    color += self.trace(world, &lights, caustics, &ray, &mut rng);
+                }
+                *image.get_mut(x, y) = color * (1. / self.samples as f64);
+            }
+        }
+        image
+    }
+
+    /// Returns the radiance carried along a ray estimated by a single path.
+    ///
+    /// With a caustic photon map, the lights whose caustics are not covered
+    /// by the photons are given along with the map.
+    fn trace(
+        &self,
+        world: &World,
+        lights: &[&dyn Light],
+        caustics: Option<(&PhotonMap, &[&dyn Light])>,
+        ray: &Ray,
+        rng: &mut Rng,
+    ) -> Color {
+        let mut color = Color::black();
+        let mut throughput = Color::white();
+        let mut ray = *ray;
+        let mut vertex: Option<Vertex> = None;
+        let mut vertex_lights = lights;
+        // the last vertex not on a specular surface is a diffuse surface
+        let mut diffuse = false;
+        let mut medium = world.medium();
+        for depth in 0..self.max_depth {
+            if let Some(vertex) = &vertex {
+                color += throughput * reached(self.heuristic, world, vertex_lights, vertex, &ray, medium, rng);
+            }
+            let (point, scattering) = match next_event(world, &mut ray, &mut medium, &mut throughput, rng) {
+                Event::Escaped => {
+                    if vertex.is_none() {
+                        color += throughput * world.background(ray.direction);
+                    }
+                    break;
+                }
+                Event::Medium(point, medium) => {
+                    // the photons only land on surfaces
+                    diffuse = false;
+                    vertex_lights = lights;
+                    let wo = -ray.direction.normalized();
+                    (point, Scattering::Medium { medium, wo })
+                }
+                Event::Surface(comps) => {
+                    let material = comps.object.material();
+                    color += throughput * material.emission;
+                    let bsdf = Bsdf::new(material);
+                    if bsdf.is_black() {
+                        break;
+                    }
+                    let (normal, wo) = (comps.normalv, comps.eyev);
+                    // light reaching a diffuse surface through specular
+                    // bounces is estimated by the photons
+                    vertex_lights = match caustics {
+                        Some((_, uncovered)) if diffuse && bsdf.is_specular() => uncovered,
+                        Some((map, _)) if !bsdf.is_specular() => {
+                            diffuse = true;
+                            color += throughput * map.estimate(comps.point, normal, wo, &bsdf, self.caustic_radius);
+                            lights
+                        }
+                        _ => lights,
+                    };
+                    (comps.over_point, Scattering::Surface { bsdf, normal, wo })
+                }
+            };
+            let (direct, counts) = direct(
+                self.heuristic,
+                world,
+                vertex_lights,
+                point,
+                ray.time,
+                &scattering,
+                medium,
+                rng,
+            );
+            color += throughput * direct;
+            if depth + 1 >= self.roulette_depth {
+                let survival = throughput.r.max(throughput.g).max(throughput.b).min(0.95);
+                if rng.next_f64() >= survival {
+                    break;
+                }
+                throughput *= 1. / survival;
+            }
+            let wi = match scattering.sample(rng) {
+                Some(wi) => wi,
+                None => break,
+            };
+            let pdf = scattering.pdf(wi);
+            if pdf <= 0. {
+                break;
+            }
+            throughput *= scattering.eval(wi) * (1. / pdf);
+            vertex = Some(Vertex { point, pdf, counts });
+            ray = Ray::new(point, wi).with_time(ray.time);
+        }
+        color
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scattering<'_> {
+    /// Returns the fraction of the light arriving from `wi` scattered
+    /// towards `wo`, times the cosine of `wi` with the normal of surfaces.
+    pub(crate) fn eval(&self, wi: Vector) -> Color {
+        match self {
+            Self::Surface { bsdf, normal, wo } => {
+                let cosine = wi.dot(*normal);
+                if cosine <= 0. {
+                    return Color::black();
+                }
+                bsdf.eval(*normal, *wo, wi) * cosine
+            }
+            Self::Medium { medium, wo } => Color::white() * medium.phase(*wo, wi),
+        }
+    }
+
+    /// Returns the density of the directions sampled by [`Self::sample`].
+    pub(crate) fn pdf(&self, wi: Vector) -> f64 {
+        match self {
+            Self::Surface { bsdf, normal, wo } => bsdf.pdf(*normal, *wo, wi),
+            Self::Medium { medium, wo } => medium.phase(*wo, wi),
+        }
+    }
+
+    /// Samples the direction the scattered light comes from.
+    pub(crate) fn sample(&self, rng: &mut Rng) -> Option<Vector> {
+        match self {
+            Self::Surface { bsdf, normal, wo } => {
+                bsdf.sample(*normal, *wo, rng.next_f64(), rng.next_f64(), rng.next_f64())
+            }
+            Self::Medium { medium, wo } => Some(medium.sample_phase(*wo, rng.next_f64(), rng.next_f64())),
+        }
+    }
+}
+
+/// Follows a ray through the boundaries of media up to the next event of the
+/// path.
+///
+/// The ray and the medium it travels through are moved past the boundaries,
+/// the throughput of the path is weighted by the distances sampled in media.
+fn next_event<'a>(
+    world: &'a World,
+    ray: &mut Ray,
+    medium: &mut Option<&'a Medium>,
+    throughput: &mut Color,
+    rng: &mut Rng,
+) -> Event<'a> {
+    loop {
+        let intersections = world.intersect(ray);
+        let hit = Intersection::hit(&intersections);
+        if let Some(medium) = *medium {
+            let max_distance = hit.map_or(f64::INFINITY, |hit| hit.t);
+            let (distance, weight) = medium.sample(ray, max_distance, rng);
+            *throughput *= weight;
+            if let Some(distance) = distance {
+                return Event::Medium(ray.position(distance), medium);
+            }
+        }
+        let hit = match hit {
+            Some(hit) => hit,
+            None => return Event::Escaped,
+        };
+        let comps = Computations::new(&hit, ray);
+        *medium = match &comps.object.material().medium {
+            Some(_) if comps.inside => world.medium(),
+            Some(inner) => Some(inner),
+            None => return Event::Surface(comps),
+        };
+        *ray = Ray::new(comps.under_point, ray.direction).with_time(ray.time);
+    }
+}
+
+/// Returns the light scattered at a point of a path at specified time
+/// estimated from the samples of specified lights seen through specified
+/// medium, and the number of samples of each light.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn direct(
+    heuristic: Heuristic,
+    world: &World,
+    lights: &[&dyn Light],
+    point: Point,
+    time: f64,
+    scattering: &Scattering,
+    medium: Option<&Medium>,
+    rng: &mut Rng,
+) -> (Color, Vec<usize>) {
+    let mut color = Color::black();
+    let mut counts = vec![];
+    for light in lights {
+        let samples = light.samples(point);
+        counts.push(samples.len());
+        if samples.is_empty() {
+            continue;
+        }
+        let mut sum = Color::black();
+        for sample in samples.iter() {
+            let f = scattering.eval(sample.direction);
+            if f == Color::black() {
+                continue;
+            }
+            let ray = Ray::new(point, sample.direction).with_time(time);
+            let transmittance = world.transmittance(&ray, sample.distance, medium, rng);
+            if transmittance == Color::black() {
+                continue;
+            }
+            let light_pdf = samples.len() as f64 * sample.pdf;
+            let weight = heuristic.weight(light_pdf, scattering.pdf(sample.direction));
+            sum += f * sample.intensity * transmittance * weight;
+        }
+        color += sum * (1. / samples.len() as f64);
+    }
+    (color, counts)
+}
+
+/// Returns the light of specified lights reached by a ray sampled at a
+/// vertex, seen through specified medium.
+pub(crate) fn reached(
+    heuristic: Heuristic,
+    world: &World,
+    lights: &[&dyn Light],
+    vertex: &Vertex,
+    ray: &Ray,
+    medium: Option<&Medium>,
+    rng: &mut Rng,
+) -> Color {
+    let mut color = Color::black();
+    for (light, &count) in lights.iter().zip(vertex.counts.iter()) {
+        let sample = match light.sample_along(vertex.point, ray.direction) {
+            Some(sample) => sample,
+            None => continue,
+        };
+        let shadow = Ray::new(vertex.point, ray.direction).with_time(ray.time);
+        let transmittance = world.transmittance(&shadow, sample.distance, medium, rng);
+        if transmittance == Color::black() {
+            continue;
+        }
+        // radiance of the light is the intensity times the light density
+        let weight = heuristic.weight(vertex.pdf, count as f64 * sample.pdf);
+        color += sample.intensity * transmittance * (sample.pdf * weight);
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{
+        AreaLight, Attenuation, Density, DirectionalLight, Environment, Material, Matrix, Motion, PointLight, Quadric,
+        Shape, Vector,
+    };
+
+    use super::*;
+
+    fn camera() -> Camera {
+        let mut camera = Camera::new(1, 1, 0.01);
+        let from = Point::new(0., 5., 0.);
+        let to = Point::new(0., 0., 0.);
+        let up = Vector::new(0., 0., 1.);
+        camera.set_transform(Matrix::view_transform(from, to, up));
+        camera
+    }
+
+    fn diffuse(color: Color, emission: Color) -> Material {
+        Material {
+            color,
+            diffuse: 1.,
+            specular: 0.,
+            emission,
+            ..Material::default()
+        }
+    }
+
+    #[test]
+    fn settings() {
+        let mut tracer = PathTracer::default();
+        tracer.set_samples_per_pixel(0);
+        assert_eq!(tracer.samples_per_pixel(), 1);
+        tracer.set_max_depth(4);
+        assert_eq!(tracer.max_depth(), 4);
+        tracer.set_roulette_depth(2);
+        assert_eq!(tracer.roulette_depth(), 2);
+        tracer.set_heuristic(Heuristic::Balance);
+        assert_eq!(tracer.heuristic(), Heuristic::Balance);
+        tracer.set_seed(7);
+        assert_eq!(tracer.seed(), 7);
+        tracer.set_caustic_photons(1000);
+        assert_eq!(tracer.caustic_photons(), 1000);
+        tracer.set_caustic_radius(0.5);
+        assert_eq!(tracer.caustic_radius(), 0.5);
+    }
+
+    #[test]
+    fn background() {
+        let mut world = World::new();
+        world.set_environment(Environment::new(Canvas::with_color(8, 4, Color::new(0.2, 0.4, 0.6))));
+        let image = PathTracer::new().render(&world, &camera());
+        assert_eq!(image.get(0, 0), &Color::new(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn emission() {
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(diffuse(Color::black(), Color::new(1., 2., 3.)));
+        world.add_object(sphere);
+        let image = PathTracer::new().render(&world, &camera());
+        assert_eq!(image.get(0, 0), &Color::new(1., 2., 3.));
+    }
+
+    #[test]
+    fn depth_of_field() {
+        // a small emitting sphere covering the center pixel
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::scaling(0.5, 0.5, 0.5));
+        sphere.set_material(diffuse(Color::black(), Color::white()));
+        world.add_object(sphere);
+        let mut camera = Camera::new(3, 3, 2. * 0.3f64.atan());
+        camera.set_transform(Matrix::translation(0., 0., -5.));
+        camera.set_aperture(0.5);
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(64);
+        // in focus the sphere stays sharp
+        camera.set_focal_distance(5.);
+        let sharp = tracer.render(&world, &camera);
+        // out of focus its light spreads over the neighboring pixels
+        camera.set_focal_distance(2.);
+        let blurred = tracer.render(&world, &camera);
+        assert!(sharp.get(1, 1).r > 0.7);
+        assert!(sharp.get(0, 1).r < 0.05);
+        assert!(blurred.get(1, 1).r < sharp.get(1, 1).r - 0.2);
+        assert!(blurred.get(0, 1).r > 0.1);
+    }
+
+    #[test]
+    fn motion_blur() {
+        // an emitting sphere crossing the center pixel for half the shutter
+        // interval
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        let start = Matrix::translation(-2., 0., 0.) * Matrix::scaling(0.5, 0.5, 0.5);
+        let end = Matrix::translation(2., 0., 0.) * Matrix::scaling(0.5, 0.5, 0.5);
+        sphere.set_motion(Motion::new(start, end));
+        sphere.set_material(diffuse(Color::black(), Color::white()));
+        world.add_object(sphere);
+        let mut camera = Camera::new(1, 1, 0.01);
+        camera.set_transform(Matrix::translation(0., 0., -5.));
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(256);
+        assert_eq!(tracer.render(&world, &camera).get(0, 0), &Color::black());
+        camera.set_shutter(0., 1.);
+        let blurred = tracer.render(&world, &camera).get(0, 0).r;
+        assert!((blurred - 0.25).abs() < 0.05);
+    }
+
+    #[test]
+    fn next_event_estimation() {
+        // Lambertian plane lit from above: L = albedo / π * I * cos θ
+        let mut world = World::new();
+        let mut plane = Quadric::plane();
+        plane.set_material(diffuse(Color::new(0.5, 0.5, 0.5), Color::black()));
+        world.add_object(plane);
+        world.add_light(PointLight::new(Point::new(0., 10., 0.), Color::white()));
+        let image = PathTracer::new().render(&world, &camera());
+        assert_eq!(image.get(0, 0), &(Color::white() * (0.5 / PI)));
+    }
+
+    #[test]
+    fn furnace() {
+        // inside an emitting sphere of albedo a: L = E / (1 - a)
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::scaling(10., 10., 10.));
+        sphere.set_material(diffuse(Color::new(0.5, 0.5, 0.5), Color::white()));
+        world.add_object(sphere);
+        let mut tracer = PathTracer::new();
+        tracer.set_max_depth(32);
+        tracer.set_roulette_depth(32);
+        tracer.set_samples_per_pixel(4);
+        let image = tracer.render(&world, &camera());
+        assert_eq!(image.get(0, 0), &Color::new(2., 2., 2.));
+        // Russian roulette is unbiased
+        tracer.set_roulette_depth(1);
+        tracer.set_samples_per_pixel(1024);
+        let image = tracer.render(&world, &camera());
+        assert!((image.get(0, 0).r - 2.).abs() < 0.05);
+    }
+
+    #[test]
+    fn color_bleeding() {
+        // red wall next to a white floor lit from above
+        let mut world = World::new();
+        let mut floor = Quadric::plane();
+        floor.set_material(diffuse(Color::white(), Color::black()));
+        world.add_object(floor);
+        let mut wall = Quadric::plane();
+        wall.set_transform(Matrix::translation(0.5, 0., 0.) * Matrix::rotation_z(PI / 2.));
+        wall.set_material(diffuse(Color::red(), Color::black()));
+        world.add_object(wall);
+        world.add_light(PointLight::new(Point::new(-1., 10., 0.), Color::white()));
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(64);
+        let image = tracer.render(&world, &camera());
+        let color = image.get(0, 0);
+        assert!(color.r > color.g);
+        assert!((color.g - color.b).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mis_environment() {
+        // Lambertian plane under a uniform sky: L = albedo * radiance
+        let mut world = World::new();
+        let mut plane = Quadric::plane();
+        plane.set_material(diffuse(Color::new(0.5, 0.5, 0.5), Color::black()));
+        world.add_object(plane);
+        let mut environment = Environment::new(Canvas::with_color(16, 8, Color::white()));
+        environment.set_sample_count(4);
+        world.set_environment(environment);
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(512);
+        for heuristic in [Heuristic::Balance, Heuristic::Power] {
+            tracer.set_heuristic(heuristic);
+            let image = tracer.render(&world, &camera());
+            assert!((image.get(0, 0).r - 0.5).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn mis_area_light() {
+        // Lambertian plane under a 2x2 area light at height 1
+        let mut world = World::new();
+        let mut plane = Quadric::plane();
+        plane.set_material(diffuse(Color::white(), Color::black()));
+        world.add_object(plane);
+        let corner = Point::new(-1., 1., -1.);
+        let (u, v) = (Vector::new(2., 0., 0.), Vector::new(0., 0., 2.));
+        let mut light = AreaLight::new(corner, u, 2, v, 2, Color::white());
+        light.set_attenuation(Attenuation::inverse_square());
+        world.add_light(light);
+        // L = 1 / π * 1 / A ∫ cos θ / d² dA
+        let steps = 400;
+        let mut expected = 0.;
+        for i in 0..steps {
+            for j in 0..steps {
+                let x = -1. + 2. * (i as f64 + 0.5) / steps as f64;
+                let z = -1. + 2. * (j as f64 + 0.5) / steps as f64;
+                let d2 = x * x + 1. + z * z;
+                expected += 1. / (d2 * d2.sqrt());
+            }
+        }
+        expected /= (steps * steps) as f64 * PI;
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(512);
+        for heuristic in [Heuristic::Balance, Heuristic::Power] {
+            tracer.set_heuristic(heuristic);
+            let image = tracer.render(&world, &camera());
+            assert!((image.get(0, 0).r - expected).abs() < 0.02 * expected);
+        }
+    }
+
+    #[test]
+    fn glossy() {
+        // mirror-like plane reflects the area light above the camera
+        let mut world = World::new();
+        let mut plane = Quadric::plane();
+        plane.set_material(Material {
+            diffuse: 0.,
+            specular: 1.,
+            shininess: 1000.,
+            ..Material::default()
+        });
+        world.add_object(plane);
+        let corner = Point::new(-1., 10., -1.);
+        let (u, v) = (Vector::new(2., 0., 0.), Vector::new(0., 0., 2.));
+        world.add_light(AreaLight::new(corner, u, 1, v, 1, Color::white()));
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(64);
+        let lit = tracer.render(&world, &camera());
+        let mut camera = camera();
+        let from = Point::new(0., 5., -5.);
+        camera.set_transform(Matrix::view_transform(
+            from,
+            Point::new(0., 0., 0.),
+            Vector::new(0., 1., 0.),
+        ));
+        let dark = tracer.render(&world, &camera);
+        assert!(lit.get(0, 0).r > 1.);
+        assert!(dark.get(0, 0).r < 1e-3);
+    }
+
+    #[test]
+    fn microfacet() {
+        // smooth white conductor under a uniform sky reflects the sky
+        let mut world = World::new();
+        let mut plane = Quadric::plane();
+        plane.set_material(Material::pbr(Color::white(), 0.1, 1.));
+        world.add_object(plane);
+        world.set_environment(Environment::new(Canvas::with_color(16, 8, Color::white())));
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(64);
+        let image = tracer.render(&world, &camera());
+        assert!((image.get(0, 0).r - 1.).abs() < 0.02);
+    }
+
+    #[test]
+    fn caustics() {
+        // white floor lit by a light at (0, 2, 0) and its mirror image at
+        // (2, 2, 0): L = 1 / π * I * (1 / 4 + cos θ / 8)
+        let mut world = World::new();
+        let mut floor = Quadric::plane();
+        floor.set_material(diffuse(Color::white(), Color::black()));
+        world.add_object(floor);
+        let mut mirror = Quadric::plane();
+        mirror.set_transform(Matrix::translation(1., 0., 0.) * Matrix::rotation_z(PI / 2.));
+        mirror.set_material(Material::pbr(Color::white(), 0.05, 1.));
+        world.add_object(mirror);
+        world.add_light(PointLight::from_power(Point::new(0., 2., 0.), Color::white(), 4. * PI));
+        let expected = (0.25 + 2f64.sqrt() / 16.) / PI;
+        let mut tracer = PathTracer::new();
+        tracer.set_caustic_photons(200_000);
+        tracer.set_caustic_radius(0.5);
+        let image = tracer.render(&world, &camera());
+        assert!((image.get(0, 0).r - expected).abs() < 0.02 * expected);
+    }
+
+    #[test]
+    fn fog() {
+        // emissive sphere seen through 4 units of absorbing fog
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(diffuse(Color::black(), Color::white()));
+        world.add_object(sphere);
+        world.set_medium(Medium::new(Color::new(0.1, 0.1, 0.1), Color::black(), 0.));
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(4096);
+        let image = tracer.render(&world, &camera());
+        let expected = (-0.4f64).exp();
+        assert!((image.get(0, 0).r - expected).abs() < 0.03 * expected);
+    }
+
+    #[test]
+    fn medium_boundary() {
+        // the surface of a shape filled with a clear medium is invisible
+        let mut world = World::new();
+        world.set_environment(Environment::new(Canvas::with_color(8, 4, Color::new(0.2, 0.4, 0.6))));
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(Material {
+            medium: Some(Medium::new(Color::black(), Color::black(), 0.)),
+            ..Material::default()
+        });
+        world.add_object(sphere);
+        let image = PathTracer::new().render(&world, &camera());
+        assert_eq!(image.get(0, 0), &Color::new(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn volume_shadow() {
+        // the light at (4, 4, 0) reaches the floor through the center of a
+        // smoke sphere: L = 1 / π * I * cos θ * e^(-2σ)
+        let mut world = World::new();
+        let mut floor = Quadric::plane();
+        floor.set_material(diffuse(Color::white(), Color::black()));
+        world.add_object(floor);
+        let mut smoke = Quadric::sphere();
+        smoke.set_transform(Matrix::translation(2., 2., 0.));
+        smoke.set_material(Material {
+            medium: Some(Medium::new(Color::new(0.5, 0.5, 0.5), Color::black(), 0.)),
+            ..Material::default()
+        });
+        world.add_object(smoke);
+        world.add_light(PointLight::new(Point::new(4., 4., 0.), Color::white()));
+        let image = PathTracer::new().render(&world, &camera());
+        let expected = 1. / PI * 2f64.sqrt() / 2. * (-1f64).exp();
+        assert!((image.get(0, 0).r - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn heterogeneous_fog() {
+        // density rising linearly from the top of the sphere to the camera:
+        // optical depth σ ∫ (y - 1) / 4 dy over [1,5] is 2σ
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(diffuse(Color::black(), Color::white()));
+        world.add_object(sphere);
+        let mut density = Density::grid(2, 2, 2, |_, v, _| v);
+        density.set_transform(Matrix::translation(-1., 1., -1.) * Matrix::scaling(2., 4., 2.));
+        world.set_medium(Medium {
+            density: Some(density),
+            ..Medium::new(Color::new(0.2, 0.2, 0.2), Color::black(), 0.)
+        });
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(4096);
+        let image = tracer.render(&world, &camera());
+        let expected = (-0.4f64).exp();
+        assert!((image.get(0, 0).r - expected).abs() < 0.03 * expected);
+    }
+
+    #[test]
+    fn noise_fog() {
+        // shadow rays towards a directional light leave the fog at its bounds
+        let mut world = World::new();
+        let mut floor = Quadric::plane();
+        floor.set_material(diffuse(Color::white(), Color::black()));
+        world.add_object(floor);
+        world.add_light(DirectionalLight::new(Vector::new(0., -1., 0.), Color::white()));
+        let mut density = Density::noise(3, 1);
+        density.set_transform(Matrix::scaling(10., 10., 10.));
+        world.set_medium(Medium {
+            density: Some(density),
+            ..Medium::new(Color::new(0.05, 0.05, 0.05), Color::new(0.05, 0.05, 0.05), 0.)
+        });
+        let mut tracer = PathTracer::new();
+        tracer.set_samples_per_pixel(16);
+        let image = tracer.render(&world, &camera());
+        let color = image.get(0, 0);
+        assert!(color.r.is_finite() && color.r > 0. && color.r < 1. / PI);
+    }
+
+    #[test]
+    fn single_scattering() {
+        // light at distance 1 from the camera ray scattered once by
+        // isotropic fog: L = ∫ σ e^(-σ t) / 4π * I e^(-σ r) / r² dt
+        let sigma = 0.5;
+        let mut world = World::new();
+        world.set_medium(Medium::new(Color::black(), Color::new(sigma, sigma, sigma), 0.));
+        world.add_light(PointLight::from_power(Point::new(1., 5., 0.), Color::white(), 4. * PI));
+        let mut tracer = PathTracer::new();
+        tracer.set_max_depth(1);
+        tracer.set_samples_per_pixel(16384);
+        let image = tracer.render(&world, &camera());
+        let step = 1e-3;
+        let expected: f64 = (0..60_000)
+            .map(|i| {
+                let t = (i as f64 + 0.5) * step;
+                let r2 = 1. + t * t;
+                sigma * (-sigma * t).exp() / (4. * PI) * (-sigma * r2.sqrt()).exp() / r2 * step
+            })
+            .sum();
+        assert!((image.get(0, 0).r - expected).abs() < 0.03 * expected);
+    }
+
+    #[test]
+    fn scattering_furnace() {
+        // non-absorbing fog inside an emitting sphere: every path reaches
+        // the sphere, L = E
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::scaling(10., 10., 10.));
+        sphere.set_material(diffuse(Color::black(), Color::white()));
+        world.add_object(sphere);
+        world.set_medium(Medium::new(Color::black(), Color::new(0.2, 0.2, 0.2), 0.5));
+        let mut tracer = PathTracer::new();
+        tracer.set_max_depth(64);
+        tracer.set_roulette_depth(64);
+        let image = tracer.render(&world, &camera());
+        assert_eq!(image.get(0, 0), &Color::white());
+    }
+
+    #[test]
+    fn subsurface() {
+        // thick translucent sphere under a uniform sky seen from above:
+        // L = 1 - H(1) √(1 - ω) with Chandrasekhar's H-function for isotropic
+        // scattering of albedo ω, solved by iteration
+        let color = Color::new(0.5, 0.5, 0.5);
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::translation(0., -100., 0.) * Matrix::scaling(100., 100., 100.));
+        sphere.set_material(Material::subsurface(color, Color::new(0.1, 0.1, 0.1)));
+        world.add_object(sphere);
+        world.set_environment(Environment::new(Canvas::with_color(16, 8, Color::white())));
+        let mut tracer = PathTracer::new();
+        tracer.set_max_depth(1024);
+        tracer.set_samples_per_pixel(1024);
+        let image = tracer.render(&world, &camera());
+        let medium = Medium::from_albedo(color, Color::white());
+        let omega = medium.scattering.r / medium.extinction().r;
+        let n = 200;
+        let mus: Vec<f64> = (0..n).map(|i| (i as f64 + 0.5) / n as f64).collect();
+        let mut h = vec![1.; n];
+        let solve = |h: &[f64], mu: f64| {
+            let integral: f64 = mus.iter().zip(h).map(|(m, h)| h / (mu + m)).sum::<f64>() / n as f64;
+            1. / (1. - omega / 2. * mu * integral)
+        };
+        for _ in 0..100 {
+            h = mus.iter().map(|&mu| solve(&h, mu)).collect();
+        }
+        let expected = 1. - solve(&h, 1.) * (1. - omega).sqrt();
+        assert!((image.get(0, 0).r - expected).abs() < 0.03 * expected);
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::scaling(10., 10., 10.));
+        sphere.set_material(diffuse(Color::new(0.5, 0.5, 0.5), Color::white()));
+        world.add_object(sphere);
+        let mut tracer = PathTracer::new();
+        let a = tracer.render(&world, &camera());
+        let b = tracer.render(&world, &camera());
+        assert_eq!(a.get(0, 0), b.get(0, 0));
+        tracer.set_seed(1);
+        let c = tracer.render(&world, &camera());
+        assert_ne!(a.get(0, 0), c.get(0, 0));
+    }
+}
@@ -0,0 +1,200 @@
+use std::f64::consts::PI;
+
+use crate::{kd_tree::KdTree, util::Rng, Bsdf, Color, Computations, Emitter, Intersection, Point, Ray, Vector, World};
+
+/// Photons traced from the lights and stored where they land, used to
+/// estimate the light reflected by surfaces from the density of photons
+/// around a point.
+///
+/// The caustic map stores the photons reaching a diffuse surface after one or
+/// more bounces off specular surfaces (see [`Bsdf::is_specular`]), the paths
+/// that Monte Carlo path tracing struggles to find from the camera.
+#[derive(Clone, Debug)]
+pub struct PhotonMap {
+    tree: KdTree<Photon>,
+}
+
+/// Light landing on a surface.
+#[derive(Copy, Clone, Debug)]
+struct Photon {
+    /// unit vector towards the surface the photon comes from
+    direction: Vector,
+    /// radiant flux carried by the photon
+    power: Color,
+}
+
+impl PhotonMap {
+    /// Creates the caustic `PhotonMap` of a world by emitting `count` photons
+    /// from the lights of the world which have an [`Emitter`], each photon
+    /// bouncing at most `max_depth` times.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::f64::consts::PI;
+    /// # use ray_tracer_challenge::{Color, Material, Matrix, PhotonMap, Point, PointLight, Quadric, Shape, World};
+    /// let mut world = World::new();
+    /// world.add_object(Quadric::plane());
+    /// world.add_light(PointLight::from_power(Point::new(0., 1., 0.), Color::white(), 4. * PI));
+    /// // no specular surface, no caustics
+    /// assert!(PhotonMap::caustics(&world, 1000, 4, 0).is_empty());
+    /// let mut mirror = Quadric::plane();
+    /// mirror.set_transform(Matrix::translation(1., 0., 0.) * Matrix::rotation_z(PI / 2.));
+    /// mirror.set_material(Material::pbr(Color::white(), 0.1, 1.));
+    /// world.add_object(mirror);
+    /// assert!(!PhotonMap::caustics(&world, 1000, 4, 0).is_empty());
+    /// ```
+    pub fn caustics(world: &World, count: usize, max_depth: usize, seed: u64) -> Self {
+        let emitters: Vec<Emitter> = world.light_sources().filter_map(|light| light.emitter()).collect();
+        let mut rng = Rng::from_values(&[seed as f64, count as f64]);
+        let mut photons = vec![];
+        for _ in 0..count {
+            let (emitter, choice) = match Emitter::choose(&emitters, rng.next_f64()) {
+                Some(chosen) => chosen,
+                None => break,
+            };
+            let (position, pdf_position) = emitter.sample_position(rng.next_f64(), rng.next_f64());
+            let direction = emitter.sample_direction(rng.next_f64(), rng.next_f64());
+            let pdf = emitter.direction_pdf(direction);
+            if pdf <= 0. {
+                continue;
+            }
+            let power = emitter.intensity(position, direction) * (1. / (choice * pdf_position * pdf * count as f64));
+            Self::trace(
+                world,
+                &Ray::new(position, direction),
+                power,
+                max_depth,
+                &mut rng,
+                &mut photons,
+            );
+        }
+        Self {
+            tree: KdTree::new(photons),
+        }
+    }
+
+    /// Returns the number of photons of the map.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns `true` if the map contains no photons.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the radiance reflected towards `wo` at a point of a surface
+    /// estimated from the photons within `radius` of the point.
+    pub fn estimate(&self, point: Point, normal: Vector, wo: Vector, bsdf: &Bsdf, radius: f64) -> Color {
+        let mut sum = Color::black();
+        self.tree.within(point, radius, |photon, _| {
+            sum += bsdf.eval(normal, wo, photon.direction) * photon.power;
+        });
+        sum * (1. / (PI * radius * radius))
+    }
+
+    /// Follows a photon through specular bounces and stores it where it
+    /// lands on a diffuse surface after at least one of them.
+    fn trace(
+        world: &World,
+        ray: &Ray,
+        mut power: Color,
+        max_depth: usize,
+        rng: &mut Rng,
+        photons: &mut Vec<(Point, Photon)>,
+    ) {
+        let mut ray = *ray;
+        for depth in 0..max_depth {
+            let intersections = world.intersect(&ray);
+            let hit = match Intersection::hit(&intersections) {
+                Some(hit) => hit,
+                None => break,
+            };
+            let comps = Computations::new(&hit, &ray);
+            let bsdf = Bsdf::new(comps.object.material());
+            if bsdf.is_black() {
+                break;
+            }
+            let (normal, wo) = (comps.normalv, comps.eyev);
+            if !bsdf.is_specular() {
+                if depth > 0 {
+                    photons.push((comps.point, Photon { direction: wo, power }));
+                }
+                break;
+            }
+            let wi = match bsdf.sample(normal, wo, rng.next_f64(), rng.next_f64(), rng.next_f64()) {
+                Some(wi) => wi,
+                None => break,
+            };
+            let pdf = bsdf.pdf(normal, wo, wi);
+            if pdf <= 0. {
+                break;
+            }
+            power *= bsdf.eval(normal, wo, wi) * (wi.dot(normal) / pdf);
+            ray = Ray::new(comps.over_point, wi);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Material, Matrix, PointLight, Quadric, Shape};
+
+    use super::*;
+
+    fn world(light: PointLight) -> World {
+        // white floor next to a mirror wall lit by a light at (0, 2, 0)
+        let mut world = World::new();
+        let mut floor = Quadric::plane();
+        floor.set_material(Material {
+            diffuse: 1.,
+            specular: 0.,
+            ..Material::default()
+        });
+        world.add_object(floor);
+        let mut mirror = Quadric::plane();
+        mirror.set_transform(Matrix::translation(1., 0., 0.) * Matrix::rotation_z(PI / 2.));
+        mirror.set_material(Material::pbr(Color::white(), 0.05, 1.));
+        world.add_object(mirror);
+        world.add_light(light);
+        world
+    }
+
+    fn light() -> PointLight {
+        PointLight::from_power(Point::new(0., 2., 0.), Color::white(), 4. * PI)
+    }
+
+    #[test]
+    fn no_emitters() {
+        let world = world(PointLight::new(Point::new(0., 2., 0.), Color::white()));
+        assert!(PhotonMap::caustics(&world, 100, 4, 0).is_empty());
+    }
+
+    #[test]
+    fn photons_after_specular_bounces() {
+        let map = PhotonMap::caustics(&world(light()), 1000, 4, 0);
+        // a fifth of the photons goes down towards the mirror, the others
+        // escape or land directly on the floor
+        assert!(map.len() > 150 && map.len() < 250);
+        map.tree.within(Point::new(0., 0., 0.), 100., |photon, _| {
+            assert!(photon.direction.x > 0.);
+            assert!(photon.direction.y > 0.);
+        });
+    }
+
+    #[test]
+    fn estimate() {
+        // mirror image of the light at (2, 2, 0): E = I cos θ / d²
+        let map = PhotonMap::caustics(&world(light()), 1_000_000, 4, 0);
+        let bsdf = Bsdf::new(&Material {
+            diffuse: 1.,
+            specular: 0.,
+            ..Material::default()
+        });
+        let normal = Vector::new(0., 1., 0.);
+        let radiance = map.estimate(Point::new(0., 0., 0.), normal, normal, &bsdf, 0.3);
+        let expected = 1. / PI * 2f64.sqrt() / 2. / 8.;
+        assert!((radiance.r - expected).abs() < 0.05 * expected);
+    }
+}
@@ -0,0 +1,108 @@
+use std::f64::consts::PI;
+
+use crate::{Attenuation, Color, Emitter, Light, LightSample, Point};
+
+/// Light source with no size located at a single point in space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PointLight {
+    /// position of the light
+    pub position: Point,
+    /// intensity of the light
+    pub intensity: Color,
+    /// attenuation of the intensity with distance
+    pub attenuation: Attenuation,
+}
+
+impl PointLight {
+    /// Creates a `PointLight` from specified position and intensity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, PointLight, Point};
+    /// let light = PointLight::new(Point::new(0., 0., 0.), Color::white());
+    /// assert_eq!(light.position, Point::new(0., 0., 0.));
+    /// assert_eq!(light.intensity, Color::white());
+    /// ```
+    pub fn new(position: Point, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+            attenuation: Attenuation::none(),
+        }
+    }
+
+    /// Creates a physically based `PointLight` from its radiant power (watts)
+    /// emitted uniformly in all directions.
+    ///
+    /// The intensity is the radiant intensity `power / 4π` (watts per
+    /// steradian) tinted by `color` and the attenuation is inverse-square, so
+    /// that with distances in meters light samples give the irradiance
+    /// (watts per square meter) received at normal incidence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::f64::consts::PI;
+    /// # use ray_tracer_challenge::{Color, Light, PointLight, Point};
+    /// let light = PointLight::from_power(Point::new(0., 2., 0.), Color::white(), 4. * PI);
+    /// let samples = light.samples(Point::new(0., 0., 0.));
+    /// assert_eq!(samples[0].intensity, Color::new(0.25, 0.25, 0.25));
+    /// ```
+    pub fn from_power(position: Point, color: Color, power: f64) -> Self {
+        Self {
+            position,
+            intensity: color * (power / (4. * PI)),
+            attenuation: Attenuation::inverse_square(),
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn samples(&self, point: Point) -> Vec<LightSample> {
+        let mut sample = LightSample::at(self.position, point, self.intensity);
+        sample.intensity *= self.attenuation.factor(sample.distance);
+        vec![sample]
+    }
+
+    fn emitter(&self) -> Option<Emitter> {
+        (self.attenuation == Attenuation::inverse_square()).then(|| Emitter::point(self.position, self.intensity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{util::float_eq, Vector};
+
+    use super::*;
+
+    #[test]
+    fn samples() {
+        let light = PointLight::new(Point::new(0., 10., 0.), Color::new(0.5, 0.5, 0.5));
+        let samples = light.samples(Point::new(0., 0., 0.));
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].direction, Vector::new(0., 1., 0.));
+        assert!(float_eq(samples[0].distance, 10.));
+        assert_eq!(samples[0].intensity, Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn samples_attenuated() {
+        let mut light = PointLight::new(Point::new(0., 10., 0.), Color::white());
+        light.attenuation = Attenuation::new(1., 0.1, 0.);
+        let samples = light.samples(Point::new(0., 0., 0.));
+        assert_eq!(samples[0].intensity, Color::new(0.5, 0.5, 0.5));
+        let samples = light.samples(Point::new(0., 5., 0.));
+        assert_eq!(samples[0].intensity, Color::new(1. / 1.5, 1. / 1.5, 1. / 1.5));
+    }
+
+    #[test]
+    fn from_power() {
+        let light = PointLight::from_power(Point::new(0., 0., 0.), Color::new(1., 0.5, 0.), 100.);
+        assert_eq!(light.intensity, Color::new(100., 50., 0.) * (1. / (4. * PI)));
+        assert_eq!(light.attenuation, Attenuation::inverse_square());
+        let near = light.samples(Point::new(1., 0., 0.))[0].intensity;
+        let far = light.samples(Point::new(2., 0., 0.))[0].intensity;
+        assert_eq!(near * 0.25, far);
+    }
+}
@@ -0,0 +1,17 @@
+/// Mapping of the canvas of a [`Camera`](crate::Camera) to the rays leaving
+/// the camera.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum Projection {
+    /// rays leaving the camera through a plane one unit in front of it
+    #[default]
+    Perspective,
+    /// parallel rays along the view axis leaving a rectangle centered on the
+    /// camera
+    Orthographic,
+    /// equidistant fisheye: the angle of a ray to the view axis is
+    /// proportional to the distance of its pixel to the center of the canvas
+    Fisheye,
+    /// 360° panorama: the columns of the canvas map to the longitudes and
+    /// its rows to the latitudes around the camera
+    Equirectangular,
+}
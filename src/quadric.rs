@@ -0,0 +1,428 @@
+use crate::{Attributes, Bounds, Intersection, Matrix, Point, Ray, Shape, Vector};
+
+/// Implicit quadric surface defined by a symmetric coefficient matrix `Q`.
+///
+/// The surface is the set of points `r = (x,y,z,1)` satisfying `rᵀQr = 0`,
+/// points where `rᵀQr < 0` are considered inside. Normals are given by the
+/// gradient `2Qr`.
+#[derive(Clone, Debug)]
+pub struct Quadric {
+    coefficients: Matrix,
+    attributes: Attributes,
+}
+
+impl Quadric {
+    const LINEAR_EPSILON: f64 = 1e-12;
+
+    /// Creates a `Quadric` from specified coefficient matrix.
+    ///
+    /// The matrix is symmetrized (`(Q + Qᵀ) / 2`) as only its symmetric part
+    /// contributes to the quadratic form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Matrix, Point, Quadric, Ray, Shape, Vector};
+    /// // unit sphere: x² + y² + z² - 1 = 0
+    /// let quadric = Quadric::new(Matrix::new([
+    ///     1., 0., 0., 0.,
+    ///     0., 1., 0., 0.,
+    ///     0., 0., 1., 0.,
+    ///     0., 0., 0., -1.,
+    /// ]));
+    /// let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+    /// let xs = quadric.intersect(&ray);
+    /// assert_eq!(xs.len(), 2);
+    /// assert_eq!(xs[0].t, 4.);
+    /// assert_eq!(xs[1].t, 6.);
+    /// ```
+    pub fn new(coefficients: Matrix) -> Self {
+        let mut symmetric = Matrix::default();
+        for row in 0..4 {
+            for col in 0..4 {
+                *symmetric.get_mut(row, col) = (coefficients.get(row, col) + coefficients.get(col, row)) / 2.;
+            }
+        }
+        Self {
+            coefficients: symmetric,
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Creates the unit sphere `x² + y² + z² = 1`.
+    pub fn sphere() -> Self {
+        Self::ellipsoid(1., 1., 1.)
+    }
+
+    /// Creates the ellipsoid `x²/a² + y²/b² + z²/c² = 1`.
+    pub fn ellipsoid(a: f64, b: f64, c: f64) -> Self {
+        #[rustfmt::skip]
+        let coefficients = Matrix::new([
+            1. / (a * a), 0., 0., 0.,
+            0., 1. / (b * b), 0., 0.,
+            0., 0., 1. / (c * c), 0.,
+            0., 0., 0., -1.,
+        ]);
+        Self::new(coefficients)
+    }
+
+    /// Creates the circular paraboloid `y = x² + z²`.
+    pub fn paraboloid() -> Self {
+        #[rustfmt::skip]
+        let coefficients = Matrix::new([
+            1., 0., 0., 0.,
+            0., 0., 0., -0.5,
+            0., 0., 1., 0.,
+            0., -0.5, 0., 0.,
+        ]);
+        Self::new(coefficients)
+    }
+
+    /// Creates the hyperboloid of one sheet `x² + z² - y² = 1`.
+    pub fn hyperboloid() -> Self {
+        #[rustfmt::skip]
+        let coefficients = Matrix::new([
+            1., 0., 0., 0.,
+            0., -1., 0., 0.,
+            0., 0., 1., 0.,
+            0., 0., 0., -1.,
+        ]);
+        Self::new(coefficients)
+    }
+
+    /// Creates the plane `y = 0`, the half space `y < 0` being inside.
+    pub fn plane() -> Self {
+        #[rustfmt::skip]
+        let coefficients = Matrix::new([
+            0., 0., 0., 0.,
+            0., 0., 0., 0.5,
+            0., 0., 0., 0.,
+            0., 0.5, 0., 0.,
+        ]);
+        Self::new(coefficients)
+    }
+
+    /// Get the (symmetric) coefficient matrix.
+    #[must_use]
+    pub fn coefficients(&self) -> &Matrix {
+        &self.coefficients
+    }
+
+    /// Returns `uᵀQv` for homogeneous coordinates `u` and `v`.
+    fn form(&self, u: [f64; 4], v: [f64; 4]) -> f64 {
+        let mut sum = 0.;
+        for (row, u) in u.iter().enumerate() {
+            for (col, v) in v.iter().enumerate() {
+                sum += u * self.coefficients.get(row, col) * v;
+            }
+        }
+        sum
+    }
+}
+
+impl Shape for Quadric {
+    fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    fn attributes_mut(&mut self) -> &mut Attributes {
+        &mut self.attributes
+    }
+
+    /// Only ellipsoids (whose quadratic part is definite) are bounded.
+    fn bounds(&self) -> Bounds {
+        // rᵀQr = xᵀAx + 2bᵀx + c, oriented so that A is positive definite
+        let q = &self.coefficients;
+        let sign = q.get(0, 0).signum();
+        let mut a = Matrix::identity();
+        for row in 0..3 {
+            for col in 0..3 {
+                *a.get_mut(row, col) = sign * q.get(row, col);
+            }
+        }
+        let minor = a.get(0, 0) * a.get(1, 1) - a.get(0, 1) * a.get(1, 0);
+        if *a.get(0, 0) <= 0. || minor <= 0. || a.determinant() <= 0. {
+            return Bounds::infinite();
+        }
+        let Ok(inverse) = a.inverse() else {
+            return Bounds::infinite();
+        };
+        let b = [sign * q.get(0, 3), sign * q.get(1, 3), sign * q.get(2, 3)];
+        // centered at -A⁻¹b, the surface is (x - center)ᵀA(x - center) = k
+        let center: Vec<f64> = (0..3)
+            .map(|row| -(0..3).map(|col| inverse.get(row, col) * b[col]).sum::<f64>())
+            .collect();
+        let k = -(0..3).map(|i| b[i] * center[i]).sum::<f64>() - sign * q.get(3, 3);
+        if k < 0. {
+            return Bounds::empty();
+        }
+        let half = |i: usize| (k * inverse.get(i, i)).sqrt();
+        Bounds::new(
+            Point::new(center[0] - half(0), center[1] - half(1), center[2] - half(2)),
+            Point::new(center[0] + half(0), center[1] + half(1), center[2] + half(2)),
+        )
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let origin = [ray.origin.x, ray.origin.y, ray.origin.z, 1.];
+        let direction = [ray.direction.x, ray.direction.y, ray.direction.z, 0.];
+        let a = self.form(direction, direction);
+        let b = 2. * self.form(direction, origin);
+        let c = self.form(origin, origin);
+        // the equation is linear when the direction follows an asymptote of
+        // the quadric, relative to the length of the direction
+        let scale = ray.direction.dot(ray.direction);
+        let roots = if a.abs() <= Self::LINEAR_EPSILON * scale {
+            if b == 0. {
+                return vec![];
+            }
+            vec![-c / b]
+        } else {
+            let discriminant = b * b - 4. * a * c;
+            if discriminant < 0. {
+                return vec![];
+            }
+            // numerically stable roots, without cancellation between b and
+            // the square root of the discriminant
+            let q = -(b + b.signum() * discriminant.sqrt()) / 2.;
+            let (t1, t2) = if q == 0. { (0., 0.) } else { (q / a, c / q) };
+            vec![t1.min(t2), t1.max(t2)]
+        };
+        roots
+            .into_iter()
+            .map(|t| Intersection::new(t, self, self.local_normal_at(ray.position(t), ray.time)))
+            .collect()
+    }
+
+    fn local_normal_at(&self, point: Point, _time: f64) -> Vector {
+        let r = [point.x, point.y, point.z, 1.];
+        let q = &self.coefficients;
+        let gradient = |row: usize| (0..4).map(|col| q.get(row, col) * r[col]).sum::<f64>();
+        Vector::new(gradient(0), gradient(1), gradient(2)).normalized()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{util::float_eq, Motion};
+
+    use super::*;
+
+    #[test]
+    fn intersect_from_surface() {
+        let quadric = Quadric::sphere();
+        let ray = Ray::new(Point::new(0., 0., -1.), Vector::new(0., 0., 1.));
+        let xs = quadric.local_intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        assert!(float_eq(xs[0].t, 0.));
+        assert!(float_eq(xs[1].t, 2.));
+        let ray = Ray::new(Point::new(0., 0., 1.), Vector::new(0., 0., 1.));
+        let xs = quadric.local_intersect(&ray);
+        assert!(float_eq(xs[0].t, -2.));
+        assert!(float_eq(xs[1].t, 0.));
+    }
+
+    #[test]
+    fn intersect_large_ellipsoid() {
+        let quadric = Quadric::ellipsoid(500., 500., 500.);
+        let ray = Ray::new(Point::new(0., 0., -1000.), Vector::new(0., 0., 1.));
+        let xs = quadric.local_intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        assert!(float_eq(xs[0].t, 500.));
+        assert!(float_eq(xs[1].t, 1500.));
+    }
+
+    #[test]
+    fn symmetrize() {
+        #[rustfmt::skip]
+        let quadric = Quadric::new(Matrix::new([
+            1., 2., 0., 0.,
+            0., 1., 0., 0.,
+            0., 0., 1., 0.,
+            0., 0., 4., -1.,
+        ]));
+        #[rustfmt::skip]
+        let expected = Matrix::new([
+            1., 1., 0., 0.,
+            1., 1., 0., 0.,
+            0., 0., 1., 2.,
+            0., 0., 2., -1.,
+        ]);
+        assert_eq!(quadric.coefficients(), &expected);
+    }
+
+    #[test]
+    fn intersect_sphere() {
+        let quadric = Quadric::sphere();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = quadric.intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        assert!(float_eq(xs[0].t, 4.));
+        assert!(float_eq(xs[1].t, 6.));
+        assert_eq!(xs[0].normal, Vector::new(0., 0., -1.));
+        assert_eq!(xs[1].normal, Vector::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn intersect_tangent() {
+        let quadric = Quadric::sphere();
+        let ray = Ray::new(Point::new(0., 1., -5.), Vector::new(0., 0., 1.));
+        let xs = quadric.intersect(&ray);
+        assert_eq!(xs.len(), 2);
+        assert!(float_eq(xs[0].t, 5.));
+        assert!(float_eq(xs[1].t, 5.));
+    }
+
+    #[test]
+    fn intersect_miss() {
+        let quadric = Quadric::sphere();
+        let ray = Ray::new(Point::new(0., 2., -5.), Vector::new(0., 0., 1.));
+        assert!(quadric.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_inside() {
+        let quadric = Quadric::sphere();
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let xs = quadric.intersect(&ray);
+        assert!(float_eq(xs[0].t, -1.));
+        assert!(float_eq(xs[1].t, 1.));
+    }
+
+    #[test]
+    fn intersect_ellipsoid() {
+        let quadric = Quadric::ellipsoid(1., 2., 3.);
+        let ray = Ray::new(Point::new(0., -5., 0.), Vector::new(0., 1., 0.));
+        let xs = quadric.intersect(&ray);
+        assert!(float_eq(xs[0].t, 3.));
+        assert!(float_eq(xs[1].t, 7.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = quadric.intersect(&ray);
+        assert!(float_eq(xs[0].t, 2.));
+        assert!(float_eq(xs[1].t, 8.));
+    }
+
+    #[test]
+    fn intersect_paraboloid() {
+        let quadric = Quadric::paraboloid();
+        let ray = Ray::new(Point::new(-5., 4., 0.), Vector::new(1., 0., 0.));
+        let xs = quadric.intersect(&ray);
+        assert!(float_eq(xs[0].t, 3.));
+        assert!(float_eq(xs[1].t, 7.));
+        let ray = Ray::new(Point::new(0., 5., 0.), Vector::new(0., -1., 0.));
+        let xs = quadric.intersect(&ray);
+        assert_eq!(xs.len(), 1);
+        assert!(float_eq(xs[0].t, 5.));
+        assert_eq!(xs[0].normal, Vector::new(0., -1., 0.));
+    }
+
+    #[test]
+    fn intersect_hyperboloid() {
+        let quadric = Quadric::hyperboloid();
+        let ray = Ray::new(Point::new(-5., 0., 0.), Vector::new(1., 0., 0.));
+        let xs = quadric.intersect(&ray);
+        assert!(float_eq(xs[0].t, 4.));
+        assert!(float_eq(xs[1].t, 6.));
+        let ray = Ray::new(Point::new(0., -5., 0.), Vector::new(0., 1., 0.));
+        assert!(quadric.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_plane() {
+        let quadric = Quadric::plane();
+        let ray = Ray::new(Point::new(0., 1., 0.), Vector::new(0., -1., 0.));
+        let xs = quadric.intersect(&ray);
+        assert_eq!(xs.len(), 1);
+        assert!(float_eq(xs[0].t, 1.));
+        assert_eq!(xs[0].normal, Vector::new(0., 1., 0.));
+        let ray = Ray::new(Point::new(0., 1., 0.), Vector::new(0., 0., 1.));
+        assert!(quadric.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_transformed() {
+        let mut quadric = Quadric::sphere();
+        quadric.set_transform(Matrix::scaling(2., 2., 2.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = quadric.intersect(&ray);
+        assert!(float_eq(xs[0].t, 3.));
+        assert!(float_eq(xs[1].t, 7.));
+    }
+
+    #[test]
+    fn intersect_moving() {
+        let mut quadric = Quadric::sphere();
+        quadric.set_motion(Motion::new(Matrix::identity(), Matrix::translation(4., 0., 0.)));
+        assert_eq!(quadric.transform(), &Matrix::identity());
+        let ray = Ray::new(Point::new(2., 0., -5.), Vector::new(0., 0., 1.));
+        assert!(quadric.intersect(&ray).is_empty());
+        let xs = quadric.intersect(&ray.with_time(0.5));
+        assert!(float_eq(xs[0].t, 4.));
+        assert_eq!(xs[0].normal, Vector::new(0., 0., -1.));
+        assert!(quadric.intersect(&ray.with_time(1.)).is_empty());
+        // a new transform stops the shape
+        quadric.set_transform(Matrix::translation(2., 0., 0.));
+        assert_eq!(quadric.motion(), None);
+        assert_eq!(quadric.intersect(&ray).len(), 2);
+    }
+
+    #[test]
+    fn normal_moving() {
+        // spinning ellipsoid: normals follow the transform at the ray time
+        let mut quadric = Quadric::ellipsoid(2., 1., 1.);
+        let end = Matrix::translation(0., 1., 0.) * Matrix::rotation_z(PI / 2.);
+        quadric.set_motion(Motion::new(Matrix::identity(), end));
+        let ray = Ray::new(Point::new(-0.3, -5., 0.3), Vector::new(0.1, 1., 0.));
+        for time in [0., 0.3, 0.5, 1.] {
+            let ray = ray.with_time(time);
+            let hit = quadric.intersect(&ray)[0];
+            assert_eq!(quadric.normal_at(ray.position(hit.t), time), hit.normal);
+        }
+        let point = Point::new(0., 3., 0.);
+        assert_eq!(quadric.normal_at(point, 1.), Vector::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn bounds() {
+        let quadric = Quadric::ellipsoid(1., 2., 3.);
+        assert_eq!(
+            quadric.bounds(),
+            Bounds::new(Point::new(-1., -2., -3.), Point::new(1., 2., 3.))
+        );
+        // coefficients of an ellipsoid moved away from the origin, inside
+        // being positive
+        let transform = Matrix::translation(1., -2., 0.5) * Matrix::rotation_z(PI / 2.);
+        let inverse = transform.inverse().unwrap();
+        let mut coefficients = inverse.transpose() * *Quadric::ellipsoid(1., 2., 3.).coefficients() * inverse;
+        for row in 0..4 {
+            for col in 0..4 {
+                *coefficients.get_mut(row, col) *= -1.;
+            }
+        }
+        let quadric = Quadric::new(coefficients);
+        assert_eq!(
+            quadric.bounds(),
+            Bounds::new(Point::new(-1., -3., -2.5), Point::new(3., -1., 3.5))
+        );
+        assert_eq!(Quadric::paraboloid().bounds(), Bounds::infinite());
+        assert_eq!(Quadric::hyperboloid().bounds(), Bounds::infinite());
+        assert_eq!(Quadric::plane().bounds(), Bounds::infinite());
+        let mut moving = Quadric::sphere();
+        moving.set_motion(Motion::new(Matrix::identity(), Matrix::translation(1., 0., 0.)));
+        assert_eq!(moving.bounds(), Quadric::sphere().bounds());
+        assert_eq!(moving.parent_bounds(), Bounds::infinite());
+    }
+
+    #[test]
+    fn normal() {
+        let quadric = Quadric::sphere();
+        let value = 3f64.sqrt() / 3.;
+        let normal = quadric.normal_at(Point::new(value, value, value), 0.);
+        assert_eq!(normal, Vector::new(value, value, value));
+        let quadric = Quadric::hyperboloid();
+        let normal = quadric.normal_at(Point::new(1., 1., 1.), 0.);
+        assert_eq!(normal, Vector::new(1., -1., 1.).normalized());
+    }
+}
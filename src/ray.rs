@@ -0,0 +1,130 @@
+use crate::{Matrix, Point, Vector};
+
+/// Half-line starting at an `origin` and extending infinitely along a
+/// `direction`, at an instant of the shutter interval.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Ray {
+    /// starting point of the ray
+    pub origin: Point,
+    /// direction of the ray (not necessarily normalized)
+    pub direction: Vector,
+    /// instant the ray is traced at, moving shapes are intersected where
+    /// they are at that time (see [`Motion`](crate::Motion))
+    pub time: f64,
+}
+
+impl Ray {
+    /// Creates a `Ray` from specified origin and direction at time `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Point, Ray, Vector};
+    /// let origin = Point::new(1., 2., 3.);
+    /// let direction = Vector::new(4., 5., 6.);
+    /// let ray = Ray::new(origin, direction);
+    /// assert_eq!(ray.origin, origin);
+    /// assert_eq!(ray.direction, direction);
+    /// assert_eq!(ray.time, 0.);
+    /// ```
+    pub fn new(origin: Point, direction: Vector) -> Self {
+        Self {
+            origin,
+            direction,
+            time: 0.,
+        }
+    }
+
+    /// Returns the ray traced at specified time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Point, Ray, Vector};
+    /// let ray = Ray::new(Point::new(1., 2., 3.), Vector::new(0., 1., 0.)).with_time(0.5);
+    /// assert_eq!(ray.time, 0.5);
+    /// ```
+    #[must_use]
+    pub fn with_time(self, time: f64) -> Self {
+        Self { time, ..self }
+    }
+
+    /// Returns the point at distance `t` along the ray.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Point, Ray, Vector};
+    /// let ray = Ray::new(Point::new(2., 3., 4.), Vector::new(1., 0., 0.));
+    /// assert_eq!(ray.position(-1.), Point::new(1., 3., 4.));
+    /// assert_eq!(ray.position(2.5), Point::new(4.5, 3., 4.));
+    /// ```
+    pub fn position(&self, t: f64) -> Point {
+        self.origin + self.direction * t
+    }
+
+    /// Returns the ray transformed by specified matrix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Matrix, Point, Ray, Vector};
+    /// let ray = Ray::new(Point::new(1., 2., 3.), Vector::new(0., 1., 0.));
+    /// let ray = ray.transform(Matrix::translation(3., 4., 5.));
+    /// assert_eq!(ray.origin, Point::new(4., 6., 8.));
+    /// assert_eq!(ray.direction, Vector::new(0., 1., 0.));
+    /// ```
+    pub fn transform(&self, matrix: Matrix) -> Self {
+        Self {
+            origin: matrix * self.origin,
+            direction: matrix * self.direction,
+            time: self.time,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        let origin = Point::new(1., 2., 3.);
+        let direction = Vector::new(4., 5., 6.);
+        let ray = Ray::new(origin, direction);
+        assert_eq!(ray.origin, origin);
+        assert_eq!(ray.direction, direction);
+    }
+
+    #[test]
+    fn position() {
+        let ray = Ray::new(Point::new(2., 3., 4.), Vector::new(1., 0., 0.));
+        assert_eq!(ray.position(0.), Point::new(2., 3., 4.));
+        assert_eq!(ray.position(1.), Point::new(3., 3., 4.));
+        assert_eq!(ray.position(-1.), Point::new(1., 3., 4.));
+        assert_eq!(ray.position(2.5), Point::new(4.5, 3., 4.));
+    }
+
+    #[test]
+    fn translation() {
+        let ray = Ray::new(Point::new(1., 2., 3.), Vector::new(0., 1., 0.));
+        let ray = ray.transform(Matrix::translation(3., 4., 5.));
+        assert_eq!(ray.origin, Point::new(4., 6., 8.));
+        assert_eq!(ray.direction, Vector::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn scaling() {
+        let ray = Ray::new(Point::new(1., 2., 3.), Vector::new(0., 1., 0.));
+        let ray = ray.transform(Matrix::scaling(2., 3., 4.));
+        assert_eq!(ray.origin, Point::new(2., 6., 12.));
+        assert_eq!(ray.direction, Vector::new(0., 3., 0.));
+    }
+
+    #[test]
+    fn time() {
+        let ray = Ray::new(Point::new(1., 2., 3.), Vector::new(0., 1., 0.)).with_time(0.25);
+        assert_eq!(ray.time, 0.25);
+        assert_eq!(ray.transform(Matrix::scaling(2., 3., 4.)).time, 0.25);
+    }
+}
@@ -0,0 +1,349 @@
+use std::fmt::Debug;
+
+use crate::{util::EPSILON, Attributes, Intersection, Point, Ray, Shape, Vector};
+
+/// Shape defined by a signed distance function.
+///
+/// The distance function returns, for any object space point, the distance to
+/// the closest surface point: negative inside the shape, positive outside. It
+/// must never overestimate the true distance for sphere tracing to converge.
+///
+/// Rays are intersected by *sphere tracing*: the ray is marched by the
+/// distance to the surface until it falls below the tolerance, the maximum
+/// number of steps is reached or the ray travels beyond the maximum distance.
+/// Only the closest surface crossing is reported. Normals are estimated by
+/// central differences of the distance function.
+pub struct Sdf {
+    /// distance at a point and time (combinations of moving shapes vary
+    /// with time)
+    distance: Box<dyn Fn(Point, f64) -> f64>,
+    attributes: Attributes,
+    max_steps: usize,
+    tolerance: f64,
+    max_distance: f64,
+}
+
+impl Sdf {
+    const MAX_STEPS: usize = 256;
+    const TOLERANCE: f64 = 1.0e-6;
+    const MAX_DISTANCE: f64 = 1.0e3;
+
+    /// Creates an `Sdf` shape from specified distance function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Point, Ray, Sdf, Shape, Vector};
+    /// let sdf = Sdf::new(|p| (p.x * p.x + p.y * p.y + p.z * p.z).sqrt() - 1.);
+    /// let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+    /// let xs = sdf.intersect(&ray);
+    /// assert_eq!(xs.len(), 1);
+    /// assert!((xs[0].t - 4.).abs() < 1e-5);
+    /// ```
+    pub fn new(distance: impl Fn(Point) -> f64 + 'static) -> Self {
+        Self::with_time(move |point, _| distance(point))
+    }
+
+    /// Creates an `Sdf` shape from a distance function varying with time.
+    fn with_time(distance: impl Fn(Point, f64) -> f64 + 'static) -> Self {
+        Self {
+            distance: Box::new(distance),
+            attributes: Attributes::default(),
+            max_steps: Self::MAX_STEPS,
+            tolerance: Self::TOLERANCE,
+            max_distance: Self::MAX_DISTANCE,
+        }
+    }
+
+    /// Creates a sphere of specified radius centered at the origin.
+    pub fn sphere(radius: f64) -> Self {
+        Self::new(move |p| (p.x * p.x + p.y * p.y + p.z * p.z).sqrt() - radius)
+    }
+
+    /// Creates a box centered at the origin with specified half extents whose
+    /// edges are rounded by specified radius.
+    ///
+    /// The rounded box fits within `[-size, size]`.
+    pub fn rounded_box(size: Vector, radius: f64) -> Self {
+        Self::new(move |p| {
+            let qx = p.x.abs() - size.x + radius;
+            let qy = p.y.abs() - size.y + radius;
+            let qz = p.z.abs() - size.z + radius;
+            let outside = Vector::new(qx.max(0.), qy.max(0.), qz.max(0.)).len();
+            let inside = qx.max(qy).max(qz).min(0.);
+            outside + inside - radius
+        })
+    }
+
+    /// Creates the smooth union of two shapes blended over distance `k`.
+    ///
+    /// Operand transforms and motions are applied before blending, distances
+    /// are only exact for rigid transforms (no scaling or shearing). The
+    /// union is sharp (no blending) for `k <= 0`.
+    pub fn smooth_union(self, other: Sdf, k: f64) -> Self {
+        Self::with_time(move |p, time| {
+            let a = self.evaluate_at(self.inverse_at(time) * p, time);
+            let b = other.evaluate_at(other.inverse_at(time) * p, time);
+            if k <= 0. {
+                return a.min(b);
+            }
+            let h = (0.5 + 0.5 * (b - a) / k).clamp(0., 1.);
+            b + (a - b) * h - k * h * (1. - h)
+        })
+    }
+
+    /// Get the maximum number of sphere tracing steps.
+    #[must_use]
+    pub fn max_steps(&self) -> usize {
+        self.max_steps
+    }
+
+    /// Sets the maximum number of sphere tracing steps.
+    pub fn set_max_steps(&mut self, max_steps: usize) {
+        self.max_steps = max_steps;
+    }
+
+    /// Get the distance below which a point is considered on the surface.
+    #[must_use]
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    /// Sets the distance below which a point is considered on the surface.
+    pub fn set_tolerance(&mut self, tolerance: f64) {
+        self.tolerance = tolerance;
+    }
+
+    /// Get the object space distance beyond which rays are considered missing.
+    #[must_use]
+    pub fn max_distance(&self) -> f64 {
+        self.max_distance
+    }
+
+    /// Sets the object space distance beyond which rays are considered
+    /// missing.
+    pub fn set_max_distance(&mut self, max_distance: f64) {
+        self.max_distance = max_distance;
+    }
+
+    /// Returns the signed distance from specified object space point to the
+    /// surface (at time `0` for combinations of moving shapes).
+    pub fn evaluate(&self, point: Point) -> f64 {
+        self.evaluate_at(point, 0.)
+    }
+
+    /// Returns the signed distance from specified object space point to the
+    /// surface at specified time.
+    fn evaluate_at(&self, point: Point, time: f64) -> f64 {
+        (self.distance)(point, time)
+    }
+}
+
+impl Debug for Sdf {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt.debug_struct("Sdf")
+            .field("attributes", &self.attributes)
+            .field("max_steps", &self.max_steps)
+            .field("tolerance", &self.tolerance)
+            .field("max_distance", &self.max_distance)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Shape for Sdf {
+    fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    fn attributes_mut(&mut self) -> &mut Attributes {
+        &mut self.attributes
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        // march along the normalized direction, `t` is rescaled on hit
+        let len = ray.direction.len();
+        if len == 0. {
+            return vec![];
+        }
+        let direction = ray.direction / len;
+        let mut distance = 0.;
+        for _ in 0..self.max_steps {
+            let point = ray.origin + direction * distance;
+            let step = self.evaluate_at(point, ray.time).abs();
+            if step < self.tolerance {
+                let normal = self.local_normal_at(point, ray.time);
+                return vec![Intersection::new(distance / len, self, normal)];
+            }
+            distance += step;
+            if distance > self.max_distance {
+                break;
+            }
+        }
+        vec![]
+    }
+
+    fn local_normal_at(&self, point: Point, time: f64) -> Vector {
+        let dx = Vector::new(EPSILON, 0., 0.);
+        let dy = Vector::new(0., EPSILON, 0.);
+        let dz = Vector::new(0., 0., EPSILON);
+        let distance = |point: Point| self.evaluate_at(point, time);
+        Vector::new(
+            distance(point + dx) - distance(point - dx),
+            distance(point + dy) - distance(point - dy),
+            distance(point + dz) - distance(point - dz),
+        )
+        .normalized()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{util::float_eq, Matrix, Motion};
+
+    use super::*;
+
+    #[test]
+    fn intersect_sphere() {
+        let sdf = Sdf::sphere(1.);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = sdf.intersect(&ray);
+        assert_eq!(xs.len(), 1);
+        assert!(float_eq(xs[0].t, 4.));
+        assert_eq!(xs[0].normal, Vector::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn intersect_miss() {
+        let sdf = Sdf::sphere(1.);
+        let ray = Ray::new(Point::new(0., 2., -5.), Vector::new(0., 0., 1.));
+        assert!(sdf.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_inside() {
+        let sdf = Sdf::sphere(1.);
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let xs = sdf.intersect(&ray);
+        assert_eq!(xs.len(), 1);
+        assert!(float_eq(xs[0].t, 1.));
+        assert_eq!(xs[0].normal, Vector::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn intersect_transformed() {
+        let mut sdf = Sdf::sphere(1.);
+        sdf.set_transform(Matrix::scaling(2., 2., 2.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = sdf.intersect(&ray);
+        assert_eq!(xs.len(), 1);
+        assert!(float_eq(xs[0].t, 3.));
+
+        sdf.set_transform(Matrix::translation(5., 0., 0.));
+        assert!(sdf.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn intersect_unnormalized_direction() {
+        let sdf = Sdf::sphere(1.);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 2.));
+        let xs = sdf.intersect(&ray);
+        assert!(float_eq(xs[0].t, 2.));
+    }
+
+    #[test]
+    fn intersect_degenerate_direction() {
+        let sdf = Sdf::sphere(1.);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 0.));
+        assert!(sdf.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn max_steps() {
+        let mut sdf = Sdf::sphere(1.);
+        sdf.set_max_steps(1);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        assert!(sdf.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn max_distance() {
+        let mut sdf = Sdf::sphere(1.);
+        sdf.set_max_distance(3.);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        assert!(sdf.intersect(&ray).is_empty());
+    }
+
+    #[test]
+    fn tolerance() {
+        let mut sdf = Sdf::sphere(1.);
+        let ray = Ray::new(Point::new(0., 0.9, -5.), Vector::new(0., 0., 1.));
+        let exact = sdf.intersect(&ray)[0].t;
+        assert!(float_eq(exact, 5. - 0.19f64.sqrt()));
+        sdf.set_tolerance(0.5);
+        let coarse = sdf.intersect(&ray)[0].t;
+        assert!(coarse < exact - 0.1);
+    }
+
+    #[test]
+    fn normal() {
+        let sdf = Sdf::sphere(1.);
+        let value = 3f64.sqrt() / 3.;
+        let normal = sdf.normal_at(Point::new(value, value, value), 0.);
+        assert_eq!(normal, Vector::new(value, value, value));
+    }
+
+    #[test]
+    fn rounded_box() {
+        let sdf = Sdf::rounded_box(Vector::new(1., 1., 1.), 0.25);
+        assert!(float_eq(sdf.evaluate(Point::new(2., 0., 0.)), 1.));
+        assert!(float_eq(sdf.evaluate(Point::new(0., 0., 0.)), -1.));
+        let corner = 0.75 + 0.25 / 3f64.sqrt();
+        assert!(float_eq(sdf.evaluate(Point::new(corner, corner, corner)), 0.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = sdf.intersect(&ray);
+        assert!(float_eq(xs[0].t, 4.));
+    }
+
+    #[test]
+    fn smooth_union() {
+        let mut a = Sdf::sphere(1.);
+        a.set_transform(Matrix::translation(-1., 0., 0.));
+        let mut b = Sdf::sphere(1.);
+        b.set_transform(Matrix::translation(1., 0., 0.));
+        let sdf = a.smooth_union(b, 0.5);
+        assert!(float_eq(sdf.evaluate(Point::new(-2., 0., 0.)), 0.));
+        assert!(sdf.evaluate(Point::new(0., 0., 0.)) < 0.);
+        assert!(sdf.evaluate(Point::new(0., 0.3, 0.)) < 0.);
+    }
+
+    #[test]
+    fn sharp_union() {
+        let mut a = Sdf::sphere(1.);
+        a.set_transform(Matrix::translation(-1., 0., 0.));
+        let mut b = Sdf::sphere(1.);
+        b.set_transform(Matrix::translation(1.5, 0., 0.));
+        let sdf = a.smooth_union(b, 0.);
+        let value = sdf.evaluate(Point::new(0.25, 0., 0.));
+        assert!(value.is_finite());
+        assert!(float_eq(value, 0.25));
+        assert!(float_eq(sdf.evaluate(Point::new(2.5, 0., 0.)), 0.));
+    }
+
+    #[test]
+    fn smooth_union_moving() {
+        let a = Sdf::sphere(1.);
+        let mut b = Sdf::sphere(1.);
+        let end = Matrix::translation(5., 0., 0.) * Matrix::rotation_y(PI / 2.);
+        b.set_motion(Motion::new(Matrix::identity(), end));
+        let sdf = a.smooth_union(b, 0.1);
+        let ray = Ray::new(Point::new(5., 0., -5.), Vector::new(0., 0., 1.));
+        assert!(sdf.intersect(&ray).is_empty());
+        let ray = ray.with_time(1.);
+        let xs = sdf.intersect(&ray);
+        assert!((xs[0].t - 4.).abs() < 1e-5);
+        assert_eq!(xs[0].normal, Vector::new(0., 0., -1.));
+        assert_eq!(sdf.normal_at(ray.position(xs[0].t), 1.), xs[0].normal);
+    }
+}
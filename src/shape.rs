@@ -0,0 +1,257 @@
+use std::fmt::Debug;
+
+use crate::{Attributes, Bounds, Intersection, Material, Matrix, Motion, Point, Ray, Vector};
+
+/// Geometric object that can be intersected by a [`Ray`].
+///
+/// Implementors work in *object space*: `local_intersect` and
+/// `local_normal_at` receive rays and points already transformed by the
+/// inverse of the shape transform. The provided `intersect` and `normal_at`
+/// methods handle the conversion from and to *world space*.
+///
+/// Moving shapes have a [`Motion`], their transform is then the transform at
+/// time `0` and rays are intersected with the shape at their own time, as
+/// normals are computed at a given time.
+pub trait Shape: Debug {
+    /// Returns the transform, motion and material of the shape.
+    fn attributes(&self) -> &Attributes;
+
+    /// Returns the transform, motion and material of the shape for updating
+    /// them.
+    fn attributes_mut(&mut self) -> &mut Attributes;
+
+    /// Returns the object to world transform of the shape.
+    fn transform(&self) -> &Matrix {
+        &self.attributes().transform
+    }
+
+    /// Returns the world to object transform of the shape (inverse of
+    /// [`Shape::transform`]).
+    fn inverse(&self) -> &Matrix {
+        &self.attributes().inverse
+    }
+
+    /// Returns the motion of the shape, `None` if it does not move.
+    fn motion(&self) -> Option<&Motion> {
+        self.attributes().motion.as_ref()
+    }
+
+    /// Returns the material of the shape.
+    fn material(&self) -> &Material {
+        &self.attributes().material
+    }
+
+    /// Sets the object to world transform of the shape, which stops moving.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the transform is not invertible.
+    fn set_transform(&mut self, transform: Matrix) {
+        let attributes = self.attributes_mut();
+        attributes.inverse = transform.inverse().expect("shape transform must be invertible");
+        attributes.transform = transform;
+        attributes.motion = None;
+    }
+
+    /// Sets the motion of the shape, its transform becoming the transform at
+    /// the opening of the shutter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a transform of the motion is not invertible.
+    fn set_motion(&mut self, motion: Motion) {
+        self.set_transform(*motion.start());
+        self.attributes_mut().motion = Some(motion);
+    }
+
+    /// Sets the material of the shape.
+    fn set_material(&mut self, material: Material) {
+        self.attributes_mut().material = material;
+    }
+
+    /// Returns the object space bounding box of the shape, infinite for
+    /// unbounded shapes (the default).
+    fn bounds(&self) -> Bounds {
+        Bounds::infinite()
+    }
+
+    /// Returns the bounding box of the shape in the space of its parent
+    /// (world space for shapes outside groups), infinite for moving shapes.
+    fn parent_bounds(&self) -> Bounds {
+        match self.motion() {
+            Some(_) => Bounds::infinite(),
+            None => self.bounds().transform(self.transform()),
+        }
+    }
+
+    /// Returns the intersections of an object space ray with the shape.
+    ///
+    /// Normals of returned intersections are expressed in object space.
+    fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>>;
+
+    /// Returns the object space normal of the shape at specified object space
+    /// point and time (only used by shapes made of moving shapes).
+    fn local_normal_at(&self, point: Point, time: f64) -> Vector;
+
+    /// Returns the world to object transform of the shape at specified time,
+    /// the cached [`Shape::inverse`] for shapes that do not move.
+    fn inverse_at(&self, time: f64) -> Matrix {
+        match self.motion() {
+            Some(motion) => motion
+                .transform_at(time)
+                .inverse()
+                .expect("shape transform must be invertible"),
+            None => *self.inverse(),
+        }
+    }
+
+    /// Returns the intersections of a world space ray with the shape.
+    ///
+    /// Normals of returned intersections are expressed in world space.
+    fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let inverse = self.inverse_at(ray.time);
+        let mut intersections = self.local_intersect(&ray.transform(inverse));
+        let transpose = inverse.transpose();
+        for intersection in intersections.iter_mut() {
+            intersection.normal = (transpose * intersection.normal).normalized();
+        }
+        intersections
+    }
+
+    /// Returns the world space normal of the shape at specified world space
+    /// point and time.
+    fn normal_at(&self, point: Point, time: f64) -> Vector {
+        let inverse = self.inverse_at(time);
+        let normal = self.local_normal_at(inverse * point, time);
+        (inverse.transpose() * normal).normalized()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, f64::consts::PI};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestShape {
+        attributes: Attributes,
+        ray: Cell<Option<Ray>>,
+    }
+
+    impl TestShape {
+        fn new(transform: Matrix) -> Self {
+            let mut shape = Self {
+                attributes: Attributes::default(),
+                ray: Cell::new(None),
+            };
+            shape.set_transform(transform);
+            shape
+        }
+    }
+
+    impl Shape for TestShape {
+        fn attributes(&self) -> &Attributes {
+            &self.attributes
+        }
+
+        fn attributes_mut(&mut self) -> &mut Attributes {
+            &mut self.attributes
+        }
+
+        fn local_intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+            self.ray.set(Some(*ray));
+            vec![Intersection::new(1., self, Vector::new(0., 0., -1.))]
+        }
+
+        fn local_normal_at(&self, point: Point, _time: f64) -> Vector {
+            Vector::new(point.x, point.y, point.z)
+        }
+    }
+
+    #[test]
+    fn intersect_scaled() {
+        let shape = TestShape::new(Matrix::scaling(2., 2., 2.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        shape.intersect(&ray);
+        let local = shape.ray.get().unwrap();
+        assert_eq!(local.origin, Point::new(0., 0., -2.5));
+        assert_eq!(local.direction, Vector::new(0., 0., 0.5));
+    }
+
+    #[test]
+    fn intersect_translated() {
+        let shape = TestShape::new(Matrix::translation(5., 0., 0.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        shape.intersect(&ray);
+        let local = shape.ray.get().unwrap();
+        assert_eq!(local.origin, Point::new(-5., 0., -5.));
+        assert_eq!(local.direction, Vector::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn intersect_normal() {
+        let shape = TestShape::new(Matrix::scaling(1., 0.5, 1.) * Matrix::rotation_x(PI / 4.));
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = shape.intersect(&ray);
+        let expected = shape.inverse().transpose() * Vector::new(0., 0., -1.);
+        assert_eq!(xs[0].normal, expected.normalized());
+    }
+
+    #[test]
+    fn inverse_static() {
+        // the cached inverse is returned as is, even when it is stale
+        let mut shape = TestShape::new(Matrix::translation(5., 0., 0.));
+        shape.attributes.inverse = Matrix::scaling(2., 2., 2.);
+        assert_eq!(shape.inverse_at(0.7), Matrix::scaling(2., 2., 2.));
+    }
+
+    #[test]
+    fn attributes() {
+        let mut shape = TestShape::new(Matrix::identity());
+        assert_eq!(shape.attributes(), &Attributes::default());
+        shape.set_transform(Matrix::translation(2., 3., 4.));
+        assert_eq!(shape.transform(), &Matrix::translation(2., 3., 4.));
+        assert_eq!(shape.inverse(), &Matrix::translation(-2., -3., -4.));
+        let motion = Motion::new(Matrix::scaling(2., 2., 2.), Matrix::identity());
+        shape.set_motion(motion);
+        assert_eq!(shape.transform(), &Matrix::scaling(2., 2., 2.));
+        assert_eq!(shape.motion(), Some(&motion));
+        shape.set_transform(Matrix::identity());
+        assert_eq!(shape.motion(), None);
+        let material = Material {
+            ambient: 1.,
+            ..Material::default()
+        };
+        shape.set_material(material.clone());
+        assert_eq!(shape.material(), &material);
+    }
+
+    #[test]
+    #[should_panic(expected = "shape transform must be invertible")]
+    fn singular_transform() {
+        TestShape::new(Matrix::scaling(0., 1., 1.));
+    }
+
+    #[test]
+    fn parent_bounds() {
+        let shape = TestShape::new(Matrix::translation(1., 0., 0.));
+        assert_eq!(shape.bounds(), Bounds::infinite());
+        assert_eq!(shape.parent_bounds(), Bounds::infinite());
+    }
+
+    #[test]
+    fn normal_translated() {
+        let shape = TestShape::new(Matrix::translation(0., 1., 0.));
+        let value = 2f64.sqrt() / 2.;
+        let normal = shape.normal_at(Point::new(0., 1. + value, -value), 0.);
+        assert_eq!(normal, Vector::new(0., value, -value));
+    }
+
+    #[test]
+    fn normal_transformed() {
+        let shape = TestShape::new(Matrix::scaling(1., 0.5, 1.) * Matrix::rotation_z(PI / 5.));
+        let normal = shape.normal_at(Point::new(0., 2f64.sqrt() / 2., -(2f64.sqrt()) / 2.), 0.);
+        assert_eq!(normal, Vector::new(0., 0.97014, -0.24254));
+    }
+}
@@ -0,0 +1,457 @@
+use crate::{
+    path_tracer::{reached, Scattering, Vertex},
+    spectrum::{self, Observer},
+    util::Rng,
+    Bsdf, Camera, Canvas, Color, Computations, Heuristic, Intersection, Light, Point, Ray, Vector, World,
+};
+
+/// Monte Carlo path tracing integrator carrying a single wavelength per path.
+///
+/// Each path samples a wavelength of the visible spectrum (stratified over
+/// the samples of a pixel and favoring those the eye is most sensitive to),
+/// the colors of the world are upsampled to smooth
+/// spectra and evaluated at that wavelength. The radiance of the paths is
+/// accumulated against the CIE color matching functions and converted from
+/// XYZ to colors, flat spectra giving gray colors.
+///
+/// Surfaces with a [`Dielectric`](crate::Dielectric) reflect or refract the
+/// light depending on their Fresnel reflectance at the wavelength, rendering
+/// dispersion and thin-film interference. Other surfaces scatter light
+/// according to their [`Bsdf`] with next event estimation and multiple
+/// importance sampling as in the [`PathTracer`](crate::PathTracer).
+///
+/// Participating media are not supported: the shapes bounding a
+/// [`Medium`](crate::Medium) are rendered as surfaces.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpectralPathTracer {
+    samples: usize,
+    max_depth: usize,
+    roulette_depth: usize,
+    heuristic: Heuristic,
+    seed: u64,
+}
+
+impl SpectralPathTracer {
+    const SAMPLES: usize = 16;
+    const MAX_DEPTH: usize = 16;
+    const ROULETTE_DEPTH: usize = 3;
+
+    /// Creates a `SpectralPathTracer` with default settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Heuristic, SpectralPathTracer};
+    /// let tracer = SpectralPathTracer::new();
+    /// assert_eq!(tracer.samples_per_pixel(), 16);
+    /// assert_eq!(tracer.max_depth(), 16);
+    /// assert_eq!(tracer.roulette_depth(), 3);
+    /// assert_eq!(tracer.heuristic(), Heuristic::Power);
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            samples: Self::SAMPLES,
+            max_depth: Self::MAX_DEPTH,
+            roulette_depth: Self::ROULETTE_DEPTH,
+            heuristic: Heuristic::default(),
+            seed: 0,
+        }
+    }
+
+    /// Get the number of paths (and wavelengths) traced per pixel.
+    #[must_use]
+    pub fn samples_per_pixel(&self) -> usize {
+        self.samples
+    }
+
+    /// Sets the number of paths (and wavelengths) traced per pixel.
+    pub fn set_samples_per_pixel(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    /// Get the maximum number of bounces of a path.
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Sets the maximum number of bounces of a path.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Get the number of bounces after which paths are subject to Russian
+    /// roulette.
+    #[must_use]
+    pub fn roulette_depth(&self) -> usize {
+        self.roulette_depth
+    }
+
+    /// Sets the number of bounces after which paths are subject to Russian
+    /// roulette.
+    pub fn set_roulette_depth(&mut self, roulette_depth: usize) {
+        self.roulette_depth = roulette_depth;
+    }
+
+    /// Get the heuristic weighting light and BSDF samples.
+    #[must_use]
+    pub fn heuristic(&self) -> Heuristic {
+        self.heuristic
+    }
+
+    /// Sets the heuristic weighting light and BSDF samples.
+    pub fn set_heuristic(&mut self, heuristic: Heuristic) {
+        self.heuristic = heuristic;
+    }
+
+    /// Get the seed of the random sequences.
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets the seed of the random sequences, renders with the same seed are
+    /// identical.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Renders the world seen by the camera.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Canvas, Color, Environment, SpectralPathTracer, World};
+    /// let mut world = World::new();
+    /// world.set_environment(Environment::new(Canvas::with_color(8, 4, Color::white())));
+    /// let mut tracer = SpectralPathTracer::new();
+    /// tracer.set_samples_per_pixel(256);
+    /// let image = tracer.render(&world, &Camera::new(2, 2, 1.));
+    /// let color = image.get(0, 0);
+    /// assert!((color.r - 1.).abs() < 0.02 && (color.b - 1.).abs() < 0.02);
+    /// ```
+    pub fn render(&self, world: &World, camera: &Camera) -> Canvas {
+        let mut image = Canvas::new(camera.hsize(), camera.vsize());
+        let lights: Vec<&dyn Light> = world.light_sources().collect();
+        let observer = Observer::new();
+        for y in 0..camera.vsize() {
+            for x in 0..camera.hsize() {
+                let mut rng = Rng::from_values(&[x as f64, y as f64, self.seed as f64]);
+                let mut sum = (0., 0., 0.);
+                for i in 0..self.samples {
+                    let u = (i as f64 + rng.next_f64()) / self.samples as f64;
+                    let (wavelength, pdf) = spectrum::sample_wavelength(u);
+                    let ray = camera.sample_ray(x, y, &mut rng);
+                    let radiance = self.trace(world, &lights, &ray, wavelength, &mut rng) / pdf;
+                    let (cx, cy, cz) = spectrum::xyz(wavelength);
+                    sum = (sum.0 + radiance * cx, sum.1 + radiance * cy, sum.2 + radiance * cz);
+                }
+                let samples = self.samples as f64;
+                *image.get_mut(x, y) = observer.color((sum.0 / samples, sum.1 / samples, sum.2 / samples));
+            }
+        }
+        image
+    }
+
+    /// Returns the spectral radiance at a wavelength carried along a ray
+    /// estimated by a single path.
+    fn trace(&self, world: &World, lights: &[&dyn Light], ray: &Ray, wavelength: f64, rng: &mut Rng) -> f64 {
+        let spectral = |color: Color| spectrum::upsample(color, wavelength);
+        let mut radiance = 0.;
+        let mut throughput = 1.;
+        let mut ray = *ray;
+        let mut vertex: Option<Vertex> = None;
+        for depth in 0..self.max_depth {
+            let intersections = world.intersect(&ray);
+            let hit = Intersection::hit(&intersections);
+            match &vertex {
+                Some(vertex) => {
+                    let light = reached(self.heuristic, world, lights, vertex, &ray, None, rng);
+                    radiance += throughput * spectral(light);
+                }
+                None if hit.is_none() => radiance += throughput * spectral(world.background(ray.direction)),
+                None => {}
+            }
+            let hit = match hit {
+                Some(hit) => hit,
+                None => break,
+            };
+            let comps = Computations::new(&hit, &ray);
+            let material = comps.object.material();
+            radiance += throughput * spectral(material.emission);
+            if let Some(dielectric) = &material.dielectric {
+                if !self.survives(depth, &mut throughput, rng) {
+                    break;
+                }
+                let index = dielectric.refractive_index(wavelength);
+                let (n1, n2) = if comps.inside { (index, 1.) } else { (1., index) };
+                let reflectance = dielectric.reflectance(comps.eyev.dot(comps.normalv), n1, n2, wavelength);
+                // choosing reflection or refraction by the reflectance
+                // leaves the throughput unchanged
+                let incident = -comps.eyev;
+                ray = match refract(incident, comps.normalv, n1 / n2) {
+                    Some(direction) if rng.next_f64() >= reflectance => Ray::new(comps.under_point, direction),
+                    _ => Ray::new(comps.over_point, incident.reflect(comps.normalv)),
+                }
+                .with_time(ray.time);
+                // the lights cannot be sampled through a specular bounce,
+                // the lights reached by the next segment get the full weight
+                vertex = Some(Vertex {
+                    point: ray.origin,
+                    pdf: 1.,
+                    counts: vec![0; lights.len()],
+                });
+                continue;
+            }
+            let bsdf = Bsdf::new(material);
+            if bsdf.is_black() {
+                break;
+            }
+            let (normal, wo) = (comps.normalv, comps.eyev);
+            let scattering = Scattering::Surface { bsdf, normal, wo };
+            let (direct, counts) = self.direct(world, lights, comps.over_point, ray.time, &scattering, wavelength);
+            radiance += throughput * direct;
+            if !self.survives(depth, &mut throughput, rng) {
+                break;
+            }
+            let wi = match scattering.sample(rng) {
+                Some(wi) => wi,
+                None => break,
+            };
+            let pdf = scattering.pdf(wi);
+            if pdf <= 0. {
+                break;
+            }
+            throughput *= spectral(scattering.eval(wi)) / pdf;
+            vertex = Some(Vertex {
+                point: comps.over_point,
+                pdf,
+                counts,
+            });
+            ray = Ray::new(comps.over_point, wi).with_time(ray.time);
+        }
+        radiance
+    }
+
+    /// Returns the spectral radiance at a wavelength scattered at a point at
+    /// specified time estimated from the samples of the lights, and the
+    /// number of samples of each light.
+    fn direct(
+        &self,
+        world: &World,
+        lights: &[&dyn Light],
+        point: Point,
+        time: f64,
+        scattering: &Scattering,
+        wavelength: f64,
+    ) -> (f64, Vec<usize>) {
+        let mut radiance = 0.;
+        let mut counts = vec![];
+        for light in lights {
+            let samples = light.samples(point);
+            counts.push(samples.len());
+            if samples.is_empty() {
+                continue;
+            }
+            let mut sum = 0.;
+            for sample in samples.iter() {
+                let f = spectrum::upsample(scattering.eval(sample.direction), wavelength);
+                let shadow = Ray::new(point, sample.direction).with_time(time);
+                if f <= 0. || world.is_occluded(&shadow, sample.distance) {
+                    continue;
+                }
+                let light_pdf = samples.len() as f64 * sample.pdf;
+                let weight = self.heuristic.weight(light_pdf, scattering.pdf(sample.direction));
+                sum += f * spectrum::upsample(sample.intensity, wavelength) * weight;
+            }
+            radiance += sum / samples.len() as f64;
+        }
+        (radiance, counts)
+    }
+
+    /// Returns `false` if a path is terminated by Russian roulette at
+    /// specified depth, the throughput of surviving paths is compensated.
+    fn survives(&self, depth: usize, throughput: &mut f64, rng: &mut Rng) -> bool {
+        if depth + 1 < self.roulette_depth {
+            return true;
+        }
+        let survival = throughput.min(0.95);
+        if rng.next_f64() >= survival {
+            return false;
+        }
+        *throughput /= survival;
+        true
+    }
+}
+
+impl Default for SpectralPathTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the direction of the light refracted through a surface of
+/// specified normal (on the side of the incident light), `eta` being the
+/// ratio of the refractive indices of both sides (`None` for total internal
+/// reflection).
+fn refract(incident: Vector, normal: Vector, eta: f64) -> Option<Vector> {
+    let cos_i = -incident.dot(normal);
+    let sin2_t = eta * eta * (1. - cos_i * cos_i);
+    if sin2_t > 1. {
+        return None;
+    }
+    Some(incident * eta + normal * (eta * cos_i - (1. - sin2_t).sqrt()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{
+        spectrum::{MAX_WAVELENGTH, MIN_WAVELENGTH},
+        util::float_eq,
+        Dielectric, Dispersion, Environment, Material, Matrix, PointLight, Quadric, Shape,
+    };
+
+    use super::*;
+
+    fn camera() -> Camera {
+        let mut camera = Camera::new(1, 1, 0.01);
+        let from = Point::new(0., 5., 0.);
+        let to = Point::new(0., 0., 0.);
+        let up = Vector::new(0., 0., 1.);
+        camera.set_transform(Matrix::view_transform(from, to, up));
+        camera
+    }
+
+    fn diffuse(color: Color, emission: Color) -> Material {
+        Material {
+            color,
+            diffuse: 1.,
+            specular: 0.,
+            emission,
+            ..Material::default()
+        }
+    }
+
+    fn close(color: &Color, expected: Color, tolerance: f64) -> bool {
+        (color.r - expected.r).abs() < tolerance
+            && (color.g - expected.g).abs() < tolerance
+            && (color.b - expected.b).abs() < tolerance
+    }
+
+    #[test]
+    fn settings() {
+        let mut tracer = SpectralPathTracer::default();
+        tracer.set_samples_per_pixel(0);
+        assert_eq!(tracer.samples_per_pixel(), 1);
+        tracer.set_max_depth(4);
+        assert_eq!(tracer.max_depth(), 4);
+        tracer.set_roulette_depth(2);
+        assert_eq!(tracer.roulette_depth(), 2);
+        tracer.set_heuristic(Heuristic::Balance);
+        assert_eq!(tracer.heuristic(), Heuristic::Balance);
+        tracer.set_seed(7);
+        assert_eq!(tracer.seed(), 7);
+    }
+
+    #[test]
+    fn refract() {
+        let normal = Vector::new(0., 1., 0.);
+        let incident = Vector::new(1., -1., 0.).normalized();
+        let refracted = super::refract(incident, normal, 1. / 1.5).unwrap();
+        assert!(float_eq(refracted.len(), 1.));
+        // Snell's law
+        assert!(float_eq(refracted.x, incident.x / 1.5));
+        assert!(refracted.y < 0.);
+        assert_eq!(super::refract(incident, normal, 1.5), None);
+    }
+
+    #[test]
+    fn furnace() {
+        // inside an emitting gray sphere of albedo a: L = E / (1 - a)
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::scaling(10., 10., 10.));
+        sphere.set_material(diffuse(Color::new(0.5, 0.5, 0.5), Color::white()));
+        world.add_object(sphere);
+        let mut tracer = SpectralPathTracer::new();
+        tracer.set_max_depth(32);
+        tracer.set_roulette_depth(32);
+        tracer.set_samples_per_pixel(256);
+        let image = tracer.render(&world, &camera());
+        assert!(close(image.get(0, 0), Color::new(2., 2., 2.), 0.05));
+    }
+
+    #[test]
+    fn next_event_estimation() {
+        // Lambertian plane lit from above: L = albedo / π * I * cos θ
+        let mut world = World::new();
+        let mut plane = Quadric::plane();
+        plane.set_material(diffuse(Color::new(0.5, 0.5, 0.5), Color::black()));
+        world.add_object(plane);
+        world.add_light(PointLight::new(Point::new(0., 10., 0.), Color::white()));
+        let mut tracer = SpectralPathTracer::new();
+        tracer.set_samples_per_pixel(256);
+        let image = tracer.render(&world, &camera());
+        assert!(close(image.get(0, 0), Color::white() * (0.5 / PI), 0.002));
+        // colored surfaces keep their hue
+        let mut world = World::new();
+        let mut plane = Quadric::plane();
+        plane.set_material(diffuse(Color::new(0.8, 0.4, 0.1), Color::black()));
+        world.add_object(plane);
+        world.add_light(PointLight::new(Point::new(0., 10., 0.), Color::white()));
+        let color = *tracer.render(&world, &camera()).get(0, 0);
+        assert!(color.r > color.g && color.g > color.b);
+    }
+
+    #[test]
+    fn glass() {
+        // all the light reflected or refracted by a glass sphere in a
+        // uniform sky comes from the sky
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(Material::dielectric(Dielectric::new(Dispersion::SF11)));
+        world.add_object(sphere);
+        world.set_environment(Environment::new(Canvas::with_color(16, 8, Color::white())));
+        let mut tracer = SpectralPathTracer::new();
+        tracer.set_max_depth(64);
+        tracer.set_roulette_depth(64);
+        tracer.set_samples_per_pixel(64);
+        let image = tracer.render(&world, &camera());
+        assert!(close(image.get(0, 0), Color::white(), 0.02));
+    }
+
+    #[test]
+    fn thin_film() {
+        // coated surface reflecting a white sky: the color of the
+        // reflectance spectrum at normal incidence
+        let mut world = World::new();
+        let mut sky = Canvas::new(8, 4);
+        for x in 0..8 {
+            *sky.get_mut(x, 0) = Color::white();
+            *sky.get_mut(x, 1) = Color::white();
+        }
+        world.set_environment(Environment::new(sky));
+        let coated = Dielectric::coated(Dispersion::constant(1.5), 250., 2.2);
+        let mut plane = Quadric::plane();
+        plane.set_material(Material::dielectric(coated));
+        world.add_object(plane);
+        let mut tracer = SpectralPathTracer::new();
+        tracer.set_samples_per_pixel(4096);
+        let image = tracer.render(&world, &camera());
+        let steps = 400;
+        let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / steps as f64;
+        let mut sum = (0., 0., 0.);
+        for i in 0..steps {
+            let wavelength = MIN_WAVELENGTH + (i as f64 + 0.5) * step;
+            let (x, y, z) = spectrum::xyz(wavelength);
+            let value = coated.reflectance(1., 1., 1.5, wavelength) * step;
+            sum = (sum.0 + x * value, sum.1 + y * value, sum.2 + z * value);
+        }
+        let expected = Observer::new().color(sum);
+        assert!(close(image.get(0, 0), expected, 0.02));
+        // interference colors the reflection
+        assert!((expected.r - expected.b).abs() > 0.1);
+    }
+}
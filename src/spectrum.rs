@@ -0,0 +1,168 @@
+use crate::Color;
+
+/// Shortest visible wavelength in nanometers.
+pub(crate) const MIN_WAVELENGTH: f64 = 380.;
+/// Longest visible wavelength in nanometers.
+pub(crate) const MAX_WAVELENGTH: f64 = 780.;
+
+/// Returns the value at a wavelength (in nanometers) of the smooth spectrum
+/// of a color.
+///
+/// The spectrum mixes three smooth basis functions (blue below 495 nm, red
+/// above 590 nm and green in between) summing to one, white and gray colors
+/// are flat spectra and reflectances in `[0,1]` stay in `[0,1]`.
+pub(crate) fn upsample(color: Color, wavelength: f64) -> f64 {
+    let smoothstep = |from: f64, to: f64| {
+        let t = ((wavelength - from) / (to - from)).clamp(0., 1.);
+        t * t * (3. - 2. * t)
+    };
+    let blue = 1. - smoothstep(480., 510.);
+    let red = smoothstep(575., 605.);
+    let green = 1. - blue - red;
+    color.r * red + color.g * green + color.b * blue
+}
+
+/// Returns a wavelength (in nanometers) of the visible spectrum sampled from
+/// a uniform number in `[0,1)` and its probability density.
+///
+/// The density (after Radziszewski et al.) is proportional to
+/// `1 / cosh²(0.0072 (λ - 538))`, roughly following the sensitivity of the
+/// eye to reduce the color noise.
+pub(crate) fn sample_wavelength(u: f64) -> (f64, f64) {
+    const CENTER: f64 = 538.;
+    const SCALE: f64 = 0.0072;
+    let cdf = |wavelength: f64| (SCALE * (wavelength - CENTER)).tanh();
+    let (min, max) = (cdf(MIN_WAVELENGTH), cdf(MAX_WAVELENGTH));
+    let wavelength = CENTER + (min + u * (max - min)).atanh() / SCALE;
+    let pdf = SCALE / (SCALE * (wavelength - CENTER)).cosh().powi(2) / (max - min);
+    (wavelength.clamp(MIN_WAVELENGTH, MAX_WAVELENGTH), pdf)
+}
+
+/// Returns the CIE 1931 color matching functions at a wavelength (in
+/// nanometers).
+///
+/// Multi-lobe fit of Wyman et al., simple analytic approximations to the CIE
+/// XYZ color matching functions.
+pub(crate) fn xyz(wavelength: f64) -> (f64, f64, f64) {
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let t = (wavelength - mean) / if wavelength < mean { below } else { above };
+        (-0.5 * t * t).exp()
+    };
+    let x = 1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7) - 0.065 * lobe(501.1, 20.4, 26.2);
+    let y = 0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1);
+    let z = 1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8);
+    (x, y, z)
+}
+
+/// Returns the linear sRGB color of CIE XYZ tristimulus values.
+fn xyz_to_rgb((x, y, z): (f64, f64, f64)) -> Color {
+    Color::new(
+        3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+        -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+        0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+    )
+}
+
+/// Conversion of spectra integrated against the color matching functions to
+/// colors, balanced so that flat spectra are gray.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Observer {
+    white: Color,
+}
+
+impl Observer {
+    const STEPS: usize = 400;
+
+    /// Creates an `Observer` by integrating the color matching functions
+    /// over the visible wavelengths.
+    pub(crate) fn new() -> Self {
+        let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / Self::STEPS as f64;
+        let mut sum = (0., 0., 0.);
+        for i in 0..Self::STEPS {
+            let (x, y, z) = xyz(MIN_WAVELENGTH + (i as f64 + 0.5) * step);
+            sum = (sum.0 + x * step, sum.1 + y * step, sum.2 + z * step);
+        }
+        Self { white: xyz_to_rgb(sum) }
+    }
+
+    /// Returns the color of a spectrum from its integrals against the color
+    /// matching functions.
+    pub(crate) fn color(&self, xyz: (f64, f64, f64)) -> Color {
+        let rgb = xyz_to_rgb(xyz);
+        Color::new(rgb.r / self.white.r, rgb.g / self.white.g, rgb.b / self.white.b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color_of(spectrum: impl Fn(f64) -> f64) -> Color {
+        let observer = Observer::new();
+        let steps = 400;
+        let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / steps as f64;
+        let mut sum = (0., 0., 0.);
+        for i in 0..steps {
+            let wavelength = MIN_WAVELENGTH + (i as f64 + 0.5) * step;
+            let (x, y, z) = xyz(wavelength);
+            let value = spectrum(wavelength) * step;
+            sum = (sum.0 + x * value, sum.1 + y * value, sum.2 + z * value);
+        }
+        observer.color(sum)
+    }
+
+    #[test]
+    fn upsample_gray() {
+        for wavelength in [380., 450., 500., 550., 600., 700.] {
+            assert!((upsample(Color::white(), wavelength) - 1.).abs() < 1e-12);
+            assert!((upsample(Color::new(0.3, 0.3, 0.3), wavelength) - 0.3).abs() < 1e-12);
+            let value = upsample(Color::new(0.9, 0.1, 0.5), wavelength);
+            assert!((0.1..=0.9).contains(&value));
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(color_of(|_| 1.), Color::white());
+        assert_eq!(color_of(|_| 0.25), Color::new(0.25, 0.25, 0.25));
+        // saturated colors keep their dominant channel
+        for (color, channel) in [(Color::red(), 0), (Color::green(), 1), (Color::blue(), 2)] {
+            let back = color_of(|wavelength| upsample(color, wavelength));
+            let channels = [back.r, back.g, back.b];
+            assert!(channels[channel] > 0.7);
+            for (i, value) in channels.iter().enumerate() {
+                if i != channel {
+                    assert!(*value < channels[channel] * 0.5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sample_wavelength() {
+        let (min, _) = super::sample_wavelength(0.);
+        let (max, _) = super::sample_wavelength(1.);
+        assert!((min - MIN_WAVELENGTH).abs() < 1e-9);
+        assert!((max - MAX_WAVELENGTH).abs() < 1e-6);
+        // the density is the derivative of the sampled cumulative distribution
+        let h = 1e-6;
+        for u in [0.1, 0.3, 0.5, 0.7, 0.9] {
+            let (wavelength, pdf) = super::sample_wavelength(u);
+            assert!((MIN_WAVELENGTH..=MAX_WAVELENGTH).contains(&wavelength));
+            let slope = (super::sample_wavelength(u + h).0 - super::sample_wavelength(u - h).0) / (2. * h);
+            assert!((slope * pdf - 1.).abs() < 1e-6);
+        }
+        // the eye is most sensitive in the green
+        assert!(super::sample_wavelength(0.5).1 > 2. * super::sample_wavelength(0.).1);
+    }
+
+    #[test]
+    fn xyz_peaks() {
+        // luminance peaks in the green, z in the blue
+        let (_, y555, _) = xyz(555.);
+        let (_, y450, z450) = xyz(450.);
+        assert!((y555 - 1.).abs() < 0.02);
+        assert!(y450 < 0.1);
+        assert!(z450 > 1.5);
+    }
+}
@@ -0,0 +1,235 @@
+use std::f64::consts::PI;
+
+use crate::{Attenuation, Color, Emitter, Light, LightSample, Point, Vector};
+
+/// Point light emitting within a cone.
+///
+/// The intensity is full within the inner cone and smoothly falls off to
+/// zero at the outer cone.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpotLight {
+    /// position of the light
+    pub position: Point,
+    /// axis of the cone (direction the light points to)
+    pub direction: Vector,
+    /// half angle (radians) of the cone of full intensity
+    pub inner_angle: f64,
+    /// half angle (radians) of the cone beyond which no light is emitted
+    pub outer_angle: f64,
+    /// intensity of the light
+    pub intensity: Color,
+    /// attenuation of the intensity with distance
+    pub attenuation: Attenuation,
+}
+
+impl SpotLight {
+    /// Creates a `SpotLight` from specified position, direction (normalized),
+    /// cone angles and intensity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the direction is zero or if the angles do not satisfy
+    /// `0 <= inner_angle <= outer_angle <= π`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::f64::consts::PI;
+    /// # use ray_tracer_challenge::{Color, Point, SpotLight, Vector};
+    /// let position = Point::new(0., 10., 0.);
+    /// let direction = Vector::new(0., -1., 0.);
+    /// let light = SpotLight::new(position, direction, PI / 8., PI / 6., Color::white());
+    /// assert_eq!(light.falloff(Point::new(0., 0., 0.)), 1.);
+    /// assert_eq!(light.falloff(Point::new(10., 0., 0.)), 0.);
+    /// ```
+    pub fn new(position: Point, direction: Vector, inner_angle: f64, outer_angle: f64, intensity: Color) -> Self {
+        Self::check(direction, inner_angle, outer_angle);
+        Self {
+            position,
+            direction: direction.normalized(),
+            inner_angle,
+            outer_angle,
+            intensity,
+            attenuation: Attenuation::none(),
+        }
+    }
+
+    /// Creates a physically based `SpotLight` from its radiant power (watts).
+    ///
+    /// The power is spread over the solid angle of the cone midway between
+    /// the inner and outer cones giving the radiant intensity (watts per
+    /// steradian), the attenuation is inverse-square.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the direction is zero or if the angles do not satisfy
+    /// `0 <= inner_angle <= outer_angle <= π`.
+    pub fn from_power(
+        position: Point,
+        direction: Vector,
+        inner_angle: f64,
+        outer_angle: f64,
+        color: Color,
+        power: f64,
+    ) -> Self {
+        Self::check(direction, inner_angle, outer_angle);
+        let solid_angle = 2. * PI * (1. - 0.5 * (inner_angle.cos() + outer_angle.cos()));
+        Self {
+            position,
+            direction: direction.normalized(),
+            inner_angle,
+            outer_angle,
+            intensity: color * (power / solid_angle),
+            attenuation: Attenuation::inverse_square(),
+        }
+    }
+
+    fn check(direction: Vector, inner_angle: f64, outer_angle: f64) {
+        assert!(direction.len() > 0., "spot light direction must not be zero");
+        assert!(
+            0. <= inner_angle && inner_angle <= outer_angle && outer_angle <= PI,
+            "spot light angles must satisfy 0 <= inner <= outer <= π"
+        );
+    }
+
+    /// Returns the fraction (in `[0,1]`) of the intensity emitted towards
+    /// specified point.
+    pub fn falloff(&self, point: Point) -> f64 {
+        let cos = (point - self.position).normalized().dot(self.direction.normalized());
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+        if cos >= cos_inner {
+            return 1.;
+        }
+        if cos <= cos_outer {
+            return 0.;
+        }
+        let x = (cos - cos_outer) / (cos_inner - cos_outer);
+        x * x * (3. - 2. * x)
+    }
+}
+
+impl Light for SpotLight {
+    fn samples(&self, point: Point) -> Vec<LightSample> {
+        let mut sample = LightSample::at(self.position, point, self.intensity * self.falloff(point));
+        sample.intensity *= self.attenuation.factor(sample.distance);
+        vec![sample]
+    }
+
+    fn emitter(&self) -> Option<Emitter> {
+        (self.attenuation == Attenuation::inverse_square()).then(|| Emitter::spot(*self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::float_eq;
+
+    use super::*;
+
+    fn light() -> SpotLight {
+        SpotLight::new(
+            Point::new(0., 10., 0.),
+            Vector::new(0., -2., 0.),
+            PI / 8.,
+            PI / 4.,
+            Color::white(),
+        )
+    }
+
+    #[test]
+    fn new() {
+        // the direction is normalized
+        assert_eq!(light().direction, Vector::new(0., -1., 0.));
+        let light = SpotLight::new(Point::new(0., 0., 0.), Vector::new(1., 1., 0.), 0., PI, Color::white());
+        assert_eq!(light.direction, Vector::new(1., 1., 0.).normalized());
+    }
+
+    #[test]
+    #[should_panic(expected = "spot light angles must satisfy 0 <= inner <= outer <= π")]
+    fn inverted_angles() {
+        SpotLight::new(
+            Point::new(0., 0., 0.),
+            Vector::new(0., -1., 0.),
+            PI / 4.,
+            PI / 8.,
+            Color::white(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "spot light angles must satisfy 0 <= inner <= outer <= π")]
+    fn wide_angle() {
+        SpotLight::from_power(
+            Point::new(0., 0., 0.),
+            Vector::new(0., -1., 0.),
+            0.,
+            4.,
+            Color::white(),
+            1.,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "spot light direction must not be zero")]
+    fn zero_direction() {
+        SpotLight::new(Point::new(0., 0., 0.), Vector::new(0., 0., 0.), 0., 1., Color::white());
+    }
+
+    #[test]
+    fn falloff() {
+        let light = light();
+        assert!(float_eq(light.falloff(Point::new(0., 0., 0.)), 1.));
+        assert!(float_eq(light.falloff(Point::new(3., 0., 0.)), 1.));
+        assert!(float_eq(light.falloff(Point::new(10.1, 0., 0.)), 0.));
+        assert!(float_eq(light.falloff(Point::new(0., 20., 0.)), 0.));
+        // half way between the inner and outer cosines
+        let cos = ((PI / 8.).cos() + (PI / 4.).cos()) / 2.;
+        let x = 10. * (1. - cos * cos).sqrt() / cos;
+        assert!(float_eq(light.falloff(Point::new(x, 0., 0.)), 0.5));
+    }
+
+    #[test]
+    fn falloff_monotonic() {
+        let light = light();
+        let values: Vec<f64> = (0..=100)
+            .map(|i| light.falloff(Point::new(i as f64 * 0.1, 0., 0.)))
+            .collect();
+        assert!(values.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn samples() {
+        let light = light();
+        let samples = light.samples(Point::new(0., 0., 0.));
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].direction, Vector::new(0., 1., 0.));
+        assert!(float_eq(samples[0].distance, 10.));
+        assert_eq!(samples[0].intensity, Color::white());
+        let samples = light.samples(Point::new(20., 0., 0.));
+        assert_eq!(samples[0].intensity, Color::black());
+    }
+
+    #[test]
+    fn samples_attenuated() {
+        let mut light = light();
+        light.attenuation = Attenuation::inverse_square();
+        let samples = light.samples(Point::new(0., 0., 0.));
+        assert_eq!(samples[0].intensity, Color::new(0.01, 0.01, 0.01));
+    }
+
+    #[test]
+    fn from_power() {
+        let light = SpotLight::from_power(
+            Point::new(0., 0., 0.),
+            Vector::new(0., 0., 1.),
+            PI / 2.,
+            PI / 2.,
+            Color::white(),
+            2. * PI,
+        );
+        // cone of half angle π/2 is the hemisphere (2π steradians)
+        assert_eq!(light.intensity, Color::white());
+        assert_eq!(light.attenuation, Attenuation::inverse_square());
+    }
+}
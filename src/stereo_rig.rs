@@ -0,0 +1,260 @@
+use crate::{Camera, Canvas, Color, Matrix};
+
+/// Pair of cameras seeing the world from the left and right eyes of a
+/// viewer.
+///
+/// The eyes are placed on both sides of a central camera, separated by the
+/// interocular distance along its horizontal axis, and turned inwards so that
+/// their view axes cross at the convergence distance in front of the central
+/// camera. Shapes at the convergence distance appear on the plane of the
+/// screen, nearer shapes in front of it and farther ones behind it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StereoRig {
+    camera: Camera,
+    interocular_distance: f64,
+    convergence_distance: f64,
+}
+
+impl StereoRig {
+    /// Creates a `StereoRig` around a central camera with specified
+    /// interocular and convergence distances (`f64::INFINITY` for parallel
+    /// eyes).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the convergence distance is not positive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, Point, StereoRig};
+    /// use std::f64::consts::PI;
+    /// let rig = StereoRig::new(Camera::new(100, 100, PI / 3.), 0.2, 5.);
+    /// assert_eq!(rig.left().origin(), Point::new(0.1, 0., 0.));
+    /// assert_eq!(rig.right().origin(), Point::new(-0.1, 0., 0.));
+    /// // the center of both canvases sees the same point
+    /// let left = rig.left().ray_for_point(50., 50.);
+    /// let right = rig.right().ray_for_point(50., 50.);
+    /// let distance = (Point::new(0., 0., -5.) - left.origin).len();
+    /// assert_eq!(left.position(distance), right.position(distance));
+    /// ```
+    pub fn new(camera: Camera, interocular_distance: f64, convergence_distance: f64) -> Self {
+        let mut rig = Self {
+            camera,
+            interocular_distance,
+            convergence_distance: 1.,
+        };
+        rig.set_convergence_distance(convergence_distance);
+        rig
+    }
+
+    /// Get the central camera.
+    #[must_use]
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    /// Sets the central camera.
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = camera;
+    }
+
+    /// Get the distance between the eyes.
+    #[must_use]
+    pub fn interocular_distance(&self) -> f64 {
+        self.interocular_distance
+    }
+
+    /// Sets the distance between the eyes.
+    pub fn set_interocular_distance(&mut self, interocular_distance: f64) {
+        self.interocular_distance = interocular_distance;
+    }
+
+    /// Get the distance from the central camera at which the view axes of
+    /// the eyes cross.
+    #[must_use]
+    pub fn convergence_distance(&self) -> f64 {
+        self.convergence_distance
+    }
+
+    /// Sets the distance from the central camera at which the view axes of
+    /// the eyes cross (`f64::INFINITY` for parallel eyes).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the distance is not positive.
+    pub fn set_convergence_distance(&mut self, convergence_distance: f64) {
+        assert!(convergence_distance > 0., "convergence distance must be positive");
+        self.convergence_distance = convergence_distance;
+    }
+
+    /// Returns the camera of the left eye.
+    pub fn left(&self) -> Camera {
+        self.eye(self.interocular_distance / 2.)
+    }
+
+    /// Returns the camera of the right eye.
+    pub fn right(&self) -> Camera {
+        self.eye(-self.interocular_distance / 2.)
+    }
+
+    /// Returns the camera of the eye at specified offset along the `x` axis
+    /// of the central camera (towards the left of its canvas).
+    fn eye(&self, offset: f64) -> Camera {
+        let angle = -(offset / self.convergence_distance).atan();
+        let eye = Matrix::rotation_y(angle) * Matrix::translation(-offset, 0., 0.);
+        let mut camera = self.camera;
+        camera.set_transform(eye * *self.camera.transform());
+        camera
+    }
+
+    /// Renders the views of both eyes with a rendering function (such as
+    /// [`Camera::render`] or the `render` method of an integrator bound to a
+    /// world), returning the left and right canvases.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Camera, PathTracer, StereoRig, World};
+    /// let world = World::new();
+    /// let rig = StereoRig::new(Camera::new(4, 2, 1.), 0.1, 3.);
+    /// let (left, right) = rig.render(|camera| PathTracer::new().render(&world, camera));
+    /// assert_eq!(StereoRig::side_by_side(&left, &right).width(), 8);
+    /// ```
+    pub fn render(&self, render: impl Fn(&Camera) -> Canvas) -> (Canvas, Canvas) {
+        (render(&self.left()), render(&self.right()))
+    }
+
+    /// Returns the canvas showing the left view on its left half and the
+    /// right view on its right half.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the views have different sizes.
+    pub fn side_by_side(left: &Canvas, right: &Canvas) -> Canvas {
+        assert_same_size(left, right);
+        let width = left.width();
+        let mut canvas = Canvas::new(width * 2, left.height());
+        for y in 0..left.height() {
+            for x in 0..width {
+                *canvas.get_mut(x, y) = *left.get(x, y);
+                *canvas.get_mut(x + width, y) = *right.get(x, y);
+            }
+        }
+        canvas
+    }
+
+    /// Returns the red-cyan anaglyph of the views: the red channel of the
+    /// left view, the green and blue channels of the right view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the views have different sizes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Canvas, Color, StereoRig};
+    /// let left = Canvas::with_color(2, 2, Color::new(0.1, 0.2, 0.3));
+    /// let right = Canvas::with_color(2, 2, Color::new(0.4, 0.5, 0.6));
+    /// let anaglyph = StereoRig::anaglyph(&left, &right);
+    /// assert_eq!(anaglyph.get(1, 1), &Color::new(0.1, 0.5, 0.6));
+    /// ```
+    pub fn anaglyph(left: &Canvas, right: &Canvas) -> Canvas {
+        assert_same_size(left, right);
+        let mut canvas = Canvas::new(left.width(), left.height());
+        for y in 0..left.height() {
+            for x in 0..left.width() {
+                let (l, r) = (left.get(x, y), right.get(x, y));
+                *canvas.get_mut(x, y) = Color::new(l.r, r.g, r.b);
+            }
+        }
+        canvas
+    }
+}
+
+fn assert_same_size(left: &Canvas, right: &Canvas) {
+    assert!(
+        left.width() == right.width() && left.height() == right.height(),
+        "stereo views must have the same size"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{util::float_eq, Point, Vector};
+
+    use super::*;
+
+    fn rig() -> StereoRig {
+        let mut camera = Camera::new(101, 51, PI / 3.);
+        let from = Point::new(1., 2., 3.);
+        let to = Point::new(1., 2., -7.);
+        camera.set_transform(Matrix::view_transform(from, to, Vector::new(0., 1., 0.)));
+        StereoRig::new(camera, 0.5, 4.)
+    }
+
+    #[test]
+    fn eyes() {
+        let rig = rig();
+        assert!(float_eq(rig.interocular_distance(), 0.5));
+        assert!(float_eq(rig.convergence_distance(), 4.));
+        let (left, right) = (rig.left(), rig.right());
+        assert!(float_eq((left.origin() - right.origin()).len(), 0.5));
+        assert_eq!(
+            Point::new(
+                (left.origin().x + right.origin().x) / 2.,
+                (left.origin().y + right.origin().y) / 2.,
+                (left.origin().z + right.origin().z) / 2.
+            ),
+            rig.camera().origin()
+        );
+        // the left eye sees from the left of the canvas
+        let camera = rig.camera();
+        let leftward = camera.ray_for_point(0., 25.5).direction;
+        assert!((left.origin() - camera.origin()).dot(leftward) > 0.);
+        // view axes cross at the convergence distance
+        let focus = Point::new(1., 2., -1.);
+        let (x, y) = left.project(focus).unwrap();
+        assert!(float_eq(x, 50.5) && float_eq(y, 25.5));
+        let (x, y) = right.project(focus).unwrap();
+        assert!(float_eq(x, 50.5) && float_eq(y, 25.5));
+        // nearer points are seen more to the right by the left eye
+        let (left_x, _) = left.project(Point::new(1., 2., 0.)).unwrap();
+        let (right_x, _) = right.project(Point::new(1., 2., 0.)).unwrap();
+        assert!(left_x > right_x);
+    }
+
+    #[test]
+    fn parallel() {
+        let mut rig = rig();
+        rig.set_convergence_distance(f64::INFINITY);
+        rig.set_interocular_distance(0.2);
+        let (left, right) = (rig.left(), rig.right());
+        let direction = rig.camera().ray_for_point(50.5, 25.5).direction;
+        assert_eq!(left.ray_for_point(50.5, 25.5).direction, direction);
+        assert_eq!(right.ray_for_point(50.5, 25.5).direction, direction);
+        assert!(float_eq((left.origin() - right.origin()).len(), 0.2));
+    }
+
+    #[test]
+    fn compositing() {
+        let left = Canvas::with_color(3, 2, Color::new(1., 0., 0.5));
+        let right = Canvas::with_color(3, 2, Color::new(0., 1., 0.25));
+        let both = StereoRig::side_by_side(&left, &right);
+        assert_eq!((both.width(), both.height()), (6, 2));
+        assert_eq!(both.get(2, 1), left.get(2, 1));
+        assert_eq!(both.get(3, 0), right.get(0, 0));
+        let anaglyph = StereoRig::anaglyph(&left, &right);
+        assert_eq!((anaglyph.width(), anaglyph.height()), (3, 2));
+        assert_eq!(anaglyph.get(0, 1), &Color::new(1., 1., 0.25));
+    }
+
+    #[test]
+    #[should_panic(expected = "stereo views must have the same size")]
+    fn mismatched_views() {
+        StereoRig::anaglyph(&Canvas::new(2, 2), &Canvas::new(3, 2));
+    }
+}
@@ -1,7 +1,73 @@
-const EPSILON: f64 = 1.0e-5;
+use std::f64::consts::PI;
+
+use crate::Vector;
+
+pub const EPSILON: f64 = 1.0e-5;
 
 pub fn float_eq(left: f64, right: f64) -> bool {
-    (left - right).abs() <= EPSILON
+    // equal infinities have no finite difference
+    left == right || (left - right).abs() <= EPSILON
+}
+
+/// Pseudo random number generator (SplitMix64).
+///
+/// Not suitable for cryptography, only used for sampling.
+#[derive(Copy, Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Creates a generator seeded by the bits of specified values.
+    pub fn from_values(values: &[f64]) -> Self {
+        let mut rng = Self(0);
+        for value in values {
+            rng.0 ^= value.to_bits();
+            rng.next_u64();
+        }
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed value in `[0,1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Returns two unit vectors forming with `normal` an orthonormal basis.
+pub fn orthonormal_basis(normal: Vector) -> (Vector, Vector) {
+    // Duff et al., building an orthonormal basis, revisited
+    let sign = 1f64.copysign(normal.z);
+    let a = -1. / (sign + normal.z);
+    let b = normal.x * normal.y * a;
+    let tangent = Vector::new(1. + sign * normal.x * normal.x * a, sign * b, -sign * normal.x);
+    let bitangent = Vector::new(b, sign + normal.y * normal.y * a, -normal.y);
+    (tangent, bitangent)
+}
+
+/// Returns a direction of the hemisphere around `normal` distributed
+/// proportionally to its cosine with `normal` (density `cos θ / π`) from two
+/// uniform values in `[0,1)`.
+pub fn cosine_hemisphere(normal: Vector, u1: f64, u2: f64) -> Vector {
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    let r = u1.sqrt();
+    let phi = 2. * PI * u2;
+    let z = (1. - u1).max(0.).sqrt();
+    (tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + normal * z).normalized()
+}
+
+/// Returns a point of the unit disk uniformly distributed from two uniform
+/// values in `[0,1)`.
+pub fn uniform_disk(u1: f64, u2: f64) -> (f64, f64) {
+    let r = u1.sqrt();
+    let phi = 2. * PI * u2;
+    (r * phi.cos(), r * phi.sin())
 }
 
 #[cfg(test)]
@@ -17,4 +83,73 @@ mod tests {
     fn float_eq_fail() {
         assert!(!float_eq(1.0_f64, 1.000_02));
     }
+
+    #[test]
+    fn float_eq_infinite() {
+        assert!(float_eq(f64::INFINITY, f64::INFINITY));
+        assert!(!float_eq(f64::INFINITY, f64::NEG_INFINITY));
+        assert!(!float_eq(f64::NAN, f64::NAN));
+    }
+
+    #[test]
+    fn rng_deterministic() {
+        let mut a = Rng(42);
+        let mut b = Rng(42);
+        assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+        let mut a = Rng::from_values(&[1., 2.]);
+        let mut b = Rng::from_values(&[1., 2.]);
+        let mut c = Rng::from_values(&[2., 1.]);
+        assert_eq!(a.next_u64(), b.next_u64());
+        assert_ne!(a.next_u64(), c.next_u64());
+    }
+
+    #[test]
+    fn rng_range() {
+        let mut rng = Rng(7);
+        let values: Vec<f64> = (0..10_000).map(|_| rng.next_f64()).collect();
+        assert!(values.iter().all(|v| (0. ..1.).contains(v)));
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        assert!((mean - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn orthonormal_basis() {
+        for normal in [
+            Vector::new(0., 0., 1.),
+            Vector::new(0., 0., -1.),
+            Vector::new(1., 2., 3.).normalized(),
+        ] {
+            let (tangent, bitangent) = super::orthonormal_basis(normal);
+            assert!(float_eq(tangent.len(), 1.));
+            assert!(float_eq(bitangent.len(), 1.));
+            assert!(float_eq(tangent.dot(normal), 0.));
+            assert!(float_eq(bitangent.dot(normal), 0.));
+            assert!(float_eq(tangent.dot(bitangent), 0.));
+        }
+    }
+
+    #[test]
+    fn cosine_hemisphere() {
+        let normal = Vector::new(0., 1., 0.);
+        let mut rng = Rng(3);
+        let directions: Vec<Vector> = (0..10_000)
+            .map(|_| super::cosine_hemisphere(normal, rng.next_f64(), rng.next_f64()))
+            .collect();
+        assert!(directions.iter().all(|d| d.dot(normal) >= 0.));
+        // E[cos θ] = 2/3 for a cosine distribution
+        let mean = directions.iter().map(|d| d.dot(normal)).sum::<f64>() / directions.len() as f64;
+        assert!((mean - 2. / 3.).abs() < 0.01);
+    }
+
+    #[test]
+    fn uniform_disk() {
+        let mut rng = Rng(5);
+        let points: Vec<(f64, f64)> = (0..10_000)
+            .map(|_| super::uniform_disk(rng.next_f64(), rng.next_f64()))
+            .collect();
+        assert!(points.iter().all(|(x, y)| x * x + y * y <= 1.));
+        // E[r] = 2/3 for a uniform disk
+        let mean = points.iter().map(|(x, y)| x.hypot(*y)).sum::<f64>() / points.len() as f64;
+        assert!((mean - 2. / 3.).abs() < 0.01);
+    }
 }
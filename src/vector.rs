@@ -117,6 +117,20 @@ impl Vector {
             z: self.x * rhs.y - self.y * rhs.x,
         }
     }
+
+    /// Returns the vector reflected around specified normal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::Vector;
+    /// let vector = Vector::new(1., -1., 0.);
+    /// let normal = Vector::new(0., 1., 0.);
+    /// assert_eq!(vector.reflect(normal), Vector::new(1., 1., 0.));
+    /// ```
+    pub fn reflect(&self, normal: Vector) -> Vector {
+        *self - normal * 2. * self.dot(normal)
+    }
 }
 
 impl Display for Vector {
@@ -407,4 +421,14 @@ mod tests {
         assert_eq!(y.cross(z), x);
         assert_eq!(z.cross(x), y);
     }
+
+    #[test]
+    fn reflect() {
+        let vector = Vector::new(1., -1., 0.);
+        let normal = Vector::new(0., 1., 0.);
+        assert_eq!(vector.reflect(normal), Vector::new(1., 1., 0.));
+        let vector = Vector::new(0., -1., 0.);
+        let normal = Vector::new(2f64.sqrt() / 2., 2f64.sqrt() / 2., 0.);
+        assert_eq!(vector.reflect(normal), Vector::new(1., 0., 0.));
+    }
 }
@@ -0,0 +1,495 @@
+use crate::{util::Rng, Color, Computations, Environment, Intersection, Light, Medium, Point, Ray, Shape, Vector};
+
+/// Collection of shapes and lights to render.
+#[derive(Debug, Default)]
+pub struct World {
+    objects: Vec<Box<dyn Shape>>,
+    lights: Vec<Box<dyn Light>>,
+    environment: Option<Environment>,
+    medium: Option<Medium>,
+}
+
+impl World {
+    /// Creates an empty `World`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::World;
+    /// let world = World::new();
+    /// assert!(world.objects().is_empty());
+    /// assert!(world.lights().is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a shape to the world.
+    pub fn add_object(&mut self, object: impl Shape + 'static) {
+        self.objects.push(Box::new(object));
+    }
+
+    /// Adds a light to the world.
+    pub fn add_light(&mut self, light: impl Light + 'static) {
+        self.lights.push(Box::new(light));
+    }
+
+    /// Get the shapes of the world.
+    #[must_use]
+    pub fn objects(&self) -> &[Box<dyn Shape>] {
+        &self.objects
+    }
+
+    /// Get the lights of the world.
+    #[must_use]
+    pub fn lights(&self) -> &[Box<dyn Light>] {
+        &self.lights
+    }
+
+    /// Get the environment surrounding the world.
+    #[must_use]
+    pub fn environment(&self) -> Option<&Environment> {
+        self.environment.as_ref()
+    }
+
+    /// Sets the environment surrounding the world.
+    ///
+    /// The environment is seen by rays escaping the world and lights the
+    /// world in addition to its lights.
+    pub fn set_environment(&mut self, environment: Environment) {
+        self.environment = Some(environment);
+    }
+
+    /// Get the participating medium filling the space between the shapes.
+    #[must_use]
+    pub fn medium(&self) -> Option<&Medium> {
+        self.medium.as_ref()
+    }
+
+    /// Sets the participating medium filling the space between the shapes
+    /// (fog), seen by the path tracer.
+    ///
+    /// Rays leaving a shape filled with a medium (see [`Material::medium`])
+    /// travel through this medium again, media bounded by shapes are not
+    /// expected to overlap.
+    ///
+    /// [`Material::medium`]: crate::Material::medium
+    pub fn set_medium(&mut self, medium: Medium) {
+        self.medium = Some(medium);
+    }
+
+    /// Returns the intersections of the ray with the shapes of the world
+    /// sorted by increasing distance.
+    pub fn intersect(&self, ray: &Ray) -> Vec<Intersection<'_>> {
+        let mut intersections: Vec<_> = self.objects.iter().flat_map(|object| object.intersect(ray)).collect();
+        intersections.sort_by(|a, b| a.t.total_cmp(&b.t));
+        intersections
+    }
+
+    /// Returns the color of the intersection described by specified
+    /// computations: the light emitted by the surface plus the light it
+    /// reflects from every light of the world.
+    pub fn shade_hit(&self, comps: &Computations) -> Color {
+        let material = comps.object.material();
+        self.light_sources().fold(material.emission, |color, light| {
+            let intensity = self.intensity_at(light, comps.over_point);
+            color + material.lighting(light, comps.over_point, comps.eyev, comps.normalv, intensity)
+        })
+    }
+
+    /// Returns the color seen along specified ray.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ray_tracer_challenge::{Color, Point, Ray, Vector, World};
+    /// let world = World::new();
+    /// let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 1., 0.));
+    /// assert_eq!(world.color_at(&ray), Color::black());
+    /// ```
+    pub fn color_at(&self, ray: &Ray) -> Color {
+        let intersections = self.intersect(ray);
+        match Intersection::hit(&intersections) {
+            Some(hit) => self.shade_hit(&Computations::new(&hit, ray)),
+            None => self.background(ray.direction),
+        }
+    }
+
+    /// Returns the color of the environment in specified direction (black
+    /// without environment).
+    pub fn background(&self, direction: Vector) -> Color {
+        match &self.environment {
+            Some(environment) => environment.radiance(direction),
+            None => Color::black(),
+        }
+    }
+
+    /// Returns the lights of the world followed by the environment.
+    pub(crate) fn light_sources(&self) -> impl Iterator<Item = &dyn Light> {
+        let environment = self.environment.iter().map(|environment| environment as &dyn Light);
+        self.lights.iter().map(|light| light.as_ref()).chain(environment)
+    }
+
+    /// Returns `true` if a shape lies between specified point and a light at
+    /// specified direction and distance from it.
+    pub fn is_shadowed(&self, point: Point, direction: Vector, distance: f64) -> bool {
+        self.is_occluded(&Ray::new(point, direction), distance)
+    }
+
+    /// Returns `true` if a shape lies along a ray before specified distance.
+    pub(crate) fn is_occluded(&self, ray: &Ray, distance: f64) -> bool {
+        let intersections = self.intersect(ray);
+        matches!(Intersection::hit(&intersections), Some(hit) if hit.t < distance)
+    }
+
+    /// Returns the fraction of the light traveling along a ray over
+    /// specified distance without being absorbed or scattered, starting in
+    /// specified medium.
+    ///
+    /// The light crosses the boundaries of the shapes filled with a medium,
+    /// other shapes block it entirely.
+    pub(crate) fn transmittance(&self, ray: &Ray, distance: f64, medium: Option<&Medium>, rng: &mut Rng) -> Color {
+        let mut transmittance = Color::white();
+        let mut ray = *ray;
+        let mut medium = medium;
+        let mut distance = distance;
+        loop {
+            let intersections = self.intersect(&ray);
+            let hit = Intersection::hit(&intersections).filter(|hit| hit.t < distance);
+            if let Some(medium) = medium {
+                transmittance *= medium.transmittance_along(&ray, hit.map_or(distance, |hit| hit.t), rng);
+            }
+            let hit = match hit {
+                Some(hit) => hit,
+                None => return transmittance,
+            };
+            let comps = Computations::new(&hit, &ray);
+            medium = match &comps.object.material().medium {
+                Some(_) if comps.inside => self.medium(),
+                Some(inner) => Some(inner),
+                None => return Color::black(),
+            };
+            ray = Ray::new(comps.under_point, ray.direction).with_time(ray.time);
+            distance -= hit.t;
+        }
+    }
+
+    /// Returns the fraction (in `[0,1]`) of the samples of specified light
+    /// visible from specified point.
+    pub fn intensity_at(&self, light: &dyn Light, point: Point) -> f64 {
+        let samples = light.samples(point);
+        if samples.is_empty() {
+            return 0.;
+        }
+        let visible = samples
+            .iter()
+            .filter(|sample| !self.is_shadowed(point, sample.direction, sample.distance))
+            .count();
+        visible as f64 / samples.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::{
+        util::float_eq, AreaLight, Canvas, DirectionalLight, Material, Matrix, PointLight, Quadric, SpotLight,
+    };
+
+    use super::*;
+
+    fn default_world() -> World {
+        let mut world = World::new();
+        world.add_light(PointLight::new(Point::new(-10., 10., -10.), Color::white()));
+        let mut s1 = Quadric::sphere();
+        s1.set_material(Material {
+            color: Color::new(0.8, 1.0, 0.6),
+            diffuse: 0.7,
+            specular: 0.2,
+            ..Material::default()
+        });
+        world.add_object(s1);
+        let mut s2 = Quadric::sphere();
+        s2.set_transform(Matrix::scaling(0.5, 0.5, 0.5));
+        world.add_object(s2);
+        world
+    }
+
+    #[test]
+    fn intersect() {
+        let world = default_world();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = world.intersect(&ray);
+        assert_eq!(xs.len(), 4);
+        assert!(float_eq(xs[0].t, 4.));
+        assert!(float_eq(xs[1].t, 4.5));
+        assert!(float_eq(xs[2].t, 5.5));
+        assert!(float_eq(xs[3].t, 6.));
+    }
+
+    #[test]
+    fn shade_hit() {
+        let world = default_world();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = world.objects()[0].intersect(&ray);
+        let comps = Computations::new(&xs[0], &ray);
+        assert_eq!(world.shade_hit(&comps), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn shade_hit_inside() {
+        let mut world = default_world();
+        world.lights.clear();
+        world.add_light(PointLight::new(Point::new(0., 0.25, 0.), Color::white()));
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., 1.));
+        let xs = world.objects()[1].intersect(&ray);
+        let comps = Computations::new(&xs[1], &ray);
+        assert_eq!(world.shade_hit(&comps), Color::new(0.90498, 0.90498, 0.90498));
+    }
+
+    #[test]
+    fn shade_hit_shadow() {
+        let mut world = World::new();
+        world.add_light(PointLight::new(Point::new(0., 0., -10.), Color::white()));
+        world.add_object(Quadric::sphere());
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::translation(0., 0., 10.));
+        world.add_object(sphere);
+        let ray = Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.));
+        let xs = world.objects()[1].intersect(&ray);
+        let comps = Computations::new(&xs[0], &ray);
+        assert_eq!(world.shade_hit(&comps), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn color_at_miss() {
+        let world = default_world();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 1., 0.));
+        assert_eq!(world.color_at(&ray), Color::black());
+    }
+
+    #[test]
+    fn color_at_hit() {
+        let world = default_world();
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        assert_eq!(world.color_at(&ray), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn is_shadowed() {
+        let world = default_world();
+        let light = Point::new(-10., 10., -10.);
+        let tests = [
+            (Point::new(0., 10., 0.), false),
+            (Point::new(10., -10., 10.), true),
+            (Point::new(-20., 20., -20.), false),
+            (Point::new(-2., 2., -2.), false),
+        ];
+        for (point, expected) in tests {
+            let vector = light - point;
+            let shadowed = world.is_shadowed(point, vector.normalized(), vector.len());
+            assert_eq!(shadowed, expected);
+        }
+    }
+
+    #[test]
+    fn intensity_at_point_light() {
+        let world = default_world();
+        let light = world.lights()[0].as_ref();
+        let tests = [
+            (Point::new(0., 1.0001, 0.), 1.),
+            (Point::new(-1.0001, 0., 0.), 1.),
+            (Point::new(0., 0., -1.0001), 1.),
+            (Point::new(0., 0., 1.0001), 0.),
+            (Point::new(1.0001, 0., 0.), 0.),
+            (Point::new(0., -1.0001, 0.), 0.),
+            (Point::new(0., 0., 0.), 0.),
+        ];
+        for (point, expected) in tests {
+            assert!(float_eq(world.intensity_at(light, point), expected));
+        }
+    }
+
+    #[test]
+    fn intensity_at_area_light() {
+        let world = default_world();
+        let mut light = AreaLight::new(
+            Point::new(-0.5, -0.5, -5.),
+            Vector::new(1., 0., 0.),
+            2,
+            Vector::new(0., 1., 0.),
+            2,
+            Color::white(),
+        );
+        light.set_jitter(false);
+        let tests = [
+            (Point::new(0., 0., 2.), 0.),
+            (Point::new(1., -1., 2.), 0.25),
+            (Point::new(1.5, 0., 2.), 0.5),
+            (Point::new(1.25, 1.25, 3.), 0.75),
+            (Point::new(0., 0., -2.), 1.),
+        ];
+        for (point, expected) in tests {
+            assert!(float_eq(world.intensity_at(&light, point), expected));
+        }
+    }
+
+    #[test]
+    fn soft_shadow() {
+        let world = default_world();
+        let light = AreaLight::new(
+            Point::new(-0.5, -0.5, -5.),
+            Vector::new(1., 0., 0.),
+            8,
+            Vector::new(0., 1., 0.),
+            8,
+            Color::white(),
+        );
+        // penumbra behind the sphere edge is partially lit
+        let intensity = world.intensity_at(&light, Point::new(1.5, 0., 2.));
+        assert!(intensity > 0. && intensity < 1.);
+        assert!(float_eq(world.intensity_at(&light, Point::new(0., 0., 2.)), 0.));
+        assert!(float_eq(world.intensity_at(&light, Point::new(0., 0., -2.)), 1.));
+    }
+
+    #[test]
+    fn shade_hit_directional_light() {
+        let mut world = World::new();
+        world.add_light(DirectionalLight::new(Vector::new(0., 0., 1.), Color::white()));
+        world.add_object(Quadric::sphere());
+        let mut sphere = Quadric::sphere();
+        sphere.set_transform(Matrix::translation(0., 0., 10.));
+        world.add_object(sphere);
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        let xs = world.objects()[0].intersect(&ray);
+        let comps = Computations::new(&xs[0], &ray);
+        assert_eq!(world.shade_hit(&comps), Color::new(1.9, 1.9, 1.9));
+        let ray = Ray::new(Point::new(0., 0., 5.), Vector::new(0., 0., 1.));
+        let xs = world.objects()[1].intersect(&ray);
+        let comps = Computations::new(&xs[0], &ray);
+        assert_eq!(world.shade_hit(&comps), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn shade_hit_spot_light() {
+        let mut world = World::new();
+        world.add_light(SpotLight::new(
+            Point::new(0., 0., -10.),
+            Vector::new(0., 0., 1.),
+            PI / 32.,
+            PI / 16.,
+            Color::white(),
+        ));
+        world.add_object(Quadric::plane());
+        let mut plane = Quadric::plane();
+        plane.set_transform(Matrix::rotation_x(-PI / 2.));
+        world.add_object(plane);
+        let lit = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        assert_eq!(world.color_at(&lit), Color::new(1.9, 1.9, 1.9));
+        let dark = Ray::new(Point::new(5., 1., -5.), Vector::new(0., 0., 1.));
+        assert_eq!(world.color_at(&dark), Color::black());
+    }
+
+    #[test]
+    fn color_at_environment() {
+        let mut world = default_world();
+        let mut map = Canvas::with_color(8, 4, Color::blue());
+        for x in 0..8 {
+            *map.get_mut(x, 0) = Color::red();
+        }
+        world.set_environment(Environment::new(map));
+        let up = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 1., 0.));
+        assert_eq!(world.color_at(&up), Color::red());
+        let down = Ray::new(Point::new(0., 0., -5.), Vector::new(0., -1., 0.));
+        assert_eq!(world.color_at(&down), Color::blue());
+    }
+
+    #[test]
+    fn shade_hit_environment() {
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(Material {
+            ambient: 0.,
+            diffuse: 1.,
+            specular: 0.,
+            ..Material::default()
+        });
+        world.add_object(sphere);
+        // uniform white sky and black ground
+        let mut map = Canvas::new(64, 32);
+        for y in 0..16 {
+            for x in 0..64 {
+                *map.get_mut(x, y) = Color::white();
+            }
+        }
+        let mut environment = Environment::new(map);
+        environment.set_sample_count(1024);
+        world.set_environment(environment);
+        let top = Ray::new(Point::new(0., 5., 0.), Vector::new(0., -1., 0.));
+        let bottom = Ray::new(Point::new(0., -5., 0.), Vector::new(0., 1., 0.));
+        let top = world.color_at(&top);
+        let bottom = world.color_at(&bottom);
+        // irradiance from the upper hemisphere of radiance 1 is π
+        assert!((top.r - PI).abs() < 0.2);
+        assert!(bottom.r < 0.1);
+    }
+
+    #[test]
+    fn shade_hit_emission() {
+        let mut world = World::new();
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(Material {
+            emission: Color::new(2., 1., 0.5),
+            ..Material::default()
+        });
+        world.add_object(sphere);
+        // emitted light is seen without any light in the world
+        let ray = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.));
+        assert_eq!(world.color_at(&ray), Color::new(2., 1., 0.5));
+        world.add_light(PointLight::new(Point::new(-10., 10., -10.), Color::white()));
+        let xs = world.objects()[0].intersect(&ray);
+        let comps = Computations::new(&xs[0], &ray);
+        let mut lit = world.shade_hit(&comps);
+        lit -= Color::new(2., 1., 0.5);
+        assert_eq!(lit, Color::new(0.58321, 0.58321, 0.58321));
+    }
+
+    #[test]
+    fn transmittance() {
+        let mut world = World::new();
+        let fog = Medium::new(Color::new(0.1, 0.2, 0.), Color::new(0., 0., 0.1), 0.);
+        let smoke = Medium::new(Color::white(), Color::black(), 0.);
+        let mut sphere = Quadric::sphere();
+        sphere.set_material(Material {
+            medium: Some(smoke.clone()),
+            ..Material::default()
+        });
+        world.add_object(sphere);
+        let mut wall = Quadric::plane();
+        wall.set_transform(Matrix::translation(0., 0., 5.) * Matrix::rotation_x(PI / 2.));
+        world.add_object(wall);
+        let mut rng = Rng::from_values(&[0.]);
+        let point = Point::new(0., 0., -5.);
+        let direction = Vector::new(0., 0., 1.);
+        let inside = (-2f64).exp();
+        let mut transmittance = |world: &World, point, distance, medium| {
+            world.transmittance(&Ray::new(point, direction), distance, medium, &mut rng)
+        };
+        assert_eq!(transmittance(&world, point, 4., None), Color::white());
+        assert_eq!(
+            transmittance(&world, point, 9., None),
+            Color::new(inside, inside, inside)
+        );
+        assert_eq!(transmittance(&world, point, 20., None), Color::black());
+        // fog between the shapes
+        world.set_medium(fog.clone());
+        assert_eq!(world.medium(), Some(&fog));
+        let expected = fog.transmittance(7.99) * smoke.transmittance(2.);
+        assert_eq!(transmittance(&world, point, 9.99, world.medium()), expected);
+        // starting inside the smoke
+        let center = Point::new(0., 0., 0.);
+        let expected = smoke.transmittance(1.) * fog.transmittance(2.);
+        assert_eq!(transmittance(&world, center, 3., Some(&smoke)), expected);
+    }
+}